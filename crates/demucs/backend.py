@@ -9,20 +9,25 @@ import sys
 import demucs.api
 import torch
 
-def version(_payload):
+def version(_payload, _progress):
     device = "cuda" if torch.cuda.is_available() else "cpu"
     return {
         "version": "0.1.0",
         "torch_device": device,
     }
     
-def separate(payload):
+def separate(payload, progress):
     input_file = payload["input_file"]
     output_dir = payload["output_dir"]
     
     log(f"Begin separation of {input_file} to {output_dir}")
     
-    separator = demucs.api.Separator(model="htdemucs", segment=4, overlap=0.1, )
+    def on_segment(data):
+        if data["state"] == "end":
+            done = data["model_idx_in_bag"] + data["segment_offset"] / data["audio_length"]
+            progress(min(done / data["models"], 1.0))
+    
+    separator = demucs.api.Separator(model="htdemucs", segment=4, overlap=0.1, callback=on_segment)
     origin, separated = separator.separate_audio_file(input_file)
     
     stems = []
@@ -60,7 +65,9 @@ def require_environ(name):
 def handle(message):
     if message['id'] == "Call":
         try:
-            payload = remote_procedure[message['procedure_id']](message['payload'])
+            call_id = message['call_id']
+            progress = lambda value: send({"id": "Progress", "call_id": call_id, "progress": value})
+            payload = remote_procedure[message['procedure_id']](message['payload'], progress)
             send({"id": "CallBack", "call_id": message['call_id'], "payload": payload})
         except Exception as e:
             log(f"remote procedure error: {e}")
@@ -7,7 +7,7 @@ use tokio::{
     io::AsyncWriteExt,
     net::TcpListener,
     sync::{
-        mpsc::{Receiver, Sender},
+        mpsc::{Receiver, Sender, UnboundedSender},
         OwnedRwLockWriteGuard,
     },
     task::JoinHandle,
@@ -35,6 +35,11 @@ pub enum FromProcessRequest {
         call_id: usize,
         payload: serde_json::Value,
     },
+    /// Progress of a running call, from 0 to 1
+    Progress {
+        call_id: usize,
+        progress: f64,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +60,7 @@ pub struct MarshalledCall {
     /// Maintain the caller idle until the call is resolved
     lock: OwnedRwLockWriteGuard<serde_json::Value>,
     call_id: usize,
+    progress: Option<UnboundedSender<f64>>,
 }
 
 #[derive(Debug, Clone)]
@@ -70,6 +76,7 @@ impl Backend {
     pub async fn new(
         environment: Arc<RwLock<Environment>>,
         backend_script: PathBuf,
+        kill: Arc<tokio::sync::Notify>,
     ) -> Result<(Self, Receiver<FromBackendRequest>)> {
         let (to_handler, from_backend) = tokio::sync::mpsc::channel(1024);
         let server: RemoteProcessServer = RemoteProcessServer::new(to_handler.clone()).await?;
@@ -78,6 +85,7 @@ impl Backend {
         let proxy_send = server.proxy_send.clone();
         let _ipc_server_handle = tokio::spawn(async move { server.handle_connection().await });
         let to_handler_clone = to_handler.clone();
+        let marshalled_clone = marshalled.clone();
         let _python_process_handle: JoinHandle<Result<()>> = tokio::spawn(async move {
             let mut cmd = environment
                 .read()
@@ -85,8 +93,19 @@ impl Backend {
                 .run_script_within_env(ENVIRONMENT_NAME, backend_script.to_str().unwrap())
                 .await?;
             cmd.env("PORT", &port);
-            cmd.is_success().await?;
-            info!("Python backend dropped");
+            tokio::select! {
+                result = cmd.is_success() => {
+                    if let Err(e) = result {
+                        error!("Python backend failed: {:?}", e);
+                    }
+                    info!("Python backend dropped");
+                }
+                _ = kill.notified() => {
+                    warn!("Python backend killed");
+                }
+            }
+            // Release the pending calls, they resolve to null
+            marshalled_clone.write().await.clear();
             to_handler_clone.send(FromBackendRequest::PythonBackendDroped).await?;
             Ok(())
         });
@@ -101,14 +120,27 @@ impl Backend {
         &self,
         procedure_id: &'static str,
         payload: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.remote_call_with_progress(procedure_id, payload, None).await
+    }
+
+    /// The progress reported by the procedure is sent to `progress`
+    pub async fn remote_call_with_progress(
+        &self,
+        procedure_id: &'static str,
+        payload: serde_json::Value,
+        progress: Option<UnboundedSender<f64>>,
     ) -> Result<serde_json::Value> {
         let lock = Arc::new(RwLock::new(serde_json::Value::Null));
         let guard = lock.clone().write_owned().await;
         let call_id = self.last_call_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        self.marshalled.write().await.insert(call_id, MarshalledCall { lock: guard, call_id });
+        self.marshalled.write().await.insert(call_id, MarshalledCall { lock: guard, call_id, progress });
         self.proxy_send.send(ToProcessRequest::Call { procedure_id, call_id, payload }).await?;
         info!("waiting for call {} to resolve", call_id);
         let value = lock.write().await;
+        if value.is_null() {
+            eyre::bail!("Call {} to {} failed", call_id, procedure_id);
+        }
         Ok(value.clone())
     }
 
@@ -117,12 +149,19 @@ impl Backend {
         Ok(serde_json::from_value(result)?)
     }
 
-    pub async fn demux(&self, input: PathBuf, output: PathBuf) -> Result<DemuxResult> {
+    pub async fn demux(
+        &self,
+        input: PathBuf,
+        output: PathBuf,
+        progress: Option<UnboundedSender<f64>>,
+    ) -> Result<DemuxResult> {
         let payload = DemuxPayload {
             input_file: input,
             output_dir: output,
         };
-        let result = self.remote_call("Separate", serde_json::to_value(payload).unwrap()).await?;
+        let result = self
+            .remote_call_with_progress("Separate", serde_json::to_value(payload).unwrap(), progress)
+            .await?;
         let result: DemuxResult = serde_json::from_value(result)?;
         Ok(result)
     }
@@ -277,6 +316,12 @@ impl RemoteProcessServer {
 
                 Ok(())
             }
+            FromProcessRequest::Progress { call_id, progress } => {
+                if let Some(MarshalledCall { progress: Some(sender), .. }) = self.marshalled.read().await.get(&call_id) {
+                    let _ = sender.send(progress);
+                }
+                Ok(())
+            }
         }
     }
 }
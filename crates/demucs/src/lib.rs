@@ -11,6 +11,7 @@
 
 use backend::{Backend, DemuxResult, FromBackendRequest, ToProcessRequest};
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, instrument};
 
 use crate::prelude::*;
@@ -23,11 +24,25 @@ const BACKEND_SCRIPT: &str = include_str!("../backend.py");
 
 const ENVIRONMENT_NAME: &str = "demucs";
 
+/// Error of a separation cancelled while it was running, the backend was killed and has to be initialized again
+#[derive(Debug)]
+pub struct BackendKilled;
+
+impl std::fmt::Display for BackendKilled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Separation cancelled, demucs backend killed")
+    }
+}
+
+impl std::error::Error for BackendKilled {}
+
 #[derive(Clone)]
 pub struct Demucs {
     conda_env_file: PathBuf,
     backend_script: PathBuf,
     environment: Arc<RwLock<macromamba::Environment>>,
+    /// Shared with the backend, it stays reachable while a call holds the backend
+    kill: Arc<tokio::sync::Notify>,
     pub status: Arc<RwLock<Status>>,
 }
 
@@ -52,6 +67,7 @@ impl Demucs {
             conda_env_file,
             backend_script,
             environment: Arc::new(RwLock::new(environment)),
+            kill: Arc::new(tokio::sync::Notify::new()),
             status: Arc::new(RwLock::new(Status::Initializing)),
         };
         instance.create_required_files().await?;
@@ -94,7 +110,7 @@ impl Demucs {
                 return Ok(())
             }
         }
-        let (backend, mut recv) = Backend::new(self.environment.clone(), self.backend_script.clone()).await?;
+        let (backend, mut recv) = Backend::new(self.environment.clone(), self.backend_script.clone(), self.kill.clone()).await?;
         let status = self.status.clone();
         let backend_clone = backend.clone();
         tokio::spawn(async move {
@@ -119,12 +135,31 @@ impl Demucs {
         Ok(())
     }
 
-    pub async fn demux(&self, input: PathBuf, output: PathBuf) -> Result<DemuxResult> {
+    /// `progress` receives the progress of the separation, from 0 to 1
+    ///
+    /// Once `cancel` is triggered a separation waiting for the backend is dropped,
+    /// the backend is only killed when it is running this separation (see `BackendKilled`)
+    pub async fn demux(
+        &self,
+        input: PathBuf,
+        output: PathBuf,
+        progress: Option<tokio::sync::mpsc::UnboundedSender<f64>>,
+        cancel: &CancellationToken,
+    ) -> Result<DemuxResult> {
         match &*self.status.read().await {
             Status::Ready { backend } => {
+                let backend = tokio::select! {
+                    backend = backend.lock() => backend,
+                    _ = cancel.cancelled() => eyre::bail!("Separation cancelled before it started"),
+                };
                 info!("Demuxing {} to {}", input.display(), output.display());
-                let result = backend.lock().await.demux(input, output).await?;
-                Ok(result)
+                tokio::select! {
+                    result = backend.demux(input, output, progress) => Ok(result?),
+                    _ = cancel.cancelled() => {
+                        self.kill();
+                        Err(BackendKilled.into())
+                    }
+                }
             }
             _ => {
                 error!("Demucs not ready, failed to process demux task");
@@ -132,6 +167,11 @@ impl Demucs {
             }
         }
     }
+
+    /// Kill the python backend, running separations fail and the instance has to be initialized again
+    pub fn kill(&self) {
+        self.kill.notify_waiters();
+    }
 }
 
 impl std::fmt::Debug for Status {
@@ -238,6 +238,40 @@ impl MambaCommand {
     }
 }
 
+/// Processes started by `cmd.exe`, killed along with it when the command is dropped before it exited.
+/// `kill_on_drop` only kills `cmd.exe`, the script it runs would keep running
+struct ProcessTree {
+    pid: Option<u32>,
+}
+
+impl ProcessTree {
+    fn new(pid: Option<u32>) -> Self {
+        Self { pid }
+    }
+
+    fn exited(&mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        let Some(pid) = self.pid else {
+            return;
+        };
+        tracing::info!(pid, "killing command process tree");
+        // Waits for the processes to be killed, a new command may be started right after
+        let result = std::process::Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        if let Err(e) = result {
+            tracing::error!(pid, "failed to kill command process tree: {}", e);
+        }
+    }
+}
+
 pub struct CommandResult {
     stdout: String,
     stderr: String,
@@ -295,9 +329,12 @@ impl ActivatedCommand {
         cmd.envs(self.environment.iter())
             .args(&["/C", &sub_cmd])
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            // Dropping the future of a running command stops it
+            .kill_on_drop(true);
 
         let mut child = cmd.spawn()?;
+        let mut tree = ProcessTree::new(child.id());
 
         let stdout = child.stdout.take().unwrap();
         let stderr = child.stderr.take().unwrap();
@@ -331,6 +368,7 @@ impl ActivatedCommand {
         }
 
         let status = child.wait().await?;
+        tree.exited();
 
         Ok(CommandResult {
             stdout: collected_stdout.join("\n"),
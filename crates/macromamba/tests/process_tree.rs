@@ -0,0 +1,52 @@
+// The commands run through `cmd.exe`
+#![cfg(windows)]
+
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use macromamba::ActivatedCommand;
+
+/// Mamba root whose activation script does nothing
+fn mamba_root() -> PathBuf {
+    let root = std::env::temp_dir().join(format!("macromamba-process-tree-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("Scripts")).unwrap();
+    std::fs::write(root.join("Scripts").join("activate.bat"), "@echo off\r\n").unwrap();
+    root
+}
+
+fn is_running(pid: u32) -> bool {
+    let output = std::process::Command::new("tasklist")
+        .args(["/NH", "/FI", &format!("PID eq {}", pid)])
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
+}
+
+#[tokio::test]
+pub async fn test_dropped_command_kills_process_tree() {
+    let root = mamba_root();
+    let pid_file = root.join("pid.txt");
+    let mut command = ActivatedCommand::new(PathBuf::new(), root.clone(), HashMap::new(), "powershell");
+    // Started by `cmd.exe` like the python backend, it writes its pid and waits
+    command.arg("-NoProfile").arg("-Command").arg(&format!(
+        "\"$PID | Out-File -Encoding ascii '{}'; Start-Sleep -Seconds 60\"",
+        pid_file.display()
+    ));
+    let running = tokio::spawn(async move { command.run().await.map(|_| ()) });
+
+    let pid = loop {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        if let Some(pid) = std::fs::read_to_string(&pid_file)
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok())
+        {
+            break pid;
+        }
+    };
+    assert!(is_running(pid));
+
+    // Same as a cancelled separation, the future running the command is dropped
+    running.abort();
+    let _ = running.await;
+    assert!(!is_running(pid));
+    std::fs::remove_dir_all(root).unwrap();
+}
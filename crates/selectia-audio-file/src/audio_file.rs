@@ -3,6 +3,8 @@ use fundsp::prelude::*;
 use symphonia::core::audio::{AsAudioBufferRef, AudioBuffer, Signal as SymphoniaSignal};
 use symphonia::core::formats::FormatReader;
 use symphonia::core::io::MediaSource;
+use symphonia::core::meta::{StandardVisualKey, Tag, Visual};
+use symphonia::core::probe::ProbedMetadata;
 
 use crate::audio_buffer::*;
 use crate::prelude::*;
@@ -10,6 +12,15 @@ use std::fs::File;
 
 pub struct EncodedAudioFile {
     format: Box<dyn FormatReader>,
+    /// Tags found before the container, such as ID3v2
+    metadata: ProbedMetadata,
+}
+
+/// Picture stored in the tags of the file
+#[derive(Debug, Clone)]
+pub struct EmbeddedPicture {
+    pub media_type: String,
+    pub data: Vec<u8>,
 }
 
 #[derive(Clone)]
@@ -98,8 +109,10 @@ impl EncodedAudioFile {
             &FormatOptions::default(),
             &MetadataOptions::default(),
         )?;
-        let format = probed.format;
-        Ok(Self { format: format })
+        Ok(Self {
+            format: probed.format,
+            metadata: probed.metadata,
+        })
     }
 
     pub fn from_file<T: AsRef<Path>>(path: T) -> AudioFileResult<Self> {
@@ -107,6 +120,45 @@ impl EncodedAudioFile {
         Self::from_source(file, path)
     }
 
+    /// Duration in seconds as advertised by the container (no decoding involved)
+    pub fn duration(&self) -> Option<f64> {
+        let track = self.format.default_track()?;
+        let frames = track.codec_params.n_frames?;
+        let sample_rate = track.codec_params.sample_rate?;
+        Some(frames as f64 / sample_rate as f64)
+    }
+
+    /// Front cover of the file, or its first picture when none is flagged as such
+    pub fn embedded_picture(&mut self) -> Option<EmbeddedPicture> {
+        let mut visuals: Vec<Visual> = vec![];
+        if let Some(revision) = self.metadata.get().as_ref().and_then(|metadata| metadata.current()) {
+            visuals.extend(revision.visuals().iter().cloned());
+        }
+        if let Some(revision) = self.format.metadata().current() {
+            visuals.extend(revision.visuals().iter().cloned());
+        }
+        let visual = visuals
+            .iter()
+            .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+            .or_else(|| visuals.first())?;
+        Some(EmbeddedPicture {
+            media_type: visual.media_type.clone(),
+            data: visual.data.to_vec(),
+        })
+    }
+
+    /// Tags found before the container then the ones of the container
+    pub fn tags(&mut self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = vec![];
+        if let Some(revision) = self.metadata.get().as_ref().and_then(|metadata| metadata.current()) {
+            tags.extend(revision.tags().iter().cloned());
+        }
+        if let Some(revision) = self.format.metadata().current() {
+            tags.extend(revision.tags().iter().cloned());
+        }
+        tags
+    }
+
     pub fn total_frames_count(mut self) -> AudioFileResult<u64> {
         let mut total = 0;
 
@@ -442,7 +494,11 @@ impl<'a> AudioFilePayload<'a> {
 
     /// *WIP*
     pub fn wav_export(&self, sample_rate: u32, path: impl AsRef<Path>) -> AudioFileResult<()> {
-        let payload = self.resample(sample_rate as f64)?;
+        let payload = if sample_rate as f64 == self.sample_rate {
+            self.clone_borrowed()
+        } else {
+            self.resample(sample_rate as f64)?
+        };
         let spec = hound::WavSpec {
             channels: self.channels as u16,
             sample_rate: sample_rate,
@@ -460,6 +516,18 @@ impl<'a> AudioFilePayload<'a> {
         writer.finalize()?;
         Ok(())
     }
+
+    /// Lossless 16 bits FLAC at the sample rate of the payload
+    pub fn flac_export(&self, path: impl AsRef<Path>) -> AudioFileResult<()> {
+        let samples = self
+            .buffer
+            .buffer
+            .iter()
+            .map(|sample| sample.to_sample::<i16>())
+            .collect::<Vec<_>>();
+        let file = std::io::BufWriter::new(File::create(path)?);
+        crate::flac::write_flac(file, self.sample_rate as u32, self.channels, &samples)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -0,0 +1,246 @@
+//! Minimal FLAC encoder: fixed blocks, fixed linear predictors and a single Rice partition per subframe.
+//! Streams are lossless but compress less than the reference encoder.
+use crate::prelude::*;
+use std::io::Write;
+
+const BLOCK_SIZE: usize = 4096;
+const BITS_PER_SAMPLE: u32 = 16;
+const MAX_RICE_PARAMETER: u32 = 14;
+
+/// Write interleaved 16 bits samples as a FLAC stream
+pub fn write_flac<W: Write>(
+    mut writer: W,
+    sample_rate: u32,
+    channels: u32,
+    samples: &[i16],
+) -> AudioFileResult<()> {
+    if channels == 0 || channels > 8 {
+        return Err(AudioFileError::InvalidChannelCount {
+            expected: 2,
+            got: channels,
+        });
+    }
+    if sample_rate == 0 || sample_rate >= 1 << 20 {
+        return Err(AudioFileError::InvalidSampleRate);
+    }
+    let frames = samples.len() / channels as usize;
+
+    let mut header = BitWriter::default();
+    header.write_bits(1, 1); // last metadata block
+    header.write_bits(0, 7); // STREAMINFO
+    header.write_bits(34, 24);
+    header.write_bits(BLOCK_SIZE as u64, 16);
+    header.write_bits(BLOCK_SIZE as u64, 16);
+    header.write_bits(0, 24); // unknown min frame size
+    header.write_bits(0, 24); // unknown max frame size
+    header.write_bits(sample_rate as u64, 20);
+    header.write_bits(channels as u64 - 1, 3);
+    header.write_bits(BITS_PER_SAMPLE as u64 - 1, 5);
+    header.write_bits(frames as u64, 36);
+    header.write_bits(0, 64); // MD5 not computed
+    header.write_bits(0, 64);
+    writer.write_all(b"fLaC")?;
+    writer.write_all(&header.into_bytes())?;
+
+    let mut channel = Vec::with_capacity(BLOCK_SIZE);
+    for (frame_idx, block) in samples.chunks(BLOCK_SIZE * channels as usize).enumerate() {
+        let block_size = block.len() / channels as usize;
+        let mut frame = BitWriter::default();
+        frame.write_bits(0b11111111111110, 14);
+        frame.write_bits(0, 1); // reserved
+        frame.write_bits(0, 1); // fixed block size
+        frame.write_bits(0b0111, 4); // block size stored after the frame number
+        frame.write_bits(0b0000, 4); // sample rate from STREAMINFO
+        frame.write_bits(channels as u64 - 1, 4); // independent channels
+        frame.write_bits(0b100, 3); // 16 bits per sample
+        frame.write_bits(0, 1); // reserved
+        write_utf8_number(&mut frame, frame_idx as u64);
+        frame.write_bits(block_size as u64 - 1, 16);
+        let crc = crc8(frame.bytes());
+        frame.write_bits(crc as u64, 8);
+
+        for channel_idx in 0..channels as usize {
+            channel.clear();
+            channel.extend(
+                block
+                    .iter()
+                    .skip(channel_idx)
+                    .step_by(channels as usize)
+                    .map(|sample| *sample as i64),
+            );
+            write_subframe(&mut frame, &channel);
+        }
+
+        frame.align();
+        let crc = crc16(frame.bytes());
+        frame.write_bits(crc as u64, 16);
+        writer.write_all(&frame.into_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Encode with the fixed predictor producing the smallest residual, falling back to verbatim samples
+fn write_subframe(writer: &mut BitWriter, samples: &[i64]) {
+    let verbatim_bits = samples.len() as u64 * BITS_PER_SAMPLE as u64;
+    let best = (0..=4usize)
+        .filter(|order| *order < samples.len())
+        .map(|order| {
+            let residuals = fixed_residuals(samples, order);
+            let parameter = rice_parameter(&residuals);
+            let bits = order as u64 * BITS_PER_SAMPLE as u64 + 6 + rice_bits(&residuals, parameter);
+            (bits, order, residuals, parameter)
+        })
+        .min_by_key(|(bits, ..)| *bits);
+
+    match best {
+        Some((bits, order, residuals, parameter)) if bits < verbatim_bits => {
+            writer.write_bits(0, 1);
+            writer.write_bits(0b001000 | order as u64, 6);
+            writer.write_bits(0, 1); // no wasted bits
+            for sample in samples[..order].iter() {
+                writer.write_signed(*sample, BITS_PER_SAMPLE);
+            }
+            writer.write_bits(0b00, 2); // 4 bits Rice parameters
+            writer.write_bits(0, 4); // a single partition
+            writer.write_bits(parameter as u64, 4);
+            for residual in residuals {
+                let folded = ((residual << 1) ^ (residual >> 63)) as u64;
+                writer.write_unary(folded >> parameter);
+                writer.write_bits(folded & ((1 << parameter) - 1), parameter);
+            }
+        }
+        _ => {
+            writer.write_bits(0, 1);
+            writer.write_bits(0b000001, 6);
+            writer.write_bits(0, 1);
+            for sample in samples {
+                writer.write_signed(*sample, BITS_PER_SAMPLE);
+            }
+        }
+    }
+}
+
+fn fixed_residuals(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|n| {
+            let s = |i: usize| samples[n - i];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+fn rice_parameter(residuals: &[i64]) -> u32 {
+    if residuals.is_empty() {
+        return 0;
+    }
+    let mean = residuals.iter().map(|r| r.unsigned_abs()).sum::<u64>() / residuals.len() as u64;
+    match mean {
+        0 => 0,
+        mean => (63 - mean.leading_zeros()).min(MAX_RICE_PARAMETER),
+    }
+}
+
+fn rice_bits(residuals: &[i64], parameter: u32) -> u64 {
+    residuals
+        .iter()
+        .map(|residual| {
+            let folded = ((residual << 1) ^ (residual >> 63)) as u64;
+            (folded >> parameter) + 1 + parameter as u64
+        })
+        .sum()
+}
+
+/// Frame numbers are coded like UTF-8 characters
+fn write_utf8_number(writer: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        writer.write_bits(value, 8);
+        return;
+    }
+    let continuation_bytes = match value {
+        v if v < 0x800 => 1,
+        v if v < 0x10000 => 2,
+        v if v < 0x200000 => 3,
+        v if v < 0x4000000 => 4,
+        _ => 5,
+    };
+    let prefix = (0xFF00u64 >> (continuation_bytes + 1)) & 0xFF;
+    writer.write_bits(prefix | (value >> (6 * continuation_bytes)), 8);
+    for idx in (0..continuation_bytes).rev() {
+        writer.write_bits(0x80 | ((value >> (6 * idx)) & 0x3F), 8);
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, byte| {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u8,
+    used: u32,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u64, count: u32) {
+        for idx in (0..count).rev() {
+            self.current = (self.current << 1) | ((value >> idx) & 1) as u8;
+            self.used += 1;
+            if self.used == 8 {
+                self.bytes.push(self.current);
+                self.current = 0;
+                self.used = 0;
+            }
+        }
+    }
+
+    fn write_signed(&mut self, value: i64, count: u32) {
+        self.write_bits(value as u64 & ((1 << count) - 1), count);
+    }
+
+    fn write_unary(&mut self, zeros: u64) {
+        for _ in 0..zeros {
+            self.write_bits(0, 1);
+        }
+        self.write_bits(1, 1);
+    }
+
+    fn align(&mut self) {
+        if self.used > 0 {
+            self.write_bits(0, 8 - self.used);
+        }
+    }
+
+    /// Bytes written so far, excluding a partial byte
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
@@ -3,5 +3,6 @@
 pub mod audio_file;
 pub mod audio_buffer;
 pub mod error;
+pub mod flac;
 pub mod prelude;
 pub use fundsp;
@@ -40,6 +40,17 @@ pub struct WorkerQueueTaskUpdatedEvent {
     pub task: Option<WorkerQueueTask>,
 }
 
+#[derive(Serialize, Clone, TS)]
+#[ts(export_to = "events.ts")]
+pub struct WorkerQueueTaskProgressEvent {
+    pub id: i64,
+    pub step: String,
+    /// From 0 to 1
+    pub progress: f64,
+    /// Estimated remaining time of the step in seconds
+    pub eta: Option<f64>,
+}
+
 #[derive(Serialize, Clone, TS)]
 #[ts(export_to = "events.ts")]
 pub struct TagListChangedEvent {}
@@ -64,6 +75,7 @@ pub enum Events {
     AudioDeckCreated(AudioDeckCreatedEvent),
     WorkerQueueTaskCreated(WorkerQueueTaskCreatedEvent),
     WorkerQueueTaskUpdated(WorkerQueueTaskUpdatedEvent),
+    WorkerQueueTaskProgress(WorkerQueueTaskProgressEvent),
     TagListChanged(TagListChangedEvent),
     EntryChanged(EntryChangedEvent),
     EntryListChanged(EntryListChangedEvent),
@@ -78,6 +90,7 @@ impl Events {
             Events::AudioDeckCreated(_) => "AudioDeckCreated",
             Events::WorkerQueueTaskCreated(_) => "WorkerQueueTaskCreated",
             Events::WorkerQueueTaskUpdated(_) => "WorkerQueueTaskUpdated",
+            Events::WorkerQueueTaskProgress(_) => "WorkerQueueTaskProgress",
             Events::TagListChanged(_) => "TagListChanged",
             Events::EntryChanged(_) => "EntryChanged",
             Events::EntryListChanged(_) => "EntryListChanged",
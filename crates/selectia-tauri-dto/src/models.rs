@@ -27,6 +27,8 @@ pub enum TaskStatus {
     Queued,
     Processing,
     Done,
+    Failed,
+    Cancelled,
 }
 
 #[derive(Serialize, Deserialize, Clone, TS)]
@@ -63,6 +65,7 @@ pub struct DeckFileView {
 #[ts(export_to = "models.ts")]
 pub struct DeckFileMetadataSnapshot {
     pub title: String,
+    pub cover_hash: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, TS)]
@@ -104,7 +107,18 @@ pub struct FilterSelection {
 pub struct EntryView {
     pub metadata_id: i64,
     pub metadata_hash: String,
+    pub cover_hash: Option<String>,
     pub tags: Vec<MetadataTagView>,
+    pub work_id: Option<i64>,
+    pub version: Option<String>,
+    pub versions: Vec<VersionView>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct VersionView {
+    pub metadata_id: i64,
+    pub version: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, TS)]
@@ -115,6 +129,8 @@ pub struct MetadataTagView {
     pub tag_name_id: i64,
     pub tag_value: String,
     pub metadata_id: i64,
+    /// Origin of the tag when set automatically (e.g `filename_rule:1`)
+    pub tag_source: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, TS)]
@@ -143,6 +159,381 @@ pub struct FileVariation {
     pub stem: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct ImportRules {
+    pub extensions: Vec<String>,
+    pub ignore: Vec<String>,
+    pub skip_hidden: bool,
+    pub follow_symlinks: bool,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct TaggingRule {
+    pub id: i64,
+    pub template: String,
+    pub enabled: bool,
+    pub position: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct ExtractedTag {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct FilenameTagPreview {
+    pub metadata_id: i64,
+    pub path: String,
+    pub rule_id: i64,
+    pub tags: Vec<ExtractedTag>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct ImportReport {
+    pub tracks: Vec<TrackReconciliation>,
+    pub playlists: Vec<PlaylistReconciliation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub enum ReconciliationStatus {
+    Matched,
+    MatchedByHash,
+    Ingested,
+    Missing,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct TrackReconciliation {
+    pub source_id: String,
+    pub path: String,
+    pub metadata_id: Option<i64>,
+    pub status: ReconciliationStatus,
+    pub updated: Vec<String>,
+    pub conflicts: Vec<TagConflict>,
+    pub cues: usize,
+    pub beat_grid_markers: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct TagConflict {
+    pub name: String,
+    pub existing: Vec<String>,
+    pub imported: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct PlaylistReconciliation {
+    pub path: Vec<String>,
+    pub playlist_id: i64,
+    pub entries: usize,
+    pub missing_entries: usize,
+}
+
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub enum TranscodeFormat {
+    Wav,
+    Flac,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct FolderExportOptions {
+    pub target: String,
+    pub layout: String,
+    pub transcode: Option<TranscodeFormat>,
+    pub playlist_name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct SeratoExportReport {
+    pub crates: Vec<String>,
+    pub files: Vec<SeratoFileReport>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct SeratoFileReport {
+    pub metadata_id: i64,
+    pub path: String,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct DuplicateGroup {
+    pub entries: Vec<DuplicateEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct DuplicateEntry {
+    pub metadata_id: i64,
+    pub similarity: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub enum VerifiedFileStatus {
+    Unchanged,
+    Changed,
+    Missing,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct VerifiedFile {
+    pub file_id: i64,
+    pub metadata_id: i64,
+    pub path: String,
+    pub status: VerifiedFileStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct LibraryRoot {
+    pub id: i64,
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct RelocationPlan {
+    pub root_id: i64,
+    pub from: String,
+    pub to: String,
+    pub files: usize,
+    pub file_variations: usize,
+    pub missing: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub enum DoctorIssueKind {
+    MissingFile,
+    MissingFileVariation,
+    StuckTask,
+    InvalidTask,
+    BrokenReference,
+    UnusedTag,
+    EntryWithoutFile,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct DoctorIssue {
+    pub kind: DoctorIssueKind,
+    pub fixable: bool,
+    pub table: String,
+    pub row_id: i64,
+    pub detail: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct DoctorReport {
+    pub issues: Vec<DoctorIssue>,
+    pub fixed: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct ValueCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct TempoBin {
+    pub start: f64,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct WeekCount {
+    pub week: i64,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct LibraryStatistics {
+    pub tracks: i64,
+    pub total_duration: f64,
+    pub unknown_duration: i64,
+    pub analysed: i64,
+    pub unanalysed: i64,
+    pub genres: Vec<ValueCount>,
+    pub artists: Vec<ValueCount>,
+    pub labels: Vec<ValueCount>,
+    pub keys: Vec<ValueCount>,
+    pub tempo_histogram: Vec<TempoBin>,
+    pub added_per_week: Vec<WeekCount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct WorkVersion {
+    pub metadata_id: i64,
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct WorkSuggestion {
+    pub work_id: Option<i64>,
+    pub artist: String,
+    pub title: String,
+    pub versions: Vec<WorkVersion>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct FailedTask {
+    pub id: i64,
+    /// Kind of task, `Unknown` if the payload can't be read anymore
+    pub name: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub last_error: Option<String>,
+}
+
+/// Size of the worker pool and tasks of each resource class allowed to run at the same time within it
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct WorkerConcurrency {
+    pub pool_size: usize,
+    /// Stem separation
+    pub separation: usize,
+    /// Exports and library verification
+    pub io: usize,
+    /// Light analysis like fingerprinting
+    pub analysis: usize,
+}
+
+/// Stages of the analysis run on the newly ingested tracks
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct AnalysisPipelineSettings {
+    pub enabled: bool,
+    /// Tags embedded in the file
+    pub metadata: bool,
+    pub waveform: bool,
+    /// Tempo and beat grid
+    pub grid: bool,
+    pub key: bool,
+    pub loudness: bool,
+    pub stems: bool,
+}
+
+/// Results of the analysis pipeline that are not tags nor beat grid
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct EntryAnalysis {
+    pub metadata_id: i64,
+    /// Peaks from 0 to 255, empty until analysed
+    pub waveform: Vec<u8>,
+    /// Integrated loudness in dB relative to full scale
+    pub loudness: Option<f64>,
+    /// Highest sample in dB relative to full scale
+    pub peak: Option<f64>,
+    pub grid_confidence: Option<f64>,
+    pub key_confidence: Option<f64>,
+}
+
+/// Task enqueued periodically by the worker, either on a cron expression or every `interval_seconds`
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct WorkerSchedule {
+    pub id: i64,
+    pub name: String,
+    /// Kind of task, `Unknown` if the payload can't be read anymore
+    pub task: String,
+    pub cron: Option<String>,
+    pub interval_seconds: Option<i64>,
+    pub enabled: bool,
+    /// Unix timestamps
+    pub last_run_at: Option<i64>,
+    pub next_run_at: i64,
+}
+
+/// Run of a task that left the worker pool
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct TaskHistoryRun {
+    pub id: i64,
+    pub task_id: i64,
+    pub name: String,
+    /// Done, failed or cancelled, a failed run may have been retried
+    pub status: TaskStatus,
+    pub resource_class: String,
+    /// 1 for the first run of the task
+    pub attempt: i64,
+    pub worker_slot: i64,
+    /// Unix timestamps
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub duration_ms: i64,
+    /// Error chain of a failed run
+    pub error: Option<String>,
+    /// JSON outcome of the task, i.e the counts of a library verification
+    pub summary: Option<String>,
+}
+
+/// Filters of the task history, `limit` defaults to 100
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct TaskHistoryQuery {
+    pub name: Option<String>,
+    pub status: Option<TaskStatus>,
+    /// Unix timestamp, runs finished before are left out
+    pub since: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Runs of a kind of task, durations are of the successful runs
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct TaskHistoryStats {
+    pub name: String,
+    pub runs: i64,
+    pub failed: i64,
+    pub cancelled: i64,
+    pub average_duration_ms: Option<f64>,
+    pub max_duration_ms: Option<i64>,
+}
+
+/// Runs kept in the task history, the oldest are deleted once either limit is reached
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export_to = "models.ts")]
+pub struct TaskHistoryRetention {
+    pub max_age_days: i64,
+    pub max_runs: i64,
+}
 
 #[derive(Serialize, Deserialize, Clone, TS)]
 #[ts(export_to = "models.ts")]
@@ -163,6 +554,44 @@ pub enum Models {
     TagName(TagName),
     TagView(TagView),
     FileVariation(FileVariation),
+    ImportRules(ImportRules),
+    TaggingRule(TaggingRule),
+    ExtractedTag(ExtractedTag),
+    FilenameTagPreview(FilenameTagPreview),
+    ImportReport(ImportReport),
+    ReconciliationStatus(ReconciliationStatus),
+    TrackReconciliation(TrackReconciliation),
+    TagConflict(TagConflict),
+    PlaylistReconciliation(PlaylistReconciliation),
+    TranscodeFormat(TranscodeFormat),
+    FolderExportOptions(FolderExportOptions),
+    SeratoExportReport(SeratoExportReport),
+    SeratoFileReport(SeratoFileReport),
+    DuplicateGroup(DuplicateGroup),
+    DuplicateEntry(DuplicateEntry),
+    VerifiedFileStatus(VerifiedFileStatus),
+    VerifiedFile(VerifiedFile),
+    LibraryRoot(LibraryRoot),
+    RelocationPlan(RelocationPlan),
+    DoctorIssueKind(DoctorIssueKind),
+    DoctorIssue(DoctorIssue),
+    DoctorReport(DoctorReport),
+    ValueCount(ValueCount),
+    TempoBin(TempoBin),
+    WeekCount(WeekCount),
+    LibraryStatistics(LibraryStatistics),
+    VersionView(VersionView),
+    WorkVersion(WorkVersion),
+    WorkSuggestion(WorkSuggestion),
+    FailedTask(FailedTask),
+    WorkerConcurrency(WorkerConcurrency),
+    AnalysisPipelineSettings(AnalysisPipelineSettings),
+    EntryAnalysis(EntryAnalysis),
+    WorkerSchedule(WorkerSchedule),
+    TaskHistoryRun(TaskHistoryRun),
+    TaskHistoryQuery(TaskHistoryQuery),
+    TaskHistoryStats(TaskHistoryStats),
+    TaskHistoryRetention(TaskHistoryRetention),
 }
 
 impl From<SelectiaTaskStatus> for TaskStatus {
@@ -171,6 +600,8 @@ impl From<SelectiaTaskStatus> for TaskStatus {
             SelectiaTaskStatus::Queued => TaskStatus::Queued,
             SelectiaTaskStatus::Processing => TaskStatus::Processing,
             SelectiaTaskStatus::Done => TaskStatus::Done,
+            SelectiaTaskStatus::Failed => TaskStatus::Failed,
+            SelectiaTaskStatus::Cancelled => TaskStatus::Cancelled,
         }
     }
 }
@@ -180,14 +611,18 @@ impl From<selectia::database::views::entry_view::EntryView> for EntryView {
         EntryView {
             metadata_id: entry.metadata_id,
             metadata_hash: entry.metadata_hash,
+            cover_hash: entry.cover_hash,
             tags: entry.tags.0.into_iter().map(|e| e.into()).collect(),
+            work_id: entry.work_id,
+            version: entry.version,
+            versions: entry.versions.0.into_iter().map(|e| VersionView { metadata_id: e.metadata_id, version: e.version }).collect(),
         }
     }
 }
 
 impl From<selectia::database::views::entry_view::MetadataTagView> for MetadataTagView {
     fn from(tag: selectia::database::views::entry_view::MetadataTagView) -> Self {
-        MetadataTagView { tag_id: tag.tag_id, metadata_tag_id: tag.metadata_tag_id, tag_name_id: tag.tag_name_id, tag_value: tag.tag_value, metadata_id: tag.metadata_id }
+        MetadataTagView { tag_id: tag.tag_id, metadata_tag_id: tag.metadata_tag_id, tag_name_id: tag.tag_name_id, tag_value: tag.tag_value, metadata_id: tag.metadata_id, tag_source: tag.tag_source }
     }
 }
 
@@ -210,7 +645,7 @@ impl From<selectia::services::audio_player::DeckFilePayloadSnapshot> for DeckFil
 
 impl From<selectia::services::audio_player::DeckFileMetadataSnapshot> for DeckFileMetadataSnapshot {
     fn from(metadata: selectia::services::audio_player::DeckFileMetadataSnapshot) -> Self {
-        DeckFileMetadataSnapshot { title: metadata.title }
+        DeckFileMetadataSnapshot { title: metadata.title, cover_hash: metadata.cover_hash }
     }
 }
 
@@ -230,3 +665,354 @@ impl From<selectia::database::models::DecodedFileVariation> for FileVariation {
         FileVariation { id: variation.id, path: variation.path, title, stem }
     }
 }
+
+impl From<selectia::database::models::ImportRules> for ImportRules {
+    fn from(rules: selectia::database::models::ImportRules) -> Self {
+        ImportRules { extensions: rules.extensions, ignore: rules.ignore, skip_hidden: rules.skip_hidden, follow_symlinks: rules.follow_symlinks, min_size: rules.min_size, max_size: rules.max_size, min_duration: rules.min_duration, max_duration: rules.max_duration }
+    }
+}
+
+impl From<ImportRules> for selectia::database::models::ImportRules {
+    fn from(rules: ImportRules) -> Self {
+        selectia::database::models::ImportRules { extensions: rules.extensions, ignore: rules.ignore, skip_hidden: rules.skip_hidden, follow_symlinks: rules.follow_symlinks, min_size: rules.min_size, max_size: rules.max_size, min_duration: rules.min_duration, max_duration: rules.max_duration }
+    }
+}
+
+impl From<selectia::database::models::TaggingRule> for TaggingRule {
+    fn from(rule: selectia::database::models::TaggingRule) -> Self {
+        TaggingRule { id: rule.id, template: rule.template, enabled: rule.enabled, position: rule.position }
+    }
+}
+
+impl From<selectia::tasks::filename_tagging::FilenameTagPreview> for FilenameTagPreview {
+    fn from(preview: selectia::tasks::filename_tagging::FilenameTagPreview) -> Self {
+        FilenameTagPreview { metadata_id: preview.metadata_id, path: preview.path, rule_id: preview.rule_id, tags: preview.tags.into_iter().map(|tag| ExtractedTag { name: tag.name, value: tag.value }).collect() }
+    }
+}
+
+impl From<selectia::tasks::reconciliation::ImportReport> for ImportReport {
+    fn from(report: selectia::tasks::reconciliation::ImportReport) -> Self {
+        ImportReport { tracks: report.tracks.into_iter().map(|track| track.into()).collect(), playlists: report.playlists.into_iter().map(|playlist| playlist.into()).collect() }
+    }
+}
+
+impl From<selectia::tasks::reconciliation::ReconciliationStatus> for ReconciliationStatus {
+    fn from(status: selectia::tasks::reconciliation::ReconciliationStatus) -> Self {
+        match status {
+            selectia::tasks::reconciliation::ReconciliationStatus::Matched => ReconciliationStatus::Matched,
+            selectia::tasks::reconciliation::ReconciliationStatus::MatchedByHash => ReconciliationStatus::MatchedByHash,
+            selectia::tasks::reconciliation::ReconciliationStatus::Ingested => ReconciliationStatus::Ingested,
+            selectia::tasks::reconciliation::ReconciliationStatus::Missing => ReconciliationStatus::Missing,
+            selectia::tasks::reconciliation::ReconciliationStatus::Failed => ReconciliationStatus::Failed,
+        }
+    }
+}
+
+impl From<selectia::tasks::reconciliation::TrackReconciliation> for TrackReconciliation {
+    fn from(track: selectia::tasks::reconciliation::TrackReconciliation) -> Self {
+        TrackReconciliation { source_id: track.source_id, path: track.path, metadata_id: track.metadata_id, status: track.status.into(), updated: track.updated, conflicts: track.conflicts.into_iter().map(|conflict| TagConflict { name: conflict.name, existing: conflict.existing, imported: conflict.imported }).collect(), cues: track.cues, beat_grid_markers: track.beat_grid_markers, error: track.error }
+    }
+}
+
+impl From<selectia::tasks::reconciliation::PlaylistReconciliation> for PlaylistReconciliation {
+    fn from(playlist: selectia::tasks::reconciliation::PlaylistReconciliation) -> Self {
+        PlaylistReconciliation { path: playlist.path, playlist_id: playlist.playlist_id, entries: playlist.entries, missing_entries: playlist.missing_entries }
+    }
+}
+
+impl From<TranscodeFormat> for selectia::tasks::folder_export::TranscodeFormat {
+    fn from(format: TranscodeFormat) -> Self {
+        match format {
+            TranscodeFormat::Wav => selectia::tasks::folder_export::TranscodeFormat::Wav,
+            TranscodeFormat::Flac => selectia::tasks::folder_export::TranscodeFormat::Flac,
+        }
+    }
+}
+
+impl From<FolderExportOptions> for selectia::tasks::folder_export::FolderExportOptions {
+    fn from(options: FolderExportOptions) -> Self {
+        selectia::tasks::folder_export::FolderExportOptions { target: options.target.into(), layout: options.layout, transcode: options.transcode.map(|format| format.into()), playlist_name: options.playlist_name }
+    }
+}
+
+impl From<selectia::tasks::serato::SeratoExportReport> for SeratoExportReport {
+    fn from(report: selectia::tasks::serato::SeratoExportReport) -> Self {
+        SeratoExportReport { crates: report.crates, files: report.files.into_iter().map(|file| file.into()).collect() }
+    }
+}
+
+impl From<selectia::tasks::serato::SeratoFileReport> for SeratoFileReport {
+    fn from(file: selectia::tasks::serato::SeratoFileReport) -> Self {
+        SeratoFileReport { metadata_id: file.metadata_id, path: file.path, error: file.error }
+    }
+}
+
+impl From<selectia::tasks::duplicates::DuplicateGroup> for DuplicateGroup {
+    fn from(group: selectia::tasks::duplicates::DuplicateGroup) -> Self {
+        DuplicateGroup { entries: group.entries.into_iter().map(|entry| entry.into()).collect() }
+    }
+}
+
+impl From<selectia::tasks::duplicates::DuplicateEntry> for DuplicateEntry {
+    fn from(entry: selectia::tasks::duplicates::DuplicateEntry) -> Self {
+        DuplicateEntry { metadata_id: entry.metadata_id, similarity: entry.similarity }
+    }
+}
+
+impl From<selectia::tasks::verify_library::VerifiedFileStatus> for VerifiedFileStatus {
+    fn from(status: selectia::tasks::verify_library::VerifiedFileStatus) -> Self {
+        match status {
+            selectia::tasks::verify_library::VerifiedFileStatus::Unchanged => VerifiedFileStatus::Unchanged,
+            selectia::tasks::verify_library::VerifiedFileStatus::Changed => VerifiedFileStatus::Changed,
+            selectia::tasks::verify_library::VerifiedFileStatus::Missing => VerifiedFileStatus::Missing,
+            selectia::tasks::verify_library::VerifiedFileStatus::Failed => VerifiedFileStatus::Failed,
+        }
+    }
+}
+
+impl From<selectia::tasks::verify_library::VerifiedFile> for VerifiedFile {
+    fn from(file: selectia::tasks::verify_library::VerifiedFile) -> Self {
+        VerifiedFile { file_id: file.file_id, metadata_id: file.metadata_id, path: file.path.to_string_lossy().to_string(), status: file.status.into(), error: file.error }
+    }
+}
+
+impl From<selectia::database::models::LibraryRoot> for LibraryRoot {
+    fn from(root: selectia::database::models::LibraryRoot) -> Self {
+        LibraryRoot { id: root.id, name: root.display_name(), path: root.path }
+    }
+}
+
+impl From<selectia::tasks::library_relocation::RelocationPlan> for RelocationPlan {
+    fn from(plan: selectia::tasks::library_relocation::RelocationPlan) -> Self {
+        RelocationPlan { root_id: plan.root_id, from: plan.from.to_string_lossy().to_string(), to: plan.to.to_string_lossy().to_string(), files: plan.files, file_variations: plan.file_variations, missing: plan.missing.into_iter().map(|path| path.to_string_lossy().to_string()).collect() }
+    }
+}
+
+impl From<selectia::tasks::doctor::DoctorIssueKind> for DoctorIssueKind {
+    fn from(kind: selectia::tasks::doctor::DoctorIssueKind) -> Self {
+        match kind {
+            selectia::tasks::doctor::DoctorIssueKind::MissingFile => DoctorIssueKind::MissingFile,
+            selectia::tasks::doctor::DoctorIssueKind::MissingFileVariation => DoctorIssueKind::MissingFileVariation,
+            selectia::tasks::doctor::DoctorIssueKind::StuckTask => DoctorIssueKind::StuckTask,
+            selectia::tasks::doctor::DoctorIssueKind::InvalidTask => DoctorIssueKind::InvalidTask,
+            selectia::tasks::doctor::DoctorIssueKind::BrokenReference => DoctorIssueKind::BrokenReference,
+            selectia::tasks::doctor::DoctorIssueKind::UnusedTag => DoctorIssueKind::UnusedTag,
+            selectia::tasks::doctor::DoctorIssueKind::EntryWithoutFile => DoctorIssueKind::EntryWithoutFile,
+        }
+    }
+}
+
+impl From<selectia::tasks::doctor::DoctorIssue> for DoctorIssue {
+    fn from(issue: selectia::tasks::doctor::DoctorIssue) -> Self {
+        DoctorIssue { kind: issue.kind.into(), fixable: issue.kind.is_fixable(), table: issue.table, row_id: issue.row_id, detail: issue.detail }
+    }
+}
+
+impl From<selectia::tasks::doctor::DoctorReport> for DoctorReport {
+    fn from(report: selectia::tasks::doctor::DoctorReport) -> Self {
+        DoctorReport { issues: report.issues.into_iter().map(|issue| issue.into()).collect(), fixed: report.fixed }
+    }
+}
+
+impl From<selectia::tasks::statistics::ValueCount> for ValueCount {
+    fn from(count: selectia::tasks::statistics::ValueCount) -> Self {
+        ValueCount { value: count.value, count: count.count }
+    }
+}
+
+impl From<selectia::tasks::statistics::LibraryStatistics> for LibraryStatistics {
+    fn from(statistics: selectia::tasks::statistics::LibraryStatistics) -> Self {
+        LibraryStatistics {
+            tracks: statistics.tracks,
+            total_duration: statistics.total_duration,
+            unknown_duration: statistics.unknown_duration,
+            analysed: statistics.analysed,
+            unanalysed: statistics.unanalysed,
+            genres: statistics.genres.into_iter().map(|count| count.into()).collect(),
+            artists: statistics.artists.into_iter().map(|count| count.into()).collect(),
+            labels: statistics.labels.into_iter().map(|count| count.into()).collect(),
+            keys: statistics.keys.into_iter().map(|count| count.into()).collect(),
+            tempo_histogram: statistics.tempo_histogram.into_iter().map(|bin| TempoBin { start: bin.start, count: bin.count }).collect(),
+            added_per_week: statistics.added_per_week.into_iter().map(|week| WeekCount { week: week.week, count: week.count }).collect(),
+        }
+    }
+}
+
+impl From<selectia::tasks::works::WorkSuggestion> for WorkSuggestion {
+    fn from(suggestion: selectia::tasks::works::WorkSuggestion) -> Self {
+        WorkSuggestion {
+            work_id: suggestion.work_id,
+            artist: suggestion.artist,
+            title: suggestion.title,
+            versions: suggestion.versions.into_iter().map(|version| WorkVersion { metadata_id: version.metadata_id, version: version.version }).collect(),
+        }
+    }
+}
+
+impl From<WorkSuggestion> for selectia::tasks::works::WorkSuggestion {
+    fn from(suggestion: WorkSuggestion) -> Self {
+        selectia::tasks::works::WorkSuggestion {
+            work_id: suggestion.work_id,
+            artist: suggestion.artist,
+            title: suggestion.title,
+            versions: suggestion.versions.into_iter().map(|version| selectia::tasks::works::WorkVersion { metadata_id: version.metadata_id, version: version.version }).collect(),
+        }
+    }
+}
+
+impl From<selectia::services::worker::WorkerConcurrency> for WorkerConcurrency {
+    fn from(concurrency: selectia::services::worker::WorkerConcurrency) -> Self {
+        WorkerConcurrency {
+            pool_size: concurrency.pool_size,
+            separation: concurrency.limits.separation,
+            io: concurrency.limits.io,
+            analysis: concurrency.limits.analysis,
+        }
+    }
+}
+
+impl From<WorkerConcurrency> for selectia::services::worker::WorkerConcurrency {
+    fn from(concurrency: WorkerConcurrency) -> Self {
+        selectia::services::worker::WorkerConcurrency {
+            pool_size: concurrency.pool_size,
+            limits: selectia::services::worker::tasks::ResourceLimits {
+                separation: concurrency.separation,
+                io: concurrency.io,
+                analysis: concurrency.analysis,
+            },
+        }
+    }
+}
+
+impl From<selectia::tasks::analysis::AnalysisPipelineSettings> for AnalysisPipelineSettings {
+    fn from(settings: selectia::tasks::analysis::AnalysisPipelineSettings) -> Self {
+        AnalysisPipelineSettings {
+            enabled: settings.enabled,
+            metadata: settings.metadata,
+            waveform: settings.waveform,
+            grid: settings.grid,
+            key: settings.key,
+            loudness: settings.loudness,
+            stems: settings.stems,
+        }
+    }
+}
+
+impl From<AnalysisPipelineSettings> for selectia::tasks::analysis::AnalysisPipelineSettings {
+    fn from(settings: AnalysisPipelineSettings) -> Self {
+        selectia::tasks::analysis::AnalysisPipelineSettings {
+            enabled: settings.enabled,
+            metadata: settings.metadata,
+            waveform: settings.waveform,
+            grid: settings.grid,
+            key: settings.key,
+            loudness: settings.loudness,
+            stems: settings.stems,
+        }
+    }
+}
+
+impl From<selectia::database::models::MetadataAnalysis> for EntryAnalysis {
+    fn from(analysis: selectia::database::models::MetadataAnalysis) -> Self {
+        EntryAnalysis {
+            metadata_id: analysis.metadata_id,
+            waveform: analysis.waveform.unwrap_or_default(),
+            loudness: analysis.loudness,
+            peak: analysis.peak,
+            grid_confidence: analysis.grid_confidence,
+            key_confidence: analysis.key_confidence,
+        }
+    }
+}
+
+impl From<selectia::database::models::Schedule> for WorkerSchedule {
+    fn from(schedule: selectia::database::models::Schedule) -> Self {
+        WorkerSchedule {
+            id: schedule.id,
+            task: serde_json::from_str::<selectia::services::worker::tasks::TaskPayload>(&schedule.payload)
+                .map(|payload| payload.name())
+                .unwrap_or("Unknown")
+                .to_string(),
+            name: schedule.name,
+            cron: schedule.cron,
+            interval_seconds: schedule.interval_seconds,
+            enabled: schedule.enabled,
+            last_run_at: schedule.last_run_at,
+            next_run_at: schedule.next_run_at,
+        }
+    }
+}
+
+impl From<selectia::database::models::TaskHistory> for TaskHistoryRun {
+    fn from(run: selectia::database::models::TaskHistory) -> Self {
+        TaskHistoryRun {
+            id: run.id,
+            task_id: run.task_id,
+            status: SelectiaTaskStatus::try_from(run.status.as_str())
+                .unwrap_or(SelectiaTaskStatus::Failed)
+                .into(),
+            name: run.name,
+            resource_class: run.resource_class,
+            attempt: run.attempt,
+            worker_slot: run.worker_slot,
+            started_at: run.started_at,
+            finished_at: run.finished_at,
+            duration_ms: run.duration_ms,
+            error: run.error,
+            summary: run.summary,
+        }
+    }
+}
+
+impl From<TaskHistoryQuery> for selectia::database::models::TaskHistoryQuery {
+    fn from(query: TaskHistoryQuery) -> Self {
+        selectia::database::models::TaskHistoryQuery {
+            name: query.name,
+            status: query.status.map(|status| {
+                match status {
+                    TaskStatus::Queued => SelectiaTaskStatus::Queued,
+                    TaskStatus::Processing => SelectiaTaskStatus::Processing,
+                    TaskStatus::Done => SelectiaTaskStatus::Done,
+                    TaskStatus::Failed => SelectiaTaskStatus::Failed,
+                    TaskStatus::Cancelled => SelectiaTaskStatus::Cancelled,
+                }
+                .as_str()
+                .to_string()
+            }),
+            since: query.since,
+            limit: query.limit,
+            offset: query.offset,
+        }
+    }
+}
+
+impl From<selectia::database::models::TaskHistoryStats> for TaskHistoryStats {
+    fn from(stats: selectia::database::models::TaskHistoryStats) -> Self {
+        TaskHistoryStats {
+            name: stats.name,
+            runs: stats.runs,
+            failed: stats.failed,
+            cancelled: stats.cancelled,
+            average_duration_ms: stats.average_duration_ms,
+            max_duration_ms: stats.max_duration_ms,
+        }
+    }
+}
+
+impl From<selectia::services::worker::TaskHistoryRetention> for TaskHistoryRetention {
+    fn from(retention: selectia::services::worker::TaskHistoryRetention) -> Self {
+        TaskHistoryRetention {
+            max_age_days: retention.max_age_days,
+            max_runs: retention.max_runs,
+        }
+    }
+}
+
+impl From<TaskHistoryRetention> for selectia::services::worker::TaskHistoryRetention {
+    fn from(retention: TaskHistoryRetention) -> Self {
+        selectia::services::worker::TaskHistoryRetention {
+            max_age_days: retention.max_age_days,
+            max_runs: retention.max_runs,
+        }
+    }
+}
@@ -15,6 +15,7 @@ use theater::{
 };
 use tokio::sync::RwLockWriteGuard;
 use worker::{
+    scheduler::install_default_schedules,
     tasks::{BackgroundTask, FileAnalysisTask, TaskPayload, TaskStatus},
     worker, Worker, WorkerEvent, WorkerTask,
 };
@@ -36,14 +37,36 @@ impl App {
 
         let settings = Settings::load().await.expect("Failed to load settings");
 
+        let database = Database::new(&settings.database_path)
+            .await
+            .expect("Failed to initialize database");
+        install_default_schedules(&database, settings.database_path.with_file_name("backups"))
+            .await
+            .expect("Failed to install default schedules");
         context
-            .register_singleton(
-                Database::new(&settings.database_path)
-                    .await
-                    .expect("Failed to initialize database"),
-            )
+            .register_singleton(database)
             .await
             .expect("Failed to register database singleton");
+        context
+            .register_singleton(CoverCache::new(settings.cover_cache_path.clone()))
+            .await
+            .expect("Failed to register cover cache singleton");
+        context
+            .register_singleton(settings.hash_strategy)
+            .await
+            .expect("Failed to register hash strategy singleton");
+        context
+            .register_singleton(settings.worker_concurrency())
+            .await
+            .expect("Failed to register worker concurrency singleton");
+        context
+            .register_singleton(settings.analysis_pipeline)
+            .await
+            .expect("Failed to register analysis pipeline settings singleton");
+        context
+            .register_singleton(settings.task_history_retention)
+            .await
+            .expect("Failed to register task history retention singleton");
 
         AudioPlayerService::spawn(&context)
             .await
@@ -60,6 +83,9 @@ impl App {
         Worker::spawn(&context)
             .await
             .expect("Failed to spawn Worker");
+        Pipeline::spawn(&context)
+            .await
+            .expect("Failed to spawn Pipeline");
 
         App {
             context: context,
@@ -69,10 +95,13 @@ impl App {
     /// Called once tauri is ready this function will create required binding betwen the global Theater and the tauri runtime.
     pub async fn setup(&self, handle: AppHandle) -> eyre::Result<()> {
         handle.manage(self.context.get_singleton::<Database>().await?);
+        handle.manage(self.context.get_singleton::<CoverCache>().await?);
+        handle.manage(self.context.get_singleton::<HashStrategy>().await?);
         handle.manage(self.context.get_singleton_address::<AudioPlayerService>().await?);
         handle.manage(self.context.get_singleton_address::<StateMachine>().await?);
         handle.manage(self.context.get_singleton_address::<FileLoader>().await?);
         handle.manage(self.context.get_singleton_address::<Worker>().await?);
+        handle.manage(self.context.get_singleton_address::<Pipeline>().await?);
         handle.manage(ContextProvider::<InteractiveListContext>::new());
 
         self.context.register_singleton(handle.clone()).await.expect("Failed to register app handle");
@@ -103,6 +132,9 @@ impl App {
                     };
                     let _ = ui_dispatcher.emit_event(dto::WorkerQueueTaskUpdatedEvent { id, task });
                 }
+                WorkerEvent::QueueTaskProgress { id, step, progress, eta } => {
+                    let _ = ui_dispatcher.emit_event(dto::WorkerQueueTaskProgressEvent { id, step, progress, eta });
+                }
             }))
             .await;
 
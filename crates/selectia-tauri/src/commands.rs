@@ -3,14 +3,23 @@ use std::collections::BTreeMap;
 use audio_player::{AudioPlayer, AudioPlayerService, AudioPlayerTask, TrackTarget};
 use dto::{EntryChangedEvent, EntryListChangedEvent, TagListChangedEvent};
 use interactive_list_context::InteractiveListContext;
-use selectia::database::views::TagView;
+use selectia::analyser::path_pattern_analyser::PathPattern;
+use selectia::database::{models::MetadataAnalysis, views::TagView};
+use selectia::tasks::doctor::Doctor;
+use selectia::tasks::library_relocation::LibraryRelocation;
+use selectia::tasks::selection::ExportSelection;
+use selectia::tasks::statistics::LibraryStatistics;
+use selectia::tasks::works::WorkGrouping;
 use tauri::{AppHandle, Emitter, State};
 use worker::{
-    tasks::{FileAnalysisTask, StemExtractionTask, TaskPayload, TaskStatus},
-    Worker, WorkerTask,
+    tasks::{
+        FileAnalysisTask, FingerprintTask, FolderExportTask, StemExtractionTask, TaskOptions, TaskPayload,
+        TaskPriority, TaskStatus, VerifyLibraryTask,
+    },
+    TaskHistoryRetention, Worker, WorkerConcurrency, WorkerTask,
 };
 
-use crate::prelude::*;
+use crate::{prelude::*, settings::Settings};
 
 #[tauri::command]
 #[instrument(skip(app, provider))]
@@ -90,13 +99,475 @@ pub async fn get_interactive_list_context_entries(
 pub async fn import_folder(
     directory: String,
     file_loader: State<'_, AddressableService<FileLoaderTask>>,
+    database: State<'_, Database>,
 ) -> AppResult<String> {
-    LoadDirectory::new({ &*file_loader }.clone(), PathBuf::from(directory))?
+    let directory = PathBuf::from(directory);
+    let rules = database.get_import_rules(&directory).await?;
+    LoadDirectory::new({ &*file_loader }.clone(), directory)?
+        .with_rules(rules)
         .load()
         .await?;
     Ok("ok".to_string())
 }
 
+#[tauri::command]
+pub async fn get_import_rules(
+    directory: String,
+    database: State<'_, Database>,
+) -> AppResult<dto::ImportRules> {
+    let rules = database.get_import_rules(Path::new(&directory)).await?;
+    Ok(rules.into())
+}
+
+#[tauri::command]
+pub async fn set_import_rules(
+    directory: String,
+    rules: dto::ImportRules,
+    database: State<'_, Database>,
+) -> AppResult<()> {
+    database
+        .upsert_library_root(Path::new(&directory), &rules.into())
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_library_roots(database: State<'_, Database>) -> AppResult<Vec<dto::LibraryRoot>> {
+    let roots = database.get_library_roots().await?;
+    Ok(roots.into_iter().map(|root| root.into()).collect())
+}
+
+#[tauri::command]
+pub async fn rename_library_root(
+    root_id: i64,
+    name: Option<String>,
+    database: State<'_, Database>,
+) -> AppResult<()> {
+    database
+        .set_library_root_name(root_id, name.as_deref())
+        .await?;
+    Ok(())
+}
+
+/// Files of the root missing at `path`, nothing is changed
+#[tauri::command]
+pub async fn preview_library_root_relocation(
+    root_id: i64,
+    path: String,
+    database: State<'_, Database>,
+) -> AppResult<dto::RelocationPlan> {
+    let plan = LibraryRelocation::new({ &*database }.clone(), root_id, PathBuf::from(path))
+        .plan()
+        .await?;
+    Ok(plan.into())
+}
+
+/// Move the root to `path`, fails when files are missing there
+#[tauri::command]
+pub async fn relocate_library_root(
+    root_id: i64,
+    path: String,
+    database: State<'_, Database>,
+    app: AppHandle,
+) -> AppResult<dto::RelocationPlan> {
+    let plan = LibraryRelocation::new({ &*database }.clone(), root_id, PathBuf::from(path))
+        .relocate()
+        .await?;
+    app.emit_event(EntryListChangedEvent {})?;
+    app.emit_event(TagListChangedEvent {})?;
+    Ok(plan.into())
+}
+
+#[tauri::command]
+pub async fn import_rekordbox_xml(
+    path: String,
+    file_loader: State<'_, AddressableService<FileLoaderTask>>,
+    database: State<'_, Database>,
+    app: AppHandle,
+) -> AppResult<dto::ImportReport> {
+    let report = RekordboxImport::from_file(
+        { &*database }.clone(),
+        { &*file_loader }.clone(),
+        Path::new(&path),
+    )
+    .await?
+    .run()
+    .await?;
+    app.emit_event(EntryListChangedEvent {})?;
+    app.emit_event(TagListChangedEvent {})?;
+    Ok(report.into())
+}
+
+/// Export `playlist_id` if set, otherwise the entries matching `filter` if set, otherwise the whole library
+#[tauri::command]
+pub async fn export_rekordbox_xml(
+    path: String,
+    playlist_id: Option<i64>,
+    filter: Option<EntryViewFilter>,
+    database: State<'_, Database>,
+) -> AppResult<()> {
+    RekordboxExport::new({ &*database }.clone(), export_selection(playlist_id, filter))
+        .write(Path::new(&path))
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn import_traktor_nml(
+    path: String,
+    file_loader: State<'_, AddressableService<FileLoaderTask>>,
+    database: State<'_, Database>,
+    app: AppHandle,
+) -> AppResult<dto::ImportReport> {
+    let report = TraktorImport::from_file(
+        { &*database }.clone(),
+        { &*file_loader }.clone(),
+        Path::new(&path),
+    )
+    .await?
+    .run()
+    .await?;
+    app.emit_event(EntryListChangedEvent {})?;
+    app.emit_event(TagListChangedEvent {})?;
+    Ok(report.into())
+}
+
+/// Export `playlist_id` if set, otherwise the entries matching `filter` if set, otherwise the whole library
+#[tauri::command]
+pub async fn export_traktor_nml(
+    path: String,
+    playlist_id: Option<i64>,
+    filter: Option<EntryViewFilter>,
+    database: State<'_, Database>,
+) -> AppResult<()> {
+    TraktorExport::new({ &*database }.clone(), export_selection(playlist_id, filter))
+        .write(Path::new(&path))
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn import_playlist_file(
+    path: String,
+    file_loader: State<'_, AddressableService<FileLoaderTask>>,
+    database: State<'_, Database>,
+    app: AppHandle,
+) -> AppResult<dto::ImportReport> {
+    let report = PlaylistFileImport::from_file(
+        { &*database }.clone(),
+        { &*file_loader }.clone(),
+        Path::new(&path),
+    )
+    .await?
+    .run()
+    .await?;
+    app.emit_event(EntryListChangedEvent {})?;
+    app.emit_event(TagListChangedEvent {})?;
+    Ok(report.into())
+}
+
+/// Export `playlist_id` if set, otherwise the entries matching `filter` if set, otherwise the whole library
+#[tauri::command]
+pub async fn export_m3u8(
+    path: String,
+    playlist_id: Option<i64>,
+    filter: Option<EntryViewFilter>,
+    database: State<'_, Database>,
+) -> AppResult<()> {
+    PlaylistFileExport::new({ &*database }.clone(), export_selection(playlist_id, filter))
+        .write(Path::new(&path))
+        .await?;
+    Ok(())
+}
+
+/// Write the crates into the `_Serato_` folder `serato_dir` and, when `write_markers` is set, the cues and grids into the MP3 files.
+/// Exports `playlist_id` if set, otherwise the entries matching `filter` if set, otherwise the whole library
+#[tauri::command]
+pub async fn export_serato(
+    serato_dir: String,
+    write_markers: bool,
+    playlist_id: Option<i64>,
+    filter: Option<EntryViewFilter>,
+    database: State<'_, Database>,
+) -> AppResult<dto::SeratoExportReport> {
+    let report = SeratoExport::new({ &*database }.clone(), export_selection(playlist_id, filter))
+        .write(Path::new(&serato_dir), write_markers)
+        .await?;
+    Ok(report.into())
+}
+
+/// Schedule the copy of `playlist_id` if set, otherwise of the entries matching `filter` if set, otherwise of the whole library
+#[tauri::command]
+pub async fn export_to_folder(
+    options: dto::FolderExportOptions,
+    playlist_id: Option<i64>,
+    filter: Option<EntryViewFilter>,
+    worker: State<'_, AddressableService<WorkerTask>>,
+) -> AppResult<()> {
+    let task = TaskPayload::FolderExport(FolderExportTask {
+        selection: export_selection(playlist_id, filter),
+        options: options.into(),
+    });
+    worker.send(WorkerTask::Schedule(task)).await?;
+    Ok(())
+}
+
+/// Schedule the fingerprinting of every entry not fingerprinted yet
+#[tauri::command]
+pub async fn fingerprint_library(
+    database: State<'_, Database>,
+    worker: State<'_, AddressableService<WorkerTask>>,
+) -> AppResult<usize> {
+    let metadata_ids = database.get_unfingerprinted_metadata_ids().await?;
+    for metadata_id in metadata_ids.iter().copied() {
+        let task = TaskPayload::Fingerprint(FingerprintTask { metadata_id });
+        worker
+            .send(WorkerTask::ScheduleWith {
+                payload: task,
+                options: TaskOptions::priority(TaskPriority::Background),
+                callback: None,
+            })
+            .await?;
+    }
+    Ok(metadata_ids.len())
+}
+
+/// Groups of fingerprinted entries sounding alike, `min_similarity` goes from 0.5 (unrelated) to 1
+#[tauri::command]
+pub async fn find_duplicates(
+    min_similarity: Option<f64>,
+    database: State<'_, Database>,
+) -> AppResult<Vec<dto::DuplicateGroup>> {
+    let min_similarity = min_similarity.unwrap_or(selectia::tasks::duplicates::DEFAULT_MIN_SIMILARITY);
+    let groups = DuplicateFinder::new({ &*database }.clone(), min_similarity)
+        .find()
+        .await?;
+    Ok(groups.into_iter().map(|group| group.into()).collect())
+}
+
+/// Hash the library files again in the background, see `get_verification_report`
+#[tauri::command]
+pub async fn verify_library(worker: State<'_, AddressableService<WorkerTask>>) -> AppResult<()> {
+    let task = TaskPayload::VerifyLibrary(VerifyLibraryTask {});
+    worker.send(WorkerTask::Schedule(task)).await?;
+    Ok(())
+}
+
+/// Files found changed, missing or unreadable by the latest library verification
+#[tauri::command]
+pub async fn get_verification_report(database: State<'_, Database>) -> AppResult<Vec<dto::VerifiedFile>> {
+    let report = selectia::tasks::verify_library::get_verification_report(&database).await?;
+    Ok(report.files.into_iter().map(|file| file.into()).collect())
+}
+
+#[tauri::command]
+pub async fn get_library_statistics(database: State<'_, Database>) -> AppResult<dto::LibraryStatistics> {
+    let statistics = LibraryStatistics::compute(&database).await?;
+    Ok(statistics.into())
+}
+
+/// Audit the library, applying the safe fixes when `fix` is set
+#[tauri::command]
+pub async fn run_doctor(
+    fix: bool,
+    database: State<'_, Database>,
+    worker: State<'_, AddressableService<WorkerTask>>,
+    app: AppHandle,
+) -> AppResult<dto::DoctorReport> {
+    let (callback, resolve) = TaskCallback::new();
+    worker.send(WorkerTask::RunningTasks { callback }).await?;
+    let doctor = Doctor::new({ &*database }.clone()).with_running_tasks(resolve.wait().await?);
+    let mut report = doctor.check().await?;
+    info!("Library health check\n{}", report);
+    if fix && report.fixable() > 0 {
+        report = doctor.fix(&report).await?;
+        app.emit_event(EntryListChangedEvent {})?;
+        app.emit_event(TagListChangedEvent {})?;
+    }
+    Ok(report.into())
+}
+
+/// Path of the cover picture, or of the smallest thumbnail covering `size` pixels
+#[tauri::command]
+pub async fn get_cover_path(
+    cover_hash: String,
+    size: Option<u32>,
+    database: State<'_, Database>,
+    covers: State<'_, CoverCache>,
+) -> AppResult<Option<String>> {
+    let Some(cover) = database.get_cover_by_hash(&cover_hash).await? else {
+        return Ok(None);
+    };
+    let path = match size {
+        Some(size) => covers.fitting_path(&cover, size),
+        None => covers.picture_path(&cover),
+    };
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+fn export_selection(playlist_id: Option<i64>, filter: Option<EntryViewFilter>) -> ExportSelection {
+    match (playlist_id, filter) {
+        (Some(playlist_id), _) => ExportSelection::Playlist(playlist_id),
+        (None, Some(filter)) => ExportSelection::Filter(filter),
+        (None, None) => ExportSelection::All,
+    }
+}
+
+#[tauri::command]
+pub async fn get_tagging_rules(database: State<'_, Database>) -> AppResult<Vec<dto::TaggingRule>> {
+    let rules = database.get_tagging_rules().await?;
+    Ok(rules.into_iter().map(dto::TaggingRule::from).collect())
+}
+
+#[tauri::command]
+pub async fn create_tagging_rule(
+    template: String,
+    database: State<'_, Database>,
+    file_loader: State<'_, AddressableService<FileLoaderTask>>,
+) -> AppResult<dto::TaggingRule> {
+    PathPattern::parse(&template)?;
+    let rule = database.create_tagging_rule(&template).await?;
+    reload_tagging_rules(&file_loader).await?;
+    Ok(rule.into())
+}
+
+#[tauri::command]
+pub async fn set_tagging_rule_enabled(
+    id: i64,
+    enabled: bool,
+    database: State<'_, Database>,
+    file_loader: State<'_, AddressableService<FileLoaderTask>>,
+) -> AppResult<()> {
+    database.set_tagging_rule_enabled(id, enabled).await?;
+    reload_tagging_rules(&file_loader).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_tagging_rule(
+    id: i64,
+    database: State<'_, Database>,
+    file_loader: State<'_, AddressableService<FileLoaderTask>>,
+) -> AppResult<()> {
+    database.delete_tagging_rule(id).await?;
+    reload_tagging_rules(&file_loader).await?;
+    Ok(())
+}
+
+/// Files loaded afterward are tagged with the current rules
+async fn reload_tagging_rules(file_loader: &AddressableService<FileLoaderTask>) -> AppResult<()> {
+    file_loader
+        .send(FileLoaderTask::ReloadTaggingRules { callback: None })
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn preview_filename_tags(
+    metadata_ids: Vec<i64>,
+    database: State<'_, Database>,
+) -> AppResult<Vec<dto::FilenameTagPreview>> {
+    let previews = FilenameTagging::new({ &*database }.clone())
+        .await?
+        .preview(&metadata_ids)
+        .await?;
+    Ok(previews.into_iter().map(dto::FilenameTagPreview::from).collect())
+}
+
+#[tauri::command]
+pub async fn apply_filename_tags(
+    metadata_ids: Vec<i64>,
+    database: State<'_, Database>,
+    app: AppHandle,
+) -> AppResult<Vec<i64>> {
+    let updated = FilenameTagging::new({ &*database }.clone())
+        .await?
+        .apply(&metadata_ids)
+        .await?;
+    for metadata_id in updated.iter().copied() {
+        let entry = database.get_entry_by_metadata_id(metadata_id).await?;
+        app.emit_event(EntryChangedEvent {
+            entry: entry.into(),
+        })?;
+    }
+    if !updated.is_empty() {
+        app.emit_event(TagListChangedEvent {})?;
+    }
+    Ok(updated)
+}
+
+/// Move everything attached to `from` onto `into`, `from` is deleted
+#[tauri::command]
+pub async fn merge_metadata(
+    from: i64,
+    into: i64,
+    database: State<'_, Database>,
+    app: AppHandle,
+) -> AppResult<()> {
+    database.merge_metadata(from, into).await?;
+    let entry = database.get_entry_by_metadata_id(into).await?;
+    app.emit_event(EntryChangedEvent {
+        entry: entry.into(),
+    })?;
+    app.emit_event(EntryListChangedEvent {})?;
+    Ok(())
+}
+
+/// Detach the file into a new entry holding a copy of the tags of `tag_name_ids`, returns the new metadata id
+#[tauri::command]
+pub async fn split_file(
+    file_id: i64,
+    tag_name_ids: Vec<i64>,
+    database: State<'_, Database>,
+    hash_strategy: State<'_, HashStrategy>,
+    app: AppHandle,
+) -> AppResult<i64> {
+    let file = database.get_file(file_id).await?;
+    let hash = selectia::services::file_loader::hash_file(Path::new(&file.path), *hash_strategy).await?;
+    let metadata_id = database.split_file(file_id, &hash, &tag_name_ids).await?;
+    for metadata_id in [file.metadata_id, metadata_id] {
+        let entry = database.get_entry_by_metadata_id(metadata_id).await?;
+        app.emit_event(EntryChangedEvent {
+            entry: entry.into(),
+        })?;
+    }
+    app.emit_event(EntryListChangedEvent {})?;
+    Ok(metadata_id)
+}
+
+/// Entries looking like versions of the same song, see `group_versions`
+#[tauri::command]
+pub async fn suggest_works(database: State<'_, Database>) -> AppResult<Vec<dto::WorkSuggestion>> {
+    let suggestions = WorkGrouping::new({ &*database }.clone()).suggest().await?;
+    Ok(suggestions.into_iter().map(|suggestion| suggestion.into()).collect())
+}
+
+/// Link the versions into a work, returns the work id
+#[tauri::command]
+pub async fn group_versions(
+    suggestion: dto::WorkSuggestion,
+    database: State<'_, Database>,
+    app: AppHandle,
+) -> AppResult<i64> {
+    let suggestion: selectia::tasks::works::WorkSuggestion = suggestion.into();
+    let work_id = WorkGrouping::new({ &*database }.clone()).group(&suggestion).await?;
+    for version in suggestion.versions {
+        let entry = database.get_entry_by_metadata_id(version.metadata_id).await?;
+        app.emit_event(EntryChangedEvent {
+            entry: entry.into(),
+        })?;
+    }
+    app.emit_event(EntryListChangedEvent {})?;
+    Ok(work_id)
+}
+
+#[tauri::command]
+pub async fn ungroup_version(metadata_id: i64, database: State<'_, Database>, app: AppHandle) -> AppResult<()> {
+    database.delete_metadata_work(metadata_id).await?;
+    app.emit_event(EntryListChangedEvent {})?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_tag_names(database: State<'_, Database>) -> AppResult<Vec<TagName>> {
     let tags = database.get_tag_names().await?;
@@ -138,6 +609,199 @@ pub async fn get_worker_queue_task(
     })
 }
 
+#[tauri::command]
+pub async fn get_failed_worker_tasks(
+    database: State<'_, Database>,
+) -> AppResult<Vec<dto::FailedTask>> {
+    let tasks = database.get_failed_tasks().await?;
+    Ok(tasks
+        .into_iter()
+        .map(|t| dto::FailedTask {
+            id: t.id,
+            name: serde_json::from_str::<TaskPayload>(&t.payload)
+                .map(|payload| payload.name())
+                .unwrap_or("Unknown")
+                .to_string(),
+            attempts: t.attempts,
+            max_attempts: t.max_attempts,
+            last_error: t.last_error,
+        })
+        .collect())
+}
+
+/// Returns the ids of the tasks put back in the queue, the tasks that were not failed are ignored
+#[tauri::command]
+pub async fn retry_worker_tasks(
+    task_ids: Vec<i64>,
+    worker: State<'_, AddressableService<WorkerTask>>,
+) -> AppResult<Vec<i64>> {
+    let mut requeued = vec![];
+    for id in task_ids {
+        let (callback, resolve) = TaskCallback::new();
+        worker
+            .send(WorkerTask::Retry {
+                id,
+                callback: Some(callback),
+            })
+            .await?;
+        requeued.extend(resolve.wait().await?);
+    }
+    requeued.sort();
+    requeued.dedup();
+    Ok(requeued)
+}
+
+#[tauri::command]
+pub async fn cancel_worker_task(
+    task_id: i64,
+    worker: State<'_, AddressableService<WorkerTask>>,
+) -> AppResult<()> {
+    worker.send(WorkerTask::Cancel { id: task_id }).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_worker_concurrency() -> AppResult<dto::WorkerConcurrency> {
+    let settings = Settings::load().await?;
+    Ok(settings.worker_concurrency().into())
+}
+
+/// Resize the worker pool now and for the next launches
+#[tauri::command]
+pub async fn set_worker_concurrency(
+    concurrency: dto::WorkerConcurrency,
+    worker: State<'_, AddressableService<WorkerTask>>,
+) -> AppResult<()> {
+    let concurrency: WorkerConcurrency = concurrency.into();
+    let mut settings = Settings::load().await?;
+    settings.worker_threads = concurrency.pool_size.max(1) as u32;
+    settings.worker_resource_limits = concurrency.limits;
+    settings.save().await?;
+    worker
+        .send(WorkerTask::SetConcurrency(settings.worker_concurrency()))
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_worker_schedules(database: State<'_, Database>) -> AppResult<Vec<dto::WorkerSchedule>> {
+    let schedules = database.get_schedules().await?;
+    Ok(schedules.into_iter().map(|schedule| schedule.into()).collect())
+}
+
+/// A schedule enabled again runs next on its cron expression or interval from now
+#[tauri::command]
+pub async fn set_worker_schedule_enabled(
+    schedule_id: i64,
+    enabled: bool,
+    worker: State<'_, AddressableService<WorkerTask>>,
+) -> AppResult<()> {
+    worker
+        .send(WorkerTask::SetScheduleEnabled {
+            id: schedule_id,
+            enabled,
+        })
+        .await?;
+    Ok(())
+}
+
+/// Most recent runs first
+#[tauri::command]
+pub async fn get_task_history(
+    query: dto::TaskHistoryQuery,
+    database: State<'_, Database>,
+) -> AppResult<Vec<dto::TaskHistoryRun>> {
+    let runs = database.get_task_history(&query.into()).await?;
+    Ok(runs.into_iter().map(|run| run.into()).collect())
+}
+
+#[tauri::command]
+pub async fn get_task_history_stats(
+    since: Option<i64>,
+    database: State<'_, Database>,
+) -> AppResult<Vec<dto::TaskHistoryStats>> {
+    let stats = database.get_task_history_stats(since).await?;
+    Ok(stats.into_iter().map(|stats| stats.into()).collect())
+}
+
+#[tauri::command]
+pub async fn get_task_history_retention() -> AppResult<dto::TaskHistoryRetention> {
+    let settings = Settings::load().await?;
+    Ok(settings.task_history_retention.into())
+}
+
+/// Applies from the next finished task and to the next launches
+#[tauri::command]
+pub async fn set_task_history_retention(
+    retention: dto::TaskHistoryRetention,
+    worker: State<'_, AddressableService<WorkerTask>>,
+) -> AppResult<()> {
+    let retention: TaskHistoryRetention = retention.into();
+    let mut settings = Settings::load().await?;
+    settings.task_history_retention = TaskHistoryRetention {
+        max_age_days: retention.max_age_days.max(1),
+        max_runs: retention.max_runs.max(1),
+    };
+    settings.save().await?;
+    worker
+        .send(WorkerTask::SetHistoryRetention(settings.task_history_retention))
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_analysis_pipeline_settings() -> AppResult<dto::AnalysisPipelineSettings> {
+    let settings = Settings::load().await?;
+    Ok(settings.analysis_pipeline.into())
+}
+
+/// Applies to the tracks ingested from now on and to the next launches
+#[tauri::command]
+pub async fn set_analysis_pipeline_settings(
+    pipeline_settings: dto::AnalysisPipelineSettings,
+    pipeline: State<'_, AddressableService<PipelineTask>>,
+) -> AppResult<()> {
+    let mut settings = Settings::load().await?;
+    settings.analysis_pipeline = pipeline_settings.into();
+    settings.save().await?;
+    pipeline
+        .send(PipelineTask::SetSettings(settings.analysis_pipeline))
+        .await?;
+    Ok(())
+}
+
+/// Schedule the enabled analysis stages not done yet for the entries
+#[tauri::command]
+pub async fn analyse_entries(
+    metadata_ids: Vec<i64>,
+    pipeline: State<'_, AddressableService<PipelineTask>>,
+) -> AppResult<()> {
+    for metadata_id in metadata_ids {
+        pipeline
+            .send(PipelineTask::Analyse {
+                metadata_id,
+                callback: None,
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_entry_analysis(
+    metadata_id: i64,
+    database: State<'_, Database>,
+) -> AppResult<dto::EntryAnalysis> {
+    let analysis = database
+        .get_metadata_analysis(metadata_id)
+        .await?
+        .unwrap_or(MetadataAnalysis {
+            metadata_id,
+            ..Default::default()
+        });
+    Ok(analysis.into())
+}
+
 #[tauri::command]
 pub async fn create_audio_deck(
     audio_player_service: State<'_, AddressableService<AudioPlayerTask>>,
@@ -239,13 +903,24 @@ pub async fn set_deck_file_status(
     Ok(())
 }
 
+/// Reuses the task already queued for the entry unless `supersede` is set
 #[tauri::command]
 pub async fn extract_stems(
     metadata_id: i64,
+    supersede: Option<bool>,
     worker: State<'_, AddressableService<WorkerTask>>,
 ) -> AppResult<()> {
     let task = TaskPayload::FileAnalysis(FileAnalysisTask { metadata_id });
-    worker.send(WorkerTask::Schedule(task)).await?;
+    worker
+        .send(WorkerTask::ScheduleWith {
+            payload: task,
+            options: TaskOptions {
+                supersede: supersede.unwrap_or(false),
+                ..TaskOptions::priority(TaskPriority::User)
+            },
+            callback: None,
+        })
+        .await?;
     Ok(())
 }
 
@@ -1,14 +1,65 @@
+use selectia::services::worker::{tasks::ResourceLimits, TaskHistoryRetention, WorkerConcurrency};
+use selectia::tasks::analysis::AnalysisPipelineSettings;
+
 use crate::prelude::*;
 
 #[derive(Serialize, Deserialize)]
 pub struct Settings {
     pub database_path: PathBuf,
     pub demuxer_data_path: PathBuf,
+    /// Size of the worker pool
     pub worker_threads: u32,
+    /// Concurrency of each resource class within the worker pool
+    #[serde(default)]
+    pub worker_resource_limits: ResourceLimits,
+    /// Defaults to `covers` in the data directory
+    #[serde(default)]
+    pub cover_cache_path: PathBuf,
+    /// How the content of the files is identified, sampled by default
+    #[serde(default)]
+    pub hash_strategy: HashStrategy,
+    /// Stages of the analysis run on the newly ingested tracks
+    #[serde(default)]
+    pub analysis_pipeline: AnalysisPipelineSettings,
+    /// Runs of the worker tasks kept in the task history
+    #[serde(default)]
+    pub task_history_retention: TaskHistoryRetention,
 }
 
 impl Settings {
     pub async fn load() -> eyre::Result<Self> {
+        let data_dir = Self::data_dir().await?;
+        let settings_file_path = data_dir.join("settings.json");
+
+        let mut settings = match Self::load_stored_settings(&settings_file_path) {
+            Ok(settings) => settings,
+            Err(e) => {
+                error!("Failed to load settings: {}", e);
+                Self::create_default_settings_file(&settings_file_path, &data_dir)?;
+                Self::load_stored_settings(&settings_file_path)?
+            }
+        };
+        if settings.cover_cache_path.as_os_str().is_empty() {
+            settings.cover_cache_path = data_dir.join("covers");
+        }
+        Ok(settings)
+    }
+
+    pub async fn save(&self) -> eyre::Result<()> {
+        let settings_file_path = Self::data_dir().await?.join("settings.json");
+        let settings_str = serde_json::to_string(self)?;
+        tokio::fs::write(settings_file_path, settings_str).await?;
+        Ok(())
+    }
+
+    pub fn worker_concurrency(&self) -> WorkerConcurrency {
+        WorkerConcurrency {
+            pool_size: self.worker_threads.max(1) as usize,
+            limits: self.worker_resource_limits,
+        }
+    }
+
+    async fn data_dir() -> eyre::Result<PathBuf> {
         let data_dir = match std::env::var("SELECTIA_DATA_DIR").ok() {
             Some(path) => {
                 info!("Using data directory from environment variable: {}", path);
@@ -20,16 +71,7 @@ impl Settings {
             }),
         };
         tokio::fs::create_dir_all(&data_dir).await?;
-        let settings_file_path = data_dir.join("settings.json");
-
-        match Self::load_stored_settings(&settings_file_path) {
-            Ok(settings) => Ok(settings),
-            Err(e) => {
-                error!("Failed to load settings: {}", e);
-                Self::create_default_settings_file(&settings_file_path, &data_dir)?;
-                Self::load_stored_settings(&settings_file_path)
-            }
-        }
+        Ok(data_dir)
     }
 
     fn create_default_settings_file(
@@ -44,6 +86,11 @@ impl Settings {
             database_path: data_dir.join("database.db"),
             demuxer_data_path: data_dir.join("demuxer"),
             worker_threads: 4,
+            worker_resource_limits: ResourceLimits::default(),
+            cover_cache_path: data_dir.join("covers"),
+            hash_strategy: HashStrategy::default(),
+            analysis_pipeline: AnalysisPipelineSettings::default(),
+            task_history_retention: TaskHistoryRetention::default(),
         };
         let settings_str = serde_json::to_string(&settings)?;
         std::fs::write(settings_file_path, settings_str)?;
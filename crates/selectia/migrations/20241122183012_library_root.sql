@@ -0,0 +1,5 @@
+CREATE TABLE library_root (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    path TEXT NOT NULL UNIQUE,
+    import_rules TEXT
+);
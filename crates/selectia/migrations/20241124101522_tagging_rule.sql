@@ -0,0 +1,24 @@
+ALTER TABLE metadata_tag ADD COLUMN source TEXT;
+
+DROP VIEW tagged_metadata;
+CREATE VIEW tagged_metadata AS
+SELECT
+    metadata_tag.metadata_id as metadata_id,
+    tag.id as tag_id,
+    tag.name_id as tag_name_id,
+    tag.value as tag_value,
+    metadata_tag.source as tag_source
+FROM metadata_tag
+    LEFT JOIN tag on tag.id = metadata_tag.tag_id;
+
+CREATE TABLE tagging_rule (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    template TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    position INTEGER NOT NULL DEFAULT 0
+);
+
+-- Ids match the `TagName` constants
+INSERT INTO tag_name (id, name, index_in_ui) VALUES (10, 'label', 5);
+INSERT INTO tag_name (id, name, index_in_ui) VALUES (11, 'remix', 6);
+INSERT INTO tag_name (id, name, use_for_filtering) VALUES (12, 'track', false);
@@ -0,0 +1,46 @@
+CREATE TABLE cue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    metadata_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    hot_cue_index INTEGER,
+    start_time REAL NOT NULL,
+    end_time REAL,
+    name TEXT,
+    color TEXT,
+    source TEXT,
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id)
+);
+
+CREATE TABLE beat_grid_marker (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    metadata_id INTEGER NOT NULL,
+    position REAL NOT NULL,
+    bpm REAL NOT NULL,
+    meter TEXT,
+    beat INTEGER NOT NULL DEFAULT 1,
+    source TEXT,
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id)
+);
+
+CREATE TABLE playlist (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    parent_id INTEGER,
+    name TEXT NOT NULL,
+    is_folder BOOLEAN NOT NULL DEFAULT FALSE,
+    position INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (parent_id) REFERENCES playlist(id)
+);
+
+CREATE TABLE playlist_entry (
+    playlist_id INTEGER NOT NULL,
+    metadata_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    FOREIGN KEY (playlist_id) REFERENCES playlist(id),
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id)
+);
+
+-- Ids match the `TagName` constants
+INSERT INTO tag_name (id, name, index_in_ui) VALUES (13, 'key', 7);
+INSERT INTO tag_name (id, name, index_in_ui) VALUES (14, 'rating', 8);
+INSERT INTO tag_name (id, name, use_for_filtering) VALUES (15, 'comment', false);
+INSERT INTO tag_name (id, name, index_in_ui) VALUES (16, 'color', 9);
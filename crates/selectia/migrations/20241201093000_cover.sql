@@ -0,0 +1,15 @@
+CREATE TABLE cover (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    hash TEXT NOT NULL UNIQUE,
+    mime_type TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL
+);
+
+CREATE TABLE metadata_cover (
+    metadata_id INTEGER PRIMARY KEY NOT NULL,
+    cover_id INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id),
+    FOREIGN KEY (cover_id) REFERENCES cover(id)
+);
@@ -0,0 +1,6 @@
+CREATE TABLE fingerprint (
+    metadata_id INTEGER PRIMARY KEY NOT NULL,
+    duration REAL NOT NULL,
+    codes BLOB NOT NULL,
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id)
+);
@@ -0,0 +1,14 @@
+-- Hashes of the metadata merged into another one, their files keep resolving to the merged entry
+CREATE TABLE metadata_alias (
+    hash TEXT PRIMARY KEY NOT NULL,
+    metadata_id INTEGER NOT NULL,
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id)
+);
+
+-- Files split from the metadata of their hash, they stay on their own entry while their content is unchanged
+CREATE TABLE file_pin (
+    path TEXT PRIMARY KEY NOT NULL,
+    hash TEXT NOT NULL,
+    metadata_id INTEGER NOT NULL,
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id)
+);
@@ -0,0 +1,7 @@
+-- Outcome of the latest library verification for each file, see `LibraryVerification`
+CREATE TABLE file_verification (
+    file_id INTEGER PRIMARY KEY NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT,
+    FOREIGN KEY (file_id) REFERENCES file(id)
+);
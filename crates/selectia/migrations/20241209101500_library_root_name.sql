@@ -0,0 +1,2 @@
+-- Roots are shown by name, their path changes with the mount point of the drive holding them
+ALTER TABLE library_root ADD COLUMN name TEXT;
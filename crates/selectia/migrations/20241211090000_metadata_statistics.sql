@@ -0,0 +1,4 @@
+-- Seconds, probed from the container of the file on ingest
+ALTER TABLE metadata ADD COLUMN duration REAL;
+-- Unix timestamp of the creation of the entry, unknown for the entries created before
+ALTER TABLE metadata ADD COLUMN created_at INTEGER;
@@ -0,0 +1,14 @@
+-- Song released in several versions (original, extended, radio edit, remixes ...)
+CREATE TABLE work (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    artist TEXT NOT NULL,
+    title TEXT NOT NULL
+);
+
+CREATE TABLE metadata_work (
+    metadata_id INTEGER PRIMARY KEY NOT NULL,
+    work_id INTEGER NOT NULL,
+    version TEXT NOT NULL,
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id),
+    FOREIGN KEY (work_id) REFERENCES work(id)
+);
@@ -0,0 +1,6 @@
+-- Failed tasks are retried with a backoff until they run out of attempts and stay in 'failed' status
+ALTER TABLE task ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE task ADD COLUMN max_attempts INTEGER NOT NULL DEFAULT 3;
+ALTER TABLE task ADD COLUMN last_error TEXT;
+-- Unix timestamp before which a queued task is not dequeued
+ALTER TABLE task ADD COLUMN next_run_at INTEGER;
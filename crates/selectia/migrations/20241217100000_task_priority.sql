@@ -0,0 +1,11 @@
+-- Queued tasks are processed by decreasing priority, then in insertion order
+ALTER TABLE task ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+
+-- A task isn't dequeued while it depends on a task still in the queue
+CREATE TABLE task_dependency (
+    task_id INTEGER NOT NULL,
+    dependency_id INTEGER NOT NULL,
+    PRIMARY KEY (task_id, dependency_id),
+    FOREIGN KEY (task_id) REFERENCES task(id) ON DELETE CASCADE,
+    FOREIGN KEY (dependency_id) REFERENCES task(id) ON DELETE CASCADE
+);
@@ -0,0 +1,11 @@
+-- Resource mostly used by a task, the worker limits how many tasks of each class run at the same time
+ALTER TABLE task ADD COLUMN resource_class TEXT NOT NULL DEFAULT 'analysis';
+
+UPDATE task SET resource_class = CASE (SELECT key FROM json_each(task.payload) LIMIT 1)
+    WHEN 'FileAnalysis' THEN 'separation'
+    WHEN 'StemExtraction' THEN 'separation'
+    WHEN 'FolderExport' THEN 'io'
+    WHEN 'VerifyLibrary' THEN 'io'
+    ELSE 'analysis'
+END
+WHERE json_valid(payload);
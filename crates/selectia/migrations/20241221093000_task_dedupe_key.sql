@@ -0,0 +1,3 @@
+-- Tasks with the same key do the same work, a new task isn't queued while one is queued or processing
+ALTER TABLE task ADD COLUMN dedupe_key TEXT;
+CREATE INDEX task_dedupe_key ON task (dedupe_key);
@@ -0,0 +1,19 @@
+-- Stages of the analysis pipeline done for a content, they are not run again for files with the same hash
+CREATE TABLE analysis_stage (
+    hash TEXT NOT NULL,
+    stage TEXT NOT NULL,
+    completed_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    PRIMARY KEY (hash, stage)
+);
+
+-- Results of the analysis pipeline that are not stored as tags or beat grid
+CREATE TABLE metadata_analysis (
+    metadata_id INTEGER PRIMARY KEY NOT NULL,
+    -- Peaks from 0 to 255, see `waveform_preview`
+    waveform BLOB,
+    loudness REAL,
+    peak REAL,
+    grid_confidence REAL,
+    key_confidence REAL,
+    FOREIGN KEY (metadata_id) REFERENCES metadata(id) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+-- Jobs the worker enqueues periodically, on a cron expression in local time or every `interval_seconds`
+CREATE TABLE schedule (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    payload TEXT NOT NULL,
+    cron TEXT,
+    interval_seconds INTEGER,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_run_at INTEGER,
+    -- Moved forward when the job is enqueued so it is not enqueued again after a restart
+    next_run_at INTEGER NOT NULL,
+    CHECK ((cron IS NULL) != (interval_seconds IS NULL))
+);
@@ -0,0 +1,24 @@
+-- Runs of the tasks that left the worker pool, kept after the task itself is deleted
+CREATE TABLE task_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    task_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    -- done, failed or cancelled
+    status TEXT NOT NULL,
+    priority INTEGER NOT NULL,
+    resource_class TEXT NOT NULL,
+    -- 1 for the first run of the task, increased on each retry
+    attempt INTEGER NOT NULL,
+    worker_slot INTEGER NOT NULL,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    -- Error chain of a failed run
+    error TEXT,
+    -- JSON outcome of the task, i.e the counts of a library verification
+    summary TEXT
+);
+
+CREATE INDEX task_history_finished_at ON task_history (finished_at);
+CREATE INDEX task_history_name ON task_history (name, finished_at);
@@ -2,6 +2,7 @@
 //! It is not able to find the onesets events but instead normalize the onesets events to a BPM value with a certain confidence as well as a well placed BPM grid
 #![allow(dead_code)]
 use crate::prelude::*;
+use eyre::bail;
 use std::collections::BTreeMap;
 
 use ndarray::Array1;
@@ -19,6 +20,8 @@ pub struct BpmAnalyser {
 #[derive(Debug)]
 pub struct BpmAnalyserResult {
     pub average_bpm: f64,
+    /// Ratio of the onsets intervals agreeing with the average
+    pub confidence: f64,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -36,6 +39,9 @@ impl BpmAnalyser {
     pub fn get_result(&self) -> Result<BpmAnalyserResult> {
         let diffs: Array1<f64> = Array1::from(self.onesets.windows(2).map(|w| w[1].seconds - w[0].seconds).collect::<Vec<f64>>());
         let bpms: Array1<f64> = diffs.mapv(|period| 60.0 / period);
+        if bpms.is_empty() {
+            bail!("Not enough onsets to detect the BPM");
+        }
 
         // dbg!(&bpms);
         let bpms_variations = bpms.windows(2).into_iter().map(|w| w[1] - w[0]).collect::<Vec<f64>>();
@@ -47,6 +53,9 @@ impl BpmAnalyser {
         let filtered_bpm = bpms.into_iter().filter((|x| {
             (median_unfiltered - x).abs() < 15.0
         })).collect::<Vec<_>>();
+        if filtered_bpm.is_empty() {
+            bail!("Onsets too irregular to detect the BPM");
+        }
 
 
         
@@ -54,6 +63,9 @@ impl BpmAnalyser {
         let filtered_bpm = filtered_bpm.into_iter().filter((|x| {
             (median_unfiltered - x).abs() < 4.0
         })).collect::<Vec<_>>();
+        if filtered_bpm.is_empty() {
+            bail!("Onsets too irregular to detect the BPM");
+        }
 
 
         
@@ -68,6 +80,7 @@ impl BpmAnalyser {
 
         Ok(BpmAnalyserResult {
             average_bpm: average,
+            confidence: filtered_bpm.len() as f64 / nbr_oneset_before as f64,
         })
     }
 
@@ -0,0 +1,128 @@
+//! Acoustic fingerprint built from the chroma (energy per pitch class) of the decoded audio,
+//! it survives re-encoding, re-tagging and volume changes unlike the file hash
+use microfft::real::rfft_4096;
+
+/// Sample rate the audio is resampled to before the analysis
+pub const SAMPLE_RATE: f64 = 11025.0;
+/// Seconds of audio fingerprinted from the start of the track
+pub const MAX_DURATION: f64 = 120.0;
+
+const FRAME_SIZE: usize = 4096;
+const HOP_SIZE: usize = 2048;
+const MIN_FREQUENCY: f32 = 55.0;
+const MAX_FREQUENCY: f32 = 3520.0;
+/// Each code holds 12 bits comparing neighbour pitch classes and 12 bits comparing with the previous frame
+const CODE_BITS: u32 = 24;
+/// Frames the fingerprints may be shifted by when compared, to absorb encoder delays and trimmed silences
+const MAX_OFFSET: usize = 32;
+const MIN_OVERLAP: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChromaFingerprint {
+    /// Duration of the whole track in seconds
+    pub duration: f64,
+    /// One code per frame of `HOP_SIZE` samples
+    pub codes: Vec<u32>,
+}
+
+impl ChromaFingerprint {
+    /// `samples` are mono at `SAMPLE_RATE`
+    pub fn from_samples(duration: f64, samples: &[f32]) -> Self {
+        let codes = chroma_frames(samples)
+            .windows(2)
+            .map(|frames| {
+                let (previous, chroma) = (frames[0], frames[1]);
+                let mut code = 0u32;
+                for idx in 0..12 {
+                    if chroma[idx] > chroma[(idx + 1) % 12] {
+                        code |= 1 << idx;
+                    }
+                    if chroma[idx] > previous[idx] {
+                        code |= 1 << (12 + idx);
+                    }
+                }
+                code
+            })
+            .collect();
+        Self { duration, codes }
+    }
+
+    /// Ratio of matching bits at the best alignment, from about 0.5 for unrelated tracks to 1
+    pub fn similarity(&self, other: &ChromaFingerprint) -> f64 {
+        let mut best = 0.0;
+        for offset in 0..=MAX_OFFSET {
+            for (a, b) in [(&self.codes, &other.codes), (&other.codes, &self.codes)] {
+                let Some(shifted) = a.get(offset..) else {
+                    continue;
+                };
+                let overlap = shifted.len().min(b.len());
+                if overlap < MIN_OVERLAP {
+                    continue;
+                }
+                let different_bits = shifted
+                    .iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| (a ^ b).count_ones())
+                    .sum::<u32>();
+                let similarity = 1.0 - different_bits as f64 / (overlap as u32 * CODE_BITS) as f64;
+                if similarity > best {
+                    best = similarity;
+                }
+            }
+        }
+        best
+    }
+
+    pub fn codes_to_bytes(&self) -> Vec<u8> {
+        self.codes.iter().flat_map(|code| code.to_le_bytes()).collect()
+    }
+
+    pub fn from_bytes(duration: f64, bytes: &[u8]) -> Self {
+        let codes = bytes
+            .chunks_exact(4)
+            .map(|code| u32::from_le_bytes([code[0], code[1], code[2], code[3]]))
+            .collect();
+        Self { duration, codes }
+    }
+}
+
+/// Normalized energy of each pitch class (C first) per frame of `HOP_SIZE` samples,
+/// `samples` are mono at `SAMPLE_RATE`
+pub fn chroma_frames(samples: &[f32]) -> Vec<[f32; 12]> {
+    let window = (0..FRAME_SIZE)
+        .map(|idx| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * idx as f32 / FRAME_SIZE as f32).cos())
+        .collect::<Vec<_>>();
+    let pitch_classes = (0..FRAME_SIZE / 2)
+        .map(|bin| {
+            let frequency = bin as f32 * SAMPLE_RATE as f32 / FRAME_SIZE as f32;
+            if !(MIN_FREQUENCY..=MAX_FREQUENCY).contains(&frequency) {
+                return None;
+            }
+            let note = (12.0 * (frequency / 440.0).log2()).round() as i32 + 69;
+            Some(note.rem_euclid(12) as usize)
+        })
+        .collect::<Vec<_>>();
+
+    let mut frame = [0f32; FRAME_SIZE];
+    let mut frames = vec![];
+    let mut start = 0;
+    while start + FRAME_SIZE <= samples.len() {
+        for (idx, sample) in samples[start..start + FRAME_SIZE].iter().enumerate() {
+            frame[idx] = sample * window[idx];
+        }
+        let spectrum = rfft_4096(&mut frame);
+        let mut chroma = [0f32; 12];
+        for (bin, value) in spectrum.iter().enumerate() {
+            if let Some(pitch_class) = pitch_classes[bin] {
+                chroma[pitch_class] += value.norm_sqr();
+            }
+        }
+        let total = chroma.iter().sum::<f32>();
+        if total > 0.0 {
+            chroma.iter_mut().for_each(|value| *value /= total);
+        }
+        frames.push(chroma);
+        start += HOP_SIZE;
+    }
+    frames
+}
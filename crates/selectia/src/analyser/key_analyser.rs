@@ -0,0 +1,65 @@
+//! Musical key estimated by correlating the average chroma of a track with the Krumhansl-Kessler key profiles
+use super::chroma_fingerprint::chroma_frames;
+
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+const PITCH_CLASSES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyAnalysis {
+    /// Pitch class of the tonic, C being 0
+    pub tonic: usize,
+    pub minor: bool,
+    /// Correlation of the chroma with the profile of the key, from -1 to 1
+    pub confidence: f64,
+}
+
+impl KeyAnalysis {
+    /// `samples` are mono at `chroma_fingerprint::SAMPLE_RATE`, `None` for silent or too short tracks
+    pub fn from_samples(samples: &[f32]) -> Option<Self> {
+        let frames = chroma_frames(samples);
+        let mut chroma = [0f32; 12];
+        for frame in frames.iter() {
+            for (idx, value) in frame.iter().enumerate() {
+                chroma[idx] += value;
+            }
+        }
+        if chroma.iter().all(|value| *value == 0.0) {
+            return None;
+        }
+        (0..12)
+            .flat_map(|tonic| [(tonic, false), (tonic, true)])
+            .map(|(tonic, minor)| {
+                let profile = if minor { &MINOR_PROFILE } else { &MAJOR_PROFILE };
+                let rotated = (0..12).map(|idx| profile[(idx + 12 - tonic) % 12]).collect::<Vec<_>>();
+                KeyAnalysis {
+                    tonic,
+                    minor,
+                    confidence: correlation(&chroma, &rotated),
+                }
+            })
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+    }
+
+    /// Name of the key, i.e `Am` or `F#`
+    pub fn name(&self) -> String {
+        let suffix = if self.minor { "m" } else { "" };
+        format!("{}{}", PITCH_CLASSES[self.tonic], suffix)
+    }
+}
+
+fn correlation(a: &[f32], b: &[f32]) -> f64 {
+    let mean_a = a.iter().sum::<f32>() as f64 / a.len() as f64;
+    let mean_b = b.iter().sum::<f32>() as f64 / b.len() as f64;
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (a, b) in a.iter().zip(b.iter()) {
+        let (a, b) = (*a as f64 - mean_a, *b as f64 - mean_b);
+        covariance += a * b;
+        variance_a += a * a;
+        variance_b += b * b;
+    }
+    if variance_a == 0.0 || variance_b == 0.0 {
+        return 0.0;
+    }
+    covariance / (variance_a * variance_b).sqrt()
+}
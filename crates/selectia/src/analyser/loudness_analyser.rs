@@ -0,0 +1,48 @@
+//! Integrated loudness gated as in ITU-R BS.1770, without the K-weighting filter so it is only an estimate of the LUFS value
+const BLOCK_DURATION: f64 = 0.4;
+const BLOCK_OVERLAP: usize = 4;
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnessAnalysis {
+    /// Loudness in dB relative to full scale
+    pub integrated: f64,
+    /// Highest sample in dB relative to full scale
+    pub peak: f64,
+}
+
+impl LoudnessAnalysis {
+    /// `samples` are mono, `None` for silent or too short tracks
+    pub fn from_samples(sample_rate: f64, samples: &[f32]) -> Option<Self> {
+        let block_size = (BLOCK_DURATION * sample_rate) as usize;
+        let hop_size = block_size / BLOCK_OVERLAP;
+        if hop_size == 0 || samples.len() < block_size {
+            return None;
+        }
+        let blocks = (0..=(samples.len() - block_size) / hop_size)
+            .map(|idx| {
+                let block = &samples[idx * hop_size..idx * hop_size + block_size];
+                block.iter().map(|sample| (*sample as f64).powi(2)).sum::<f64>() / block_size as f64
+            })
+            .filter(|power| loudness(*power) > ABSOLUTE_GATE)
+            .collect::<Vec<_>>();
+        if blocks.is_empty() {
+            return None;
+        }
+        let threshold = loudness(blocks.iter().sum::<f64>() / blocks.len() as f64) + RELATIVE_GATE;
+        let gated = blocks
+            .into_iter()
+            .filter(|power| loudness(*power) > threshold)
+            .collect::<Vec<_>>();
+        let peak = samples.iter().fold(0f32, |peak, sample| peak.max(sample.abs()));
+        Some(Self {
+            integrated: loudness(gated.iter().sum::<f64>() / gated.len() as f64),
+            peak: 20.0 * (peak as f64).log10(),
+        })
+    }
+}
+
+fn loudness(power: f64) -> f64 {
+    10.0 * power.log10()
+}
@@ -1,2 +1,7 @@
+pub mod chroma_fingerprint;
 pub mod entries_analyser;
 pub mod bpm_analyser;
+pub mod key_analyser;
+pub mod loudness_analyser;
+pub mod path_pattern_analyser;
+pub mod waveform_preview;
@@ -0,0 +1,170 @@
+//! Extract tags from a file path using user defined templates such as `{artist} - {title} ({remix})`.
+//! A template containing `/` is matched against the last path components (directories then file name without extension),
+//! otherwise it is matched against the file name only.
+//! Placeholders are named after the tag they produce (`{bpm}` being an alias of `tempo`), `{_}` matches anything without producing a tag.
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    Placeholder(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct PathPattern {
+    template: String,
+    components: Vec<Vec<Token>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractedTag {
+    /// Name of the tag (i.e lookup in the `tag_name` table)
+    pub name: String,
+    pub value: String,
+}
+
+impl PathPattern {
+    pub const IGNORED_PLACEHOLDER: &str = "_";
+
+    pub fn parse(template: &str) -> Result<Self> {
+        let components = template
+            .trim_matches('/')
+            .split('/')
+            .map(parse_component)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            template: template.to_string(),
+            components,
+        })
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Tag names produced by the template
+    pub fn tag_names(&self) -> impl Iterator<Item = &str> {
+        self.components.iter().flatten().filter_map(|token| match token {
+            Token::Placeholder(name) if name != Self::IGNORED_PLACEHOLDER => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the extracted tags or `None` if the path does not match the template
+    pub fn extract(&self, path: &Path) -> Option<Vec<ExtractedTag>> {
+        let mut segments = vec![path.file_stem()?.to_string_lossy().to_string()];
+        let mut parent = path.parent();
+        while segments.len() < self.components.len() {
+            let directory = parent?;
+            segments.push(directory.file_name()?.to_string_lossy().to_string());
+            parent = directory.parent();
+        }
+        segments.reverse();
+
+        let mut tags = vec![];
+        for (tokens, segment) in self.components.iter().zip(segments.iter()) {
+            let mut captures = vec![];
+            if !match_tokens(tokens, segment, &mut captures) {
+                return None;
+            }
+            tags.extend(
+                captures
+                    .into_iter()
+                    .filter(|(name, _)| *name != Self::IGNORED_PLACEHOLDER)
+                    .map(|(name, value)| ExtractedTag {
+                        name: name.to_string(),
+                        value: value.trim().to_string(),
+                    }),
+            );
+        }
+        Some(tags)
+    }
+}
+
+fn parse_component(component: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut rest = component;
+    while !rest.is_empty() {
+        match rest.find('{') {
+            Some(0) => {
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| eyre!("Unclosed placeholder in `{}`", component))?;
+                let name = rest[1..end].trim();
+                if name.is_empty() || name.contains('{') {
+                    return Err(eyre!("Invalid placeholder in `{}`", component));
+                }
+                if let Some(Token::Placeholder(_)) = tokens.last() {
+                    return Err(eyre!(
+                        "Placeholders must be separated by a literal in `{}`",
+                        component
+                    ));
+                }
+                let name = match name {
+                    "bpm" => "tempo",
+                    name => name,
+                };
+                tokens.push(Token::Placeholder(name.to_string()));
+                rest = &rest[end + 1..];
+            }
+            Some(start) => {
+                if rest[..start].contains('}') {
+                    return Err(eyre!("Unopened placeholder in `{}`", component));
+                }
+                tokens.push(Token::Literal(rest[..start].to_string()));
+                rest = &rest[start..];
+            }
+            None => {
+                if rest.contains('}') {
+                    return Err(eyre!("Unopened placeholder in `{}`", component));
+                }
+                tokens.push(Token::Literal(rest.to_string()));
+                rest = "";
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Match the tokens against the input, placeholders are lazy and must capture at least one character
+fn match_tokens<'a>(tokens: &'a [Token], input: &str, captures: &mut Vec<(&'a str, String)>) -> bool {
+    match tokens.split_first() {
+        None => input.is_empty(),
+        Some((Token::Literal(literal), rest)) => input
+            .strip_prefix(literal.as_str())
+            .is_some_and(|input| match_tokens(rest, input, captures)),
+        Some((Token::Placeholder(name), rest)) => {
+            for (idx, _) in input.char_indices().skip(1).chain([(input.len(), ' ')]) {
+                let (value, remaining) = input.split_at(idx);
+                if value.trim().is_empty() {
+                    continue;
+                }
+                let mark = captures.len();
+                captures.push((name.as_str(), value.to_string()));
+                if match_tokens(rest, remaining, captures) {
+                    return true;
+                }
+                captures.truncate(mark);
+            }
+            false
+        }
+    }
+}
+
+/// A set of patterns tried in order, the first matching pattern wins
+pub struct PathPatternAnalyser {
+    patterns: Vec<(i64, PathPattern)>,
+}
+
+impl PathPatternAnalyser {
+    pub fn new(patterns: Vec<(i64, PathPattern)>) -> Self {
+        Self { patterns }
+    }
+
+    /// Returns the id of the matching pattern along with the extracted tags
+    pub fn extract(&self, path: &Path) -> Option<(i64, Vec<ExtractedTag>)> {
+        self.patterns
+            .iter()
+            .find_map(|(id, pattern)| pattern.extract(path).map(|tags| (*id, tags)))
+    }
+}
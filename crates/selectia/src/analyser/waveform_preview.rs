@@ -0,0 +1,26 @@
+//! Overview of a track drawn by the UI before its audio is decoded
+
+/// Peaks of the preview, enough for a full width display
+pub const WAVEFORM_PREVIEW_SIZE: usize = 1024;
+
+/// Peak of each of the `size` slices of `samples`, scaled from 0 to 255
+pub fn waveform_preview(samples: &[f32], size: usize) -> Vec<u8> {
+    if samples.is_empty() || size == 0 {
+        return vec![];
+    }
+    let peaks = (0..size)
+        .map(|idx| {
+            let start = idx * samples.len() / size;
+            let end = ((idx + 1) * samples.len() / size).max(start + 1).min(samples.len());
+            samples[start..end].iter().fold(0f32, |peak, sample| peak.max(sample.abs()))
+        })
+        .collect::<Vec<_>>();
+    let max = peaks.iter().copied().fold(0f32, f32::max);
+    if max == 0.0 {
+        return vec![0; size];
+    }
+    peaks
+        .into_iter()
+        .map(|peak| (peak / max * 255.0).round() as u8)
+        .collect()
+}
@@ -1,4 +1,4 @@
-use models::FileVariationMetadata;
+use models::{FileVariationMetadata, ImportRules};
 
 use crate::prelude::*;
 use crate::views::entry_view::*;
@@ -37,7 +37,7 @@ impl Database {
     pub async fn get_or_create_metadata(&self, hash: &str) -> Result<(models::Metadata, bool)> {
         let metadata = sqlx::query_as!(
             models::Metadata,
-            "SELECT * FROM metadata WHERE hash = ?",
+            "SELECT * FROM metadata WHERE hash = ? ORDER BY id ASC LIMIT 1",
             hash
         )
         .fetch_optional(&self.pool)
@@ -48,7 +48,7 @@ impl Database {
                 info!("Creating metadata for {}", hash);
                 let metadata = sqlx::query_as!(
                     models::Metadata,
-                    "INSERT INTO metadata (hash) VALUES (?) RETURNING *",
+                    "INSERT INTO metadata (hash, created_at) VALUES (?, unixepoch()) RETURNING *",
                     hash
                 )
                 .fetch_one(&self.pool)
@@ -58,6 +58,91 @@ impl Database {
         }
     }
 
+    /// Metadata of the file at `path` with content `hash`, following the merges and splits done by hand
+    pub async fn find_metadata(&self, path: &Path, hash: &str) -> Result<Option<models::Metadata>> {
+        let path_str = path.to_str().unwrap();
+        let pinned = sqlx::query_as!(
+            models::Metadata,
+            "SELECT metadata.* FROM file_pin JOIN metadata ON metadata.id = file_pin.metadata_id WHERE file_pin.path = ? AND file_pin.hash = ?",
+            path_str,
+            hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if pinned.is_some() {
+            return Ok(pinned);
+        }
+
+        let aliased = sqlx::query_as!(
+            models::Metadata,
+            "SELECT metadata.* FROM metadata_alias JOIN metadata ON metadata.id = metadata_alias.metadata_id WHERE metadata_alias.hash = ?",
+            hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        match aliased {
+            Some(metadata) => Ok(Some(metadata)),
+            None => self.get_metadata_by_hash(hash).await,
+        }
+    }
+
+    /// Forget the pin of the file at `path` once its content changed, returns whether there was one
+    pub async fn delete_stale_file_pin(&self, path: &Path, hash: &str) -> Result<bool> {
+        let path_str = path.to_str().unwrap();
+        let result = sqlx::query!("DELETE FROM file_pin WHERE path = ? AND hash != ?", path_str, hash)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Keep the file at `path` on the metadata while its content is `hash`
+    pub async fn set_file_pin(&self, path: &Path, hash: &str, metadata_id: i64) -> Result<()> {
+        let path_str = path.to_str().unwrap();
+        sqlx::query!(
+            "INSERT INTO file_pin (path, hash, metadata_id) VALUES (?, ?, ?) ON CONFLICT(path) DO UPDATE SET hash = excluded.hash, metadata_id = excluded.metadata_id",
+            path_str,
+            hash,
+            metadata_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Hash the file at `path` was pinned with
+    pub async fn get_file_pin_hash(&self, path: &Path) -> Result<Option<String>> {
+        let path_str = path.to_str().unwrap();
+        let hash = sqlx::query_scalar!("SELECT hash FROM file_pin WHERE path = ?", path_str)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(hash)
+    }
+
+    pub async fn get_metadata(&self, id: i64) -> Result<models::Metadata> {
+        let metadata = sqlx::query_as!(models::Metadata, "SELECT * FROM metadata WHERE id = ?", id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(metadata)
+    }
+
+    pub async fn set_metadata_duration(&self, id: i64, duration: f64) -> Result<()> {
+        sqlx::query!("UPDATE metadata SET duration = ? WHERE id = ?", duration, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_metadata_by_hash(&self, hash: &str) -> Result<Option<models::Metadata>> {
+        let metadata = sqlx::query_as!(
+            models::Metadata,
+            "SELECT * FROM metadata WHERE hash = ? ORDER BY id ASC LIMIT 1",
+            hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(metadata)
+    }
+
     pub async fn create_or_replace_file(
         &self,
         path: &Path,
@@ -93,7 +178,13 @@ impl Database {
         tag_name_id: i64,
         value: String,
     ) -> Result<()> {
-        let existing_tag = match sqlx::query_scalar!(
+        let existing_tag = self.get_or_create_tag(tag_name_id, value).await?;
+        self.set_metadata_tag(metadata_id, existing_tag).await?;
+        Ok(())
+    }
+
+    pub async fn get_or_create_tag(&self, tag_name_id: i64, value: String) -> Result<i64> {
+        let tag_id = match sqlx::query_scalar!(
             "SELECT id FROM tag WHERE name_id = ? AND value = ?",
             tag_name_id,
             value
@@ -112,7 +203,24 @@ impl Database {
                 .await?
             }
         };
-        self.set_metadata_tag(metadata_id, existing_tag).await?;
+        Ok(tag_id)
+    }
+
+    /// Bind a tag to a metadata keeping track of where the tag comes from, does nothing if the tag is already bound
+    pub async fn set_metadata_tag_with_source(
+        &self,
+        metadata_id: i64,
+        tag_id: i64,
+        source: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO metadata_tag (metadata_id, tag_id, source) VALUES (?, ?, ?) ON CONFLICT(metadata_id, tag_id) DO NOTHING",
+            metadata_id,
+            tag_id,
+            source
+        )
+        .execute(&self.pool)
+        .await?;
         Ok(())
     }
 
@@ -133,7 +241,7 @@ impl Database {
     ///  TODO: reduce number of queries
     pub async fn set_metadata_tag(&self, metadata_id: i64, tag_id: i64) -> Result<()> {
         sqlx::query!(
-            "INSERT INTO metadata_tag (metadata_id, tag_id) VALUES (?, ?)",
+            "INSERT INTO metadata_tag (metadata_id, tag_id) VALUES (?, ?) ON CONFLICT(metadata_id, tag_id) DO NOTHING",
             metadata_id,
             tag_id
         )
@@ -189,15 +297,131 @@ impl Database {
     }
 
     pub async fn create_task(&self, payload: String) -> Result<i64> {
+        self.create_task_with_dependencies(payload, None, "analysis", 0, &[]).await
+    }
+
+    /// Create a task that isn't dequeued before `dependencies` left the queue,
+    /// dependencies already done are ignored
+    pub async fn create_task_with_dependencies(
+        &self,
+        payload: String,
+        dedupe_key: Option<&str>,
+        resource_class: &str,
+        priority: i64,
+        dependencies: &[i64],
+    ) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
         let task = sqlx::query_scalar!(
-            "INSERT INTO task (payload) VALUES (?) RETURNING id",
-            payload
+            "INSERT INTO task (payload, dedupe_key, resource_class, priority) VALUES (?, ?, ?, ?) RETURNING id",
+            payload,
+            dedupe_key,
+            resource_class,
+            priority
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
+        .await?;
+        for dependency_id in dependencies {
+            sqlx::query!(
+                "INSERT OR IGNORE INTO task_dependency (task_id, dependency_id) SELECT ?, id FROM task WHERE id = ?",
+                task,
+                dependency_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(task)
+    }
+
+    /// Latest queued or processing task with `dedupe_key`
+    pub async fn get_active_task_by_dedupe_key(&self, dedupe_key: &str) -> Result<Option<models::Task>> {
+        let task = sqlx::query_as!(
+            models::Task,
+            "SELECT * FROM task WHERE dedupe_key = ? AND status IN ('queued', 'processing') ORDER BY id DESC LIMIT 1",
+            dedupe_key
+        )
+        .fetch_optional(&self.pool)
         .await?;
         Ok(task)
     }
 
+    /// Replace task `id` by `by`: the tasks depending on it wait for `by` instead,
+    /// and it is deleted unless it is processing
+    pub async fn supersede_task(&self, id: i64, by: i64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "UPDATE OR IGNORE task_dependency SET dependency_id = ? WHERE dependency_id = ? AND task_id != ?",
+            by,
+            id,
+            by
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM task WHERE id = ? AND status IN ('queued', 'failed')", id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Raise the priority of the queued task `id` to at least `priority` and make it wait for `dependencies` too,
+    /// the tasks already waiting for it are skipped
+    pub async fn merge_queued_task(&self, id: i64, priority: i64, dependencies: &[i64]) -> Result<()> {
+        let dependents = self.get_task_dependents(id).await?;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "UPDATE task SET priority = MAX(priority, ?) WHERE id = ? AND status = 'queued'",
+            priority,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        for dependency_id in dependencies {
+            if *dependency_id == id || dependents.contains(dependency_id) {
+                continue;
+            }
+            sqlx::query!(
+                "INSERT OR IGNORE INTO task_dependency (task_id, dependency_id) SELECT ?, id FROM task WHERE id = ?",
+                id,
+                dependency_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Tasks waiting for `id`, directly or through other tasks
+    pub async fn get_task_dependents(&self, id: i64) -> Result<Vec<i64>> {
+        let dependents = sqlx::query_scalar!(
+            r#"
+            WITH RECURSIVE dependent(id) AS (
+                SELECT task_id FROM task_dependency WHERE dependency_id = ?
+                UNION
+                SELECT task_dependency.task_id FROM task_dependency
+                JOIN dependent ON task_dependency.dependency_id = dependent.id
+            )
+            SELECT id AS "id!: i64" FROM dependent ORDER BY id
+        "#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(dependents)
+    }
+
+    /// Tasks `id` waits for
+    pub async fn get_task_dependencies(&self, id: i64) -> Result<Vec<i64>> {
+        let dependencies = sqlx::query_scalar!(
+            "SELECT dependency_id FROM task_dependency WHERE task_id = ? ORDER BY dependency_id",
+            id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(dependencies)
+    }
+
     pub async fn get_task(&self, id: i64) -> Result<models::Task> {
         let task = sqlx::query_as!(models::Task, "SELECT * FROM task WHERE id = ?", id)
             .fetch_one(&self.pool)
@@ -220,20 +444,358 @@ impl Database {
     }
 
     /// Dequeue a task from the queue
-    /// This function will set the status of the task first task with queued status to processing
+    /// This function will set the status of the task first task with queued status to processing,
+    /// by decreasing priority then insertion order.
+    /// Tasks waiting for a retry are skipped until their `next_run_at`,
+    /// tasks with dependencies until all of them left the queue
     ///
     /// Returns the task or none if no task is in queued status
     pub async fn dequeue_task(&self) -> Result<Option<models::Task>> {
+        self.dequeue_task_excluding(&[]).await
+    }
+
+    /// Dequeue a task which resource class is not in `resource_classes`, see `dequeue_task`
+    pub async fn dequeue_task_excluding(&self, resource_classes: &[&str]) -> Result<Option<models::Task>> {
+        let excluded = serde_json::to_string(resource_classes)?;
         let task = sqlx::query_as!(models::Task, r#"
             UPDATE task SET status = 'processing'
-            WHERE status = 'queued' AND id = (SELECT id FROM task WHERE status = 'queued' ORDER BY id ASC LIMIT 1)
+            WHERE status = 'queued' AND id = (
+                SELECT id FROM task
+                WHERE status = 'queued' AND (next_run_at IS NULL OR next_run_at <= unixepoch())
+                    AND NOT EXISTS (SELECT 1 FROM task_dependency WHERE task_dependency.task_id = task.id)
+                    AND resource_class NOT IN (SELECT value FROM json_each(?))
+                ORDER BY priority DESC, id ASC LIMIT 1
+            )
             RETURNING *
-        "#)
+        "#, excluded)
             .fetch_optional(&self.pool)
             .await?;
         Ok(task)
     }
 
+    /// Record a failed attempt of a processing task
+    /// The task goes back in the queue after `retry_delay * 2^attempts` seconds (at most `max_retry_delay`)
+    /// or stays in failed status once it ran out of attempts, the tasks waiting for it then fail too
+    pub async fn fail_task(
+        &self,
+        id: i64,
+        error: &str,
+        retry_delay: i64,
+        max_retry_delay: i64,
+    ) -> Result<models::Task> {
+        let mut tx = self.pool.begin().await?;
+        let task = sqlx::query_as!(
+            models::Task,
+            r#"
+            UPDATE task SET
+                attempts = attempts + 1,
+                last_error = ?,
+                status = CASE WHEN attempts + 1 >= max_attempts THEN 'failed' ELSE 'queued' END,
+                next_run_at = CASE WHEN attempts + 1 >= max_attempts THEN NULL ELSE unixepoch() + MIN(? << attempts, ?) END
+            WHERE id = ?
+            RETURNING *
+        "#,
+            error,
+            retry_delay,
+            max_retry_delay,
+            id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if task.status == "failed" {
+            let dependency_error = format!("Dependency {} failed", id);
+            sqlx::query!(
+                r#"
+                WITH RECURSIVE dependent(id) AS (
+                    SELECT task_id FROM task_dependency WHERE dependency_id = ?
+                    UNION
+                    SELECT task_dependency.task_id FROM task_dependency
+                    JOIN dependent ON task_dependency.dependency_id = dependent.id
+                )
+                UPDATE task SET status = 'failed', last_error = ?, next_run_at = NULL
+                WHERE id IN (SELECT id FROM dependent) AND status = 'queued'
+            "#,
+                id,
+                dependency_error
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(task)
+    }
+
+    pub async fn get_failed_tasks(&self) -> Result<Vec<models::Task>> {
+        let tasks = sqlx::query_as!(models::Task, "SELECT * FROM task WHERE status = 'failed' ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(tasks)
+    }
+
+    /// Put a failed task back in the queue with a fresh set of attempts,
+    /// along with the failed tasks it waits for and the ones waiting for it
+    ///
+    /// Returns the ids put back in the queue, empty if the task is not in failed status
+    pub async fn retry_failed_task(&self, id: i64) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            WITH RECURSIVE
+                dependency(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT task_dependency.dependency_id FROM task_dependency
+                    JOIN dependency ON task_dependency.task_id = dependency.id
+                ),
+                dependent(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT task_dependency.task_id FROM task_dependency
+                    JOIN dependent ON task_dependency.dependency_id = dependent.id
+                )
+            UPDATE task SET status = 'queued', attempts = 0, next_run_at = NULL
+            WHERE status = 'failed'
+                AND EXISTS (SELECT 1 FROM task WHERE id = ?1 AND status = 'failed')
+                AND (id IN (SELECT id FROM dependency) OR id IN (SELECT id FROM dependent))
+            RETURNING id
+        "#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut ids = ids;
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Delete a task that is not being processed, with the tasks waiting for it as they would never run
+    ///
+    /// Returns the ids deleted, empty if the task doesn't exist or is processing
+    pub async fn delete_pending_task(&self, id: i64) -> Result<Vec<i64>> {
+        let task = sqlx::query_scalar!(
+            "SELECT id FROM task WHERE id = ? AND status IN ('queued', 'failed')",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        match task {
+            Some(id) => self.delete_task_with_dependents(id).await,
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Delete a task and the tasks waiting for it, i.e once it is cancelled
+    ///
+    /// Returns the ids deleted
+    pub async fn delete_task_with_dependents(&self, id: i64) -> Result<Vec<i64>> {
+        let mut tx = self.pool.begin().await?;
+        let ids = sqlx::query_scalar!(
+            r#"
+            WITH RECURSIVE dependent(id) AS (
+                SELECT ?1
+                UNION
+                SELECT task_dependency.task_id FROM task_dependency
+                JOIN dependent ON task_dependency.dependency_id = dependent.id
+            )
+            DELETE FROM task WHERE id IN (SELECT id FROM dependent)
+            RETURNING id
+        "#,
+            id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        let mut ids = ids;
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Earliest time at which a task waiting for a retry can be dequeued
+    pub async fn get_next_task_run_at(&self) -> Result<Option<i64>> {
+        let next = sqlx::query_scalar!(
+            r#"SELECT MIN(next_run_at) AS "next_run_at: i64" FROM task WHERE status = 'queued' AND next_run_at > unixepoch()"#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(next)
+    }
+
+    /// Returns `None` when a schedule with the same name exists, it is then left untouched
+    pub async fn create_schedule(
+        &self,
+        name: &str,
+        payload: &str,
+        cron: Option<&str>,
+        interval_seconds: Option<i64>,
+        next_run_at: i64,
+    ) -> Result<Option<i64>> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO schedule (name, payload, cron, interval_seconds, next_run_at) VALUES (?, ?, ?, ?, ?) ON CONFLICT(name) DO NOTHING RETURNING id",
+            name,
+            payload,
+            cron,
+            interval_seconds,
+            next_run_at
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    pub async fn get_schedules(&self) -> Result<Vec<models::Schedule>> {
+        let schedules = sqlx::query_as!(models::Schedule, "SELECT * FROM schedule ORDER BY id ASC")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(schedules)
+    }
+
+    pub async fn get_schedule(&self, id: i64) -> Result<models::Schedule> {
+        let schedule = sqlx::query_as!(models::Schedule, "SELECT * FROM schedule WHERE id = ?", id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(schedule)
+    }
+
+    /// Enabled schedules whose next run is due
+    pub async fn get_due_schedules(&self) -> Result<Vec<models::Schedule>> {
+        let schedules = sqlx::query_as!(
+            models::Schedule,
+            "SELECT * FROM schedule WHERE enabled AND next_run_at <= unixepoch() ORDER BY next_run_at ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(schedules)
+    }
+
+    /// Move the next run of the schedule forward, returns false if it was already moved since `due_at` was read
+    pub async fn claim_schedule_run(&self, id: i64, due_at: i64, next_run_at: i64) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE schedule SET last_run_at = unixepoch(), next_run_at = ? WHERE id = ? AND enabled AND next_run_at = ?",
+            next_run_at,
+            id,
+            due_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_schedule_enabled(&self, id: i64, enabled: bool, next_run_at: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE schedule SET enabled = ?, next_run_at = ? WHERE id = ?",
+            enabled,
+            next_run_at,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_next_schedule_run_at(&self) -> Result<Option<i64>> {
+        let next = sqlx::query_scalar!(
+            r#"SELECT MIN(next_run_at) AS "next_run_at: i64" FROM schedule WHERE enabled"#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(next)
+    }
+
+    /// Archive a run of a task still in the `task` table, before it is deleted or put back in the queue
+    pub async fn archive_task_run(&self, run: &models::TaskRun) -> Result<i64> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO task_history (
+                task_id, name, payload, status, priority, resource_class, attempt,
+                worker_slot, started_at, finished_at, duration_ms, error, summary
+            )
+            SELECT id, ?, payload, ?, priority, resource_class, attempts + 1, ?, ?, unixepoch(), ?, ?, ?
+            FROM task WHERE id = ?
+            RETURNING id
+        "#,
+            run.name,
+            run.status,
+            run.worker_slot,
+            run.started_at,
+            run.duration_ms,
+            run.error,
+            run.summary,
+            run.task_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Most recent runs first
+    pub async fn get_task_history(&self, query: &models::TaskHistoryQuery) -> Result<Vec<models::TaskHistory>> {
+        let limit = query.limit.unwrap_or(100);
+        let offset = query.offset.unwrap_or(0);
+        let runs = sqlx::query_as!(
+            models::TaskHistory,
+            r#"
+            SELECT * FROM task_history
+            WHERE (?1 IS NULL OR name = ?1) AND (?2 IS NULL OR status = ?2) AND (?3 IS NULL OR finished_at >= ?3)
+            ORDER BY finished_at DESC, id DESC
+            LIMIT ?4 OFFSET ?5
+        "#,
+            query.name,
+            query.status,
+            query.since,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(runs)
+    }
+
+    pub async fn get_task_history_run(&self, id: i64) -> Result<models::TaskHistory> {
+        let run = sqlx::query_as!(models::TaskHistory, "SELECT * FROM task_history WHERE id = ?", id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(run)
+    }
+
+    /// Runs of each kind of task finished at or after `since`
+    pub async fn get_task_history_stats(&self, since: Option<i64>) -> Result<Vec<models::TaskHistoryStats>> {
+        let stats = sqlx::query_as!(
+            models::TaskHistoryStats,
+            r#"
+            SELECT
+                name AS "name!",
+                COUNT(*) AS "runs!: i64",
+                SUM(status = 'failed') AS "failed!: i64",
+                SUM(status = 'cancelled') AS "cancelled!: i64",
+                AVG(CASE WHEN status = 'done' THEN duration_ms END) AS "average_duration_ms: f64",
+                MAX(CASE WHEN status = 'done' THEN duration_ms END) AS "max_duration_ms: i64"
+            FROM task_history
+            WHERE ?1 IS NULL OR finished_at >= ?1
+            GROUP BY name
+            ORDER BY name
+        "#,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(stats)
+    }
+
+    /// Delete the runs finished more than `max_age` seconds ago and the oldest ones beyond `max_runs`
+    pub async fn prune_task_history(&self, max_age: i64, max_runs: i64) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM task_history
+            WHERE finished_at < unixepoch() - ?
+                OR id NOT IN (SELECT id FROM task_history ORDER BY finished_at DESC, id DESC LIMIT ?)
+        "#,
+            max_age,
+            max_runs
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
     /// Sanitize task status to ensure that no task is in processing status
     /// This function should be called when before worker start processing tasks
     pub async fn sanitize_task_status(&self) -> Result<u64> {
@@ -254,6 +816,29 @@ impl Database {
         Ok(file)
     }
 
+    /// Oldest file of the metadata, `None` when it has no file
+    pub async fn get_first_file_from_metadata_id(&self, metadata_id: i64) -> Result<Option<models::File>> {
+        let file = sqlx::query_as!(
+            models::File,
+            "SELECT * FROM file WHERE metadata_id = ? ORDER BY id ASC LIMIT 1",
+            metadata_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(file)
+    }
+
+    pub async fn get_files_from_metadata_id(&self, metadata_id: i64) -> Result<Vec<models::File>> {
+        let files = sqlx::query_as!(
+            models::File,
+            "SELECT * FROM file WHERE metadata_id = ? ORDER BY id ASC",
+            metadata_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(files)
+    }
+
     pub async fn get_module(&self, name: &str) -> Result<models::Module> {
         let module = sqlx::query_as!(
             models::Module,
@@ -341,4 +926,1041 @@ impl Database {
             .collect();
         Ok(decoded_file_variations)
     }
+
+    pub async fn get_library_roots(&self) -> Result<Vec<models::LibraryRoot>> {
+        let roots = sqlx::query_as!(models::LibraryRoot, "SELECT * FROM library_root")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(roots)
+    }
+
+    pub async fn get_library_root(&self, id: i64) -> Result<models::LibraryRoot> {
+        let root = sqlx::query_as!(models::LibraryRoot, "SELECT * FROM library_root WHERE id = ?", id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(root)
+    }
+
+    pub async fn get_library_root_by_name(&self, name: &str) -> Result<Option<models::LibraryRoot>> {
+        let root = sqlx::query_as!(models::LibraryRoot, "SELECT * FROM library_root WHERE name = ?", name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(root)
+    }
+
+    pub async fn set_library_root_name(&self, id: i64, name: Option<&str>) -> Result<()> {
+        sqlx::query!("UPDATE library_root SET name = ? WHERE id = ?", name, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Move the files, file variations, pins, directory tags and nested roots under the root to `to`
+    ///
+    /// Paths are only rewritten, see `LibraryRelocation` to check the files exist at the new location first
+    pub async fn relocate_library_root(&self, id: i64, to: &Path) -> Result<()> {
+        let root = self.get_library_root(id).await?;
+        let mut tx = self.pool.begin().await?;
+
+        let files = sqlx::query!("SELECT id, path FROM file").fetch_all(&mut *tx).await?;
+        for file in files {
+            if let Some(path) = root.relocated_path(&file.path, to) {
+                sqlx::query!("UPDATE file SET path = ? WHERE id = ?", path, file.id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        let variations = sqlx::query!("SELECT id, path FROM file_variation")
+            .fetch_all(&mut *tx)
+            .await?;
+        for variation in variations {
+            if let Some(path) = root.relocated_path(&variation.path, to) {
+                sqlx::query!("UPDATE file_variation SET path = ? WHERE id = ?", path, variation.id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        let pins = sqlx::query_scalar!("SELECT path FROM file_pin").fetch_all(&mut *tx).await?;
+        for pin in pins {
+            if let Some(path) = root.relocated_path(&pin, to) {
+                sqlx::query!("UPDATE file_pin SET path = ? WHERE path = ?", path, pin)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        let roots = sqlx::query!("SELECT id, path FROM library_root").fetch_all(&mut *tx).await?;
+        for nested in roots {
+            if let Some(path) = root.relocated_path(&nested.path, to) {
+                sqlx::query!("UPDATE library_root SET path = ? WHERE id = ?", path, nested.id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        // A directory may already be tagged at the new location, its entries then share the existing tag
+        let directories = sqlx::query!("SELECT id, value FROM tag WHERE name_id = ?", TagName::DIRECTORY_ID)
+            .fetch_all(&mut *tx)
+            .await?;
+        for directory in directories {
+            let Some(value) = root.relocated_path(&directory.value, to) else {
+                continue;
+            };
+            let existing = sqlx::query_scalar!(
+                "SELECT id FROM tag WHERE name_id = ? AND value = ?",
+                TagName::DIRECTORY_ID,
+                value
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+            match existing {
+                Some(existing) => {
+                    sqlx::query!("UPDATE OR IGNORE metadata_tag SET tag_id = ? WHERE tag_id = ?", existing, directory.id)
+                        .execute(&mut *tx)
+                        .await?;
+                    sqlx::query!("DELETE FROM metadata_tag WHERE tag_id = ?", directory.id)
+                        .execute(&mut *tx)
+                        .await?;
+                    sqlx::query!("DELETE FROM tag WHERE id = ?", directory.id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                None => {
+                    sqlx::query!("UPDATE tag SET value = ? WHERE id = ?", value, directory.id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn list_file_variations(&self) -> Result<Vec<models::FileVariation>> {
+        let variations = sqlx::query_as!(models::FileVariation, "SELECT * FROM file_variation")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(variations)
+    }
+
+    pub async fn upsert_library_root(
+        &self,
+        path: &Path,
+        import_rules: &ImportRules,
+    ) -> Result<models::LibraryRoot> {
+        let path_str = path.to_str().unwrap();
+        let import_rules_str = serde_json::to_string(import_rules)?;
+        let root = sqlx::query_as!(
+            models::LibraryRoot,
+            "INSERT INTO library_root (path, import_rules) VALUES (?, ?) ON CONFLICT(path) DO UPDATE SET import_rules = ? RETURNING *",
+            path_str,
+            import_rules_str,
+            import_rules_str
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(root)
+    }
+
+    /// Get the import rules applying to `path`, i.e the rules of the deepest library root containing it
+    ///
+    /// Returns the default rules if `path` is not part of any library root
+    pub async fn get_import_rules(&self, path: &Path) -> Result<ImportRules> {
+        let rules = self
+            .get_library_roots()
+            .await?
+            .into_iter()
+            .filter(|root| path.starts_with(&root.path))
+            .max_by_key(|root| root.path.len())
+            .map(|root| root.import_rules())
+            .unwrap_or_default();
+        Ok(rules)
+    }
+
+    pub async fn get_tagging_rules(&self) -> Result<Vec<models::TaggingRule>> {
+        let rules = sqlx::query_as!(
+            models::TaggingRule,
+            "SELECT * FROM tagging_rule ORDER BY position ASC, id ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rules)
+    }
+
+    pub async fn create_tagging_rule(&self, template: &str) -> Result<models::TaggingRule> {
+        let rule = sqlx::query_as!(
+            models::TaggingRule,
+            "INSERT INTO tagging_rule (template, position) VALUES (?, (SELECT COALESCE(MAX(position), -1) + 1 FROM tagging_rule)) RETURNING *",
+            template
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(rule)
+    }
+
+    pub async fn set_tagging_rule_enabled(&self, id: i64, enabled: bool) -> Result<()> {
+        sqlx::query!("UPDATE tagging_rule SET enabled = ? WHERE id = ?", enabled, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_tagging_rule(&self, id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM tagging_rule WHERE id = ?", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_file(&self, id: i64) -> Result<models::File> {
+        let file = sqlx::query_as!(models::File, "SELECT * FROM file WHERE id = ?", id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(file)
+    }
+
+    pub async fn get_file_by_path(&self, path: &Path) -> Result<Option<models::File>> {
+        let path_str = path.to_str().unwrap();
+        let file = sqlx::query_as!(models::File, "SELECT * FROM file WHERE path = ?", path_str)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(file)
+    }
+
+    pub async fn get_cues(&self, metadata_id: i64) -> Result<Vec<models::Cue>> {
+        let cues = sqlx::query_as!(
+            models::Cue,
+            "SELECT * FROM cue WHERE metadata_id = ? ORDER BY start_time ASC",
+            metadata_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(cues)
+    }
+
+    /// Replace the cues of the metadata coming from `source`, cues from other sources are kept
+    pub async fn replace_cues(
+        &self,
+        metadata_id: i64,
+        source: &str,
+        cues: &[models::CuePoint],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "DELETE FROM cue WHERE metadata_id = ? AND source = ?",
+            metadata_id,
+            source
+        )
+        .execute(&mut *tx)
+        .await?;
+        for cue in cues {
+            sqlx::query!(
+                "INSERT INTO cue (metadata_id, kind, hot_cue_index, start_time, end_time, name, color, source) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                metadata_id,
+                cue.kind,
+                cue.hot_cue_index,
+                cue.start_time,
+                cue.end_time,
+                cue.name,
+                cue.color,
+                source
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_beat_grid(&self, metadata_id: i64) -> Result<Vec<models::BeatGridMarker>> {
+        let markers = sqlx::query_as!(
+            models::BeatGridMarker,
+            "SELECT * FROM beat_grid_marker WHERE metadata_id = ? ORDER BY position ASC",
+            metadata_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(markers)
+    }
+
+    /// Replace the beat grid markers of the metadata coming from `source`
+    pub async fn replace_beat_grid(
+        &self,
+        metadata_id: i64,
+        source: &str,
+        markers: &[models::TempoMarker],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "DELETE FROM beat_grid_marker WHERE metadata_id = ? AND source = ?",
+            metadata_id,
+            source
+        )
+        .execute(&mut *tx)
+        .await?;
+        for marker in markers {
+            sqlx::query!(
+                "INSERT INTO beat_grid_marker (metadata_id, position, bpm, meter, beat, source) VALUES (?, ?, ?, ?, ?, ?)",
+                metadata_id,
+                marker.position,
+                marker.bpm,
+                marker.meter,
+                marker.beat,
+                source
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_playlists(&self) -> Result<Vec<models::Playlist>> {
+        let playlists = sqlx::query_as!(
+            models::Playlist,
+            "SELECT * FROM playlist ORDER BY parent_id ASC, position ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(playlists)
+    }
+
+    /// Playlists are identified by their name among their siblings
+    pub async fn get_or_create_playlist(
+        &self,
+        parent_id: Option<i64>,
+        name: &str,
+        is_folder: bool,
+    ) -> Result<models::Playlist> {
+        let playlist = sqlx::query_as!(
+            models::Playlist,
+            "SELECT * FROM playlist WHERE parent_id IS ? AND name = ?",
+            parent_id,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(playlist) = playlist {
+            return Ok(playlist);
+        }
+        let playlist = sqlx::query_as!(
+            models::Playlist,
+            "INSERT INTO playlist (parent_id, name, is_folder, position) VALUES (?, ?, ?, (SELECT COALESCE(MAX(position), -1) + 1 FROM playlist WHERE parent_id IS ?)) RETURNING *",
+            parent_id,
+            name,
+            is_folder,
+            parent_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(playlist)
+    }
+
+    /// Metadata ids of the playlist, in playlist order
+    pub async fn get_playlist_entries(&self, playlist_id: i64) -> Result<Vec<i64>> {
+        let entries = sqlx::query_scalar!(
+            "SELECT metadata_id FROM playlist_entry WHERE playlist_id = ? ORDER BY position ASC",
+            playlist_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    pub async fn set_playlist_entries(&self, playlist_id: i64, metadata_ids: &[i64]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM playlist_entry WHERE playlist_id = ?", playlist_id)
+            .execute(&mut *tx)
+            .await?;
+        for (position, metadata_id) in metadata_ids.iter().enumerate() {
+            let position = position as i64;
+            sqlx::query!(
+                "INSERT INTO playlist_entry (playlist_id, metadata_id, position) VALUES (?, ?, ?)",
+                playlist_id,
+                metadata_id,
+                position
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_or_create_cover(
+        &self,
+        hash: &str,
+        mime_type: &str,
+        width: i64,
+        height: i64,
+    ) -> Result<models::Cover> {
+        sqlx::query!(
+            "INSERT INTO cover (hash, mime_type, width, height) VALUES (?, ?, ?, ?) ON CONFLICT(hash) DO NOTHING",
+            hash,
+            mime_type,
+            width,
+            height
+        )
+        .execute(&self.pool)
+        .await?;
+        let cover = sqlx::query_as!(models::Cover, "SELECT * FROM cover WHERE hash = ?", hash)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cover)
+    }
+
+    pub async fn get_cover_by_hash(&self, hash: &str) -> Result<Option<models::Cover>> {
+        let cover = sqlx::query_as!(models::Cover, "SELECT * FROM cover WHERE hash = ?", hash)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(cover)
+    }
+
+    pub async fn get_cover(&self, metadata_id: i64) -> Result<Option<models::Cover>> {
+        let cover = sqlx::query_as!(
+            models::Cover,
+            "SELECT cover.* FROM cover JOIN metadata_cover ON metadata_cover.cover_id = cover.id WHERE metadata_cover.metadata_id = ?",
+            metadata_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(cover)
+    }
+
+    /// `source` tells where the picture was found, such as `embedded` or the name of the picture file
+    pub async fn set_metadata_cover(&self, metadata_id: i64, cover_id: i64, source: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO metadata_cover (metadata_id, cover_id, source) VALUES (?, ?, ?) ON CONFLICT(metadata_id) DO UPDATE SET cover_id = excluded.cover_id, source = excluded.source",
+            metadata_id,
+            cover_id,
+            source
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_fingerprint(&self, metadata_id: i64, duration: f64, codes: &[u8]) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO fingerprint (metadata_id, duration, codes) VALUES (?, ?, ?) ON CONFLICT(metadata_id) DO UPDATE SET duration = excluded.duration, codes = excluded.codes",
+            metadata_id,
+            duration,
+            codes
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_fingerprints(&self) -> Result<Vec<models::Fingerprint>> {
+        let fingerprints = sqlx::query_as!(models::Fingerprint, "SELECT * FROM fingerprint ORDER BY metadata_id ASC")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(fingerprints)
+    }
+
+    /// Metadata having a file but no fingerprint yet
+    pub async fn get_unfingerprinted_metadata_ids(&self) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar!(
+            "SELECT DISTINCT file.metadata_id FROM file LEFT JOIN fingerprint ON fingerprint.metadata_id = file.metadata_id WHERE fingerprint.metadata_id IS NULL ORDER BY file.metadata_id ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    /// Stages of the analysis pipeline already done for the content `hash`
+    pub async fn get_completed_analysis_stages(&self, hash: &str) -> Result<Vec<String>> {
+        let stages = sqlx::query_scalar!("SELECT stage FROM analysis_stage WHERE hash = ?", hash)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(stages)
+    }
+
+    pub async fn complete_analysis_stage(&self, hash: &str, stage: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO analysis_stage (hash, stage) VALUES (?, ?) ON CONFLICT(hash, stage) DO UPDATE SET completed_at = excluded.completed_at",
+            hash,
+            stage
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Metadata whose grid has a confidence below `min_confidence` and was analysed at least `min_age` seconds ago
+    pub async fn get_low_confidence_grid_metadata_ids(&self, min_confidence: f64, min_age: i64) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT metadata_analysis.metadata_id FROM metadata_analysis
+            JOIN metadata ON metadata.id = metadata_analysis.metadata_id
+            JOIN analysis_stage ON analysis_stage.hash = metadata.hash AND analysis_stage.stage = 'grid'
+            WHERE metadata_analysis.grid_confidence < ? AND analysis_stage.completed_at <= unixepoch() - ?
+            ORDER BY metadata_analysis.grid_confidence ASC
+            "#,
+            min_confidence,
+            min_age
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    pub async fn get_metadata_analysis(&self, metadata_id: i64) -> Result<Option<models::MetadataAnalysis>> {
+        let analysis = sqlx::query_as!(
+            models::MetadataAnalysis,
+            "SELECT * FROM metadata_analysis WHERE metadata_id = ?",
+            metadata_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(analysis)
+    }
+
+    pub async fn set_waveform_preview(&self, metadata_id: i64, waveform: &[u8]) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO metadata_analysis (metadata_id, waveform) VALUES (?, ?) ON CONFLICT(metadata_id) DO UPDATE SET waveform = excluded.waveform",
+            metadata_id,
+            waveform
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_loudness(&self, metadata_id: i64, loudness: f64, peak: f64) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO metadata_analysis (metadata_id, loudness, peak) VALUES (?, ?, ?) ON CONFLICT(metadata_id) DO UPDATE SET loudness = excluded.loudness, peak = excluded.peak",
+            metadata_id,
+            loudness,
+            peak
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_grid_confidence(&self, metadata_id: i64, confidence: f64) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO metadata_analysis (metadata_id, grid_confidence) VALUES (?, ?) ON CONFLICT(metadata_id) DO UPDATE SET grid_confidence = excluded.grid_confidence",
+            metadata_id,
+            confidence
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_key_confidence(&self, metadata_id: i64, confidence: f64) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO metadata_analysis (metadata_id, key_confidence) VALUES (?, ?) ON CONFLICT(metadata_id) DO UPDATE SET key_confidence = excluded.key_confidence",
+            metadata_id,
+            confidence
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Replace the outcome of the previous verification
+    pub async fn replace_file_verifications(&self, verifications: &[models::FileVerification]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM file_verification")
+            .execute(&mut *tx)
+            .await?;
+        for verification in verifications {
+            sqlx::query!(
+                "INSERT INTO file_verification (file_id, status, error) VALUES (?, ?, ?)",
+                verification.file_id,
+                verification.status,
+                verification.error
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Outcome of the latest verification for the files still in the library
+    pub async fn get_file_verifications(&self) -> Result<Vec<models::FileVerification>> {
+        let verifications = sqlx::query_as!(
+            models::FileVerification,
+            "SELECT file_verification.* FROM file_verification JOIN file ON file.id = file_verification.file_id ORDER BY file_verification.file_id ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(verifications)
+    }
+
+    /// Consistent copy of the database written to `path`, which must not exist
+    pub async fn backup(&self, path: &Path) -> Result<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Rows referencing a missing row, as `(table, rowid)`, see `PRAGMA foreign_key_check`
+    pub async fn get_broken_references(&self) -> Result<Vec<(String, i64)>> {
+        let rows = sqlx::query_as::<_, (String, Option<i64>)>("SELECT \"table\", rowid FROM pragma_foreign_key_check")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(table, rowid)| rowid.map(|rowid| (table, rowid)))
+            .collect())
+    }
+
+    /// Delete the rows until no broken reference is left, the deletions may break other references
+    pub async fn delete_broken_references(&self) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("PRAGMA defer_foreign_keys = ON")
+            .execute(&mut *tx)
+            .await?;
+        let mut deleted = 0;
+        loop {
+            let rows = sqlx::query_as::<_, (String, Option<i64>)>("SELECT \"table\", rowid FROM pragma_foreign_key_check")
+                .fetch_all(&mut *tx)
+                .await?;
+            if rows.is_empty() {
+                break;
+            }
+            for (table, rowid) in rows {
+                // Table names come from the schema, tables without rowid cannot be fixed this way
+                let Some(rowid) = rowid else {
+                    return Err(eyre!("Broken reference in {} without rowid", table));
+                };
+                let result = sqlx::query(&format!("DELETE FROM \"{}\" WHERE rowid = ?", table))
+                    .bind(rowid)
+                    .execute(&mut *tx)
+                    .await?;
+                deleted += result.rows_affected();
+            }
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Tags no metadata uses anymore
+    pub async fn get_unused_tag_ids(&self) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar!(
+            "SELECT id FROM tag WHERE NOT EXISTS (SELECT 1 FROM metadata_tag WHERE metadata_tag.tag_id = tag.id) ORDER BY id ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    pub async fn delete_unused_tag(&self, id: i64) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM tag WHERE id = ? AND NOT EXISTS (SELECT 1 FROM metadata_tag WHERE metadata_tag.tag_id = tag.id)",
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Metadata none of the files resolves to anymore
+    pub async fn get_metadata_ids_without_file(&self) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar!(
+            "SELECT id FROM metadata WHERE NOT EXISTS (SELECT 1 FROM file WHERE file.metadata_id = metadata.id) ORDER BY id ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    pub async fn delete_file_variation(&self, id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM file_variation WHERE id = ?", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Put a task left in processing status back in the queue
+    pub async fn requeue_task(&self, id: i64) -> Result<()> {
+        sqlx::query!("UPDATE task SET status = 'queued' WHERE id = ? AND status = 'processing'", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Entries having a file as `(count, total duration, count without duration, count with a beat grid)`
+    pub async fn get_entry_totals(&self) -> Result<(i64, f64, i64, i64)> {
+        let totals = sqlx::query!(
+            r#"SELECT
+                COUNT(*) AS "count!: i64",
+                COALESCE(SUM(duration), 0.0) AS "duration!: f64",
+                COUNT(*) - COUNT(duration) AS "unknown_duration!: i64",
+                COUNT(*) FILTER (WHERE EXISTS (SELECT 1 FROM beat_grid_marker WHERE beat_grid_marker.metadata_id = metadata.id)) AS "analysed!: i64"
+            FROM metadata WHERE EXISTS (SELECT 1 FROM file WHERE file.metadata_id = metadata.id)"#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok((totals.count, totals.duration, totals.unknown_duration, totals.analysed))
+    }
+
+    /// Values of the tag name with their count of entries, the most used first
+    pub async fn get_tag_value_counts(&self, tag_name_id: i64, limit: i64) -> Result<Vec<(String, i64)>> {
+        let counts = sqlx::query!(
+            r#"SELECT tag.value AS "value!: String", COUNT(DISTINCT metadata_tag.metadata_id) AS "count!: i64"
+            FROM tag
+            JOIN metadata_tag ON metadata_tag.tag_id = tag.id
+            WHERE tag.name_id = ? AND EXISTS (SELECT 1 FROM file WHERE file.metadata_id = metadata_tag.metadata_id)
+            GROUP BY tag.id
+            ORDER BY 2 DESC, 1 ASC
+            LIMIT ?"#,
+            tag_name_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(counts.into_iter().map(|count| (count.value, count.count)).collect())
+    }
+
+    /// Entries per tempo range of `width` BPM as `(range start, count)`, from the tempo tag or else the beat grid
+    pub async fn get_tempo_histogram(&self, width: f64) -> Result<Vec<(f64, i64)>> {
+        let bins = sqlx::query!(
+            r#"WITH tempo AS (
+                SELECT COALESCE(
+                    (SELECT MAX(CAST(tag.value AS REAL)) FROM metadata_tag JOIN tag ON tag.id = metadata_tag.tag_id WHERE metadata_tag.metadata_id = metadata.id AND tag.name_id = ?),
+                    (SELECT bpm FROM beat_grid_marker WHERE beat_grid_marker.metadata_id = metadata.id ORDER BY position ASC LIMIT 1)
+                ) AS bpm
+                FROM metadata WHERE EXISTS (SELECT 1 FROM file WHERE file.metadata_id = metadata.id)
+            )
+            SELECT CAST(bpm / ? AS INTEGER) * ? AS "start!: f64", COUNT(*) AS "count!: i64"
+            FROM tempo WHERE bpm > 0
+            GROUP BY 1 ORDER BY 1 ASC"#,
+            TagName::TEMPO_ID,
+            width,
+            width
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(bins.into_iter().map(|bin| (bin.start, bin.count)).collect())
+    }
+
+    /// Entries created per week since `since` as `(unix timestamp of the monday, count)`
+    pub async fn get_created_per_week(&self, since: i64) -> Result<Vec<(i64, i64)>> {
+        // 1970-01-05 is the first monday after the epoch
+        let weeks = sqlx::query!(
+            r#"SELECT (created_at - 345600) / 604800 * 604800 + 345600 AS "week!: i64", COUNT(*) AS "count!: i64"
+            FROM metadata
+            WHERE created_at >= ? AND EXISTS (SELECT 1 FROM file WHERE file.metadata_id = metadata.id)
+            GROUP BY 1 ORDER BY 1 ASC"#,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(weeks.into_iter().map(|week| (week.week, week.count)).collect())
+    }
+
+    pub async fn create_work(&self, artist: &str, title: &str) -> Result<models::Work> {
+        let work = sqlx::query_as!(
+            models::Work,
+            "INSERT INTO work (artist, title) VALUES (?, ?) RETURNING *",
+            artist,
+            title
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(work)
+    }
+
+    /// Put the versions `(metadata_id, version)` in the work, leaving the works they were part of
+    pub async fn set_work_versions(&self, work_id: i64, versions: &[(i64, String)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (metadata_id, version) in versions {
+            sqlx::query!(
+                "INSERT INTO metadata_work (metadata_id, work_id, version) VALUES (?, ?, ?) ON CONFLICT(metadata_id) DO UPDATE SET work_id = excluded.work_id, version = excluded.version",
+                metadata_id,
+                work_id,
+                version
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query!(
+            "DELETE FROM metadata_work WHERE work_id != ? AND work_id IN (SELECT work_id FROM metadata_work GROUP BY work_id HAVING COUNT(*) < 2)",
+            work_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM work WHERE NOT EXISTS (SELECT 1 FROM metadata_work WHERE metadata_work.work_id = work.id)")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Works left with a single version are deleted
+    pub async fn delete_metadata_work(&self, metadata_id: i64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM metadata_work WHERE metadata_id = ?", metadata_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "DELETE FROM metadata_work WHERE work_id IN (SELECT work_id FROM metadata_work GROUP BY work_id HAVING COUNT(*) < 2)"
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM work WHERE NOT EXISTS (SELECT 1 FROM metadata_work WHERE metadata_work.work_id = work.id)")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Artist, title and work of the entries having a file
+    pub async fn get_metadata_versions(&self) -> Result<Vec<models::MetadataVersion>> {
+        let versions = sqlx::query_as!(
+            models::MetadataVersion,
+            r#"SELECT
+                metadata.id AS "metadata_id!: i64",
+                (SELECT MIN(tag.value) FROM metadata_tag JOIN tag ON tag.id = metadata_tag.tag_id WHERE metadata_tag.metadata_id = metadata.id AND tag.name_id = ?) AS "artist: String",
+                (SELECT MIN(tag.value) FROM metadata_tag JOIN tag ON tag.id = metadata_tag.tag_id WHERE metadata_tag.metadata_id = metadata.id AND tag.name_id = ?) AS "title: String",
+                metadata_work.work_id AS "work_id: i64",
+                metadata_work.version AS "version: String"
+            FROM metadata
+            LEFT JOIN metadata_work ON metadata_work.metadata_id = metadata.id
+            WHERE EXISTS (SELECT 1 FROM file WHERE file.metadata_id = metadata.id)
+            ORDER BY metadata.id ASC"#,
+            TagName::ARTIST_ID,
+            TagName::TITLE_ID
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(versions)
+    }
+
+    /// Move the files, tags, cues, beat grid, playlist entries, cover, fingerprint and analysis of `from` to `into` then delete `from`
+    ///
+    /// The beat grid, cover, fingerprint and analysis of `into` win over the ones of `from`,
+    /// moved hot cues lose their slot when `into` already uses it
+    pub async fn merge_metadata(&self, from: i64, into: i64) -> Result<()> {
+        if from == into {
+            return Err(eyre!("Cannot merge metadata {} into itself", from));
+        }
+        let mut tx = self.pool.begin().await?;
+        let source = sqlx::query_as!(models::Metadata, "SELECT * FROM metadata WHERE id = ?", from)
+            .fetch_one(&mut *tx)
+            .await?;
+        sqlx::query_scalar!("SELECT id FROM metadata WHERE id = ?", into)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        sqlx::query!("UPDATE file SET metadata_id = ? WHERE metadata_id = ?", into, from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO metadata_tag (metadata_id, tag_id, source) SELECT ?, tag_id, source FROM metadata_tag WHERE metadata_id = ? ON CONFLICT(metadata_id, tag_id) DO NOTHING",
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM metadata_tag WHERE metadata_id = ?", from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "UPDATE cue SET hot_cue_index = NULL WHERE metadata_id = ? AND hot_cue_index IN (SELECT hot_cue_index FROM cue WHERE metadata_id = ?)",
+            from,
+            into
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("UPDATE cue SET metadata_id = ? WHERE metadata_id = ?", into, from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "UPDATE beat_grid_marker SET metadata_id = ? WHERE metadata_id = ? AND NOT EXISTS (SELECT 1 FROM beat_grid_marker WHERE metadata_id = ?)",
+            into,
+            from,
+            into
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM beat_grid_marker WHERE metadata_id = ?", from)
+            .execute(&mut *tx)
+            .await?;
+        // Playlists holding both entries keep the position of `into`
+        sqlx::query!(
+            "DELETE FROM playlist_entry WHERE metadata_id = ? AND playlist_id IN (SELECT playlist_id FROM playlist_entry WHERE metadata_id = ?)",
+            from,
+            into
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("UPDATE playlist_entry SET metadata_id = ? WHERE metadata_id = ?", into, from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO metadata_cover (metadata_id, cover_id, source) SELECT ?, cover_id, source FROM metadata_cover WHERE metadata_id = ? ON CONFLICT(metadata_id) DO NOTHING",
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM metadata_cover WHERE metadata_id = ?", from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO fingerprint (metadata_id, duration, codes) SELECT ?, duration, codes FROM fingerprint WHERE metadata_id = ? ON CONFLICT(metadata_id) DO NOTHING",
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM fingerprint WHERE metadata_id = ?", from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO metadata_analysis (metadata_id, waveform, loudness, peak, grid_confidence, key_confidence) SELECT ?, waveform, loudness, peak, grid_confidence, key_confidence FROM metadata_analysis WHERE metadata_id = ? ON CONFLICT(metadata_id) DO NOTHING",
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO metadata_work (metadata_id, work_id, version) SELECT ?, work_id, version FROM metadata_work WHERE metadata_id = ? ON CONFLICT(metadata_id) DO NOTHING",
+            into,
+            from
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM metadata_work WHERE metadata_id = ?", from)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query!("UPDATE file_pin SET metadata_id = ? WHERE metadata_id = ?", into, from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("UPDATE metadata_alias SET metadata_id = ? WHERE metadata_id = ?", into, from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO metadata_alias (hash, metadata_id) VALUES (?, ?) ON CONFLICT(hash) DO UPDATE SET metadata_id = excluded.metadata_id",
+            source.hash,
+            into
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "UPDATE metadata SET duration = COALESCE(duration, ?), created_at = MIN(COALESCE(created_at, ?), COALESCE(?, created_at)) WHERE id = ?",
+            source.duration,
+            source.created_at,
+            source.created_at,
+            into
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM metadata WHERE id = ?", from)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Detach the file from its metadata into a new one holding the tags of `tag_name_ids`,
+    /// `hash` is the hash of the file content. Returns the id of the new metadata
+    pub async fn split_file(&self, file_id: i64, hash: &str, tag_name_ids: &[i64]) -> Result<i64> {
+        if tag_name_ids.contains(&TagName::DIRECTORY_ID) || tag_name_ids.contains(&TagName::FILE_NAME_ID) {
+            return Err(eyre!("The directory and file name tags cannot be copied, they follow the files"));
+        }
+        let file = self.get_file(file_id).await?;
+        // The directory and file name tags follow the files
+        let remaining_paths = sqlx::query_scalar!(
+            "SELECT path FROM file WHERE metadata_id = ? AND id != ?",
+            file.metadata_id,
+            file_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut location_tag_ids = HashMap::new();
+        for path in remaining_paths.iter().chain([&file.path]) {
+            let path = Path::new(path);
+            let values = [
+                (TagName::DIRECTORY_ID, path.parent().map(|parent| parent.to_string_lossy().to_string())),
+                (TagName::FILE_NAME_ID, path.file_prefix().map(|prefix| prefix.to_string_lossy().to_string())),
+            ];
+            let mut tag_ids = vec![];
+            for (tag_name_id, value) in values {
+                if let Some(value) = value {
+                    tag_ids.push(self.get_or_create_tag(tag_name_id, value).await?);
+                }
+            }
+            location_tag_ids.insert(path.to_path_buf(), tag_ids);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let siblings = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM file WHERE metadata_id = ? AND id != ?",
+            file.metadata_id,
+            file_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if siblings == 0 {
+            return Err(eyre!("{} is the only file of its entry", file.path));
+        }
+
+        let metadata_id = sqlx::query_scalar!(
+            "INSERT INTO metadata (hash, duration, created_at) SELECT ?, duration, created_at FROM metadata WHERE id = ? RETURNING id",
+            hash,
+            file.metadata_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        for tag_name_id in tag_name_ids {
+            sqlx::query!(
+                "INSERT INTO metadata_tag (metadata_id, tag_id, source) SELECT ?, metadata_tag.tag_id, metadata_tag.source FROM metadata_tag JOIN tag ON tag.id = metadata_tag.tag_id WHERE metadata_tag.metadata_id = ? AND tag.name_id = ? ON CONFLICT(metadata_id, tag_id) DO NOTHING",
+                metadata_id,
+                file.metadata_id,
+                tag_name_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query!("UPDATE file SET metadata_id = ? WHERE id = ?", metadata_id, file_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO file_pin (path, hash, metadata_id) VALUES (?, ?, ?) ON CONFLICT(path) DO UPDATE SET hash = excluded.hash, metadata_id = excluded.metadata_id",
+            file.path,
+            hash,
+            metadata_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for tag_name_id in [TagName::DIRECTORY_ID, TagName::FILE_NAME_ID] {
+            sqlx::query!(
+                "DELETE FROM metadata_tag WHERE metadata_id = ? AND tag_id IN (SELECT id FROM tag WHERE name_id = ?)",
+                file.metadata_id,
+                tag_name_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        for (path, tag_ids) in location_tag_ids {
+            let owner = match path == Path::new(&file.path) {
+                true => metadata_id,
+                false => file.metadata_id,
+            };
+            for tag_id in tag_ids {
+                sqlx::query!(
+                    "INSERT INTO metadata_tag (metadata_id, tag_id) VALUES (?, ?) ON CONFLICT(metadata_id, tag_id) DO NOTHING",
+                    owner,
+                    tag_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(metadata_id)
+    }
 }
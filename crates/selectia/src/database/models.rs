@@ -12,6 +12,10 @@ pub struct Module {
 pub struct Metadata {
     pub id: i64,
     pub hash: String,
+    /// Seconds, `None` until the file was probed
+    pub duration: Option<f64>,
+    /// Unix timestamp
+    pub created_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -29,10 +33,36 @@ pub struct FileVariation {
     pub metadata: Option<String>,
 }
 
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LibraryRoot {
+    pub id: i64,
+    pub path: String,
+    pub import_rules: Option<String>,
+    /// Last component of the path when not set
+    pub name: Option<String>,
+}
+
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct MetadataTag {
     pub metadata_id: i64,
     pub tag_id: i64,
+    /// Origin of the tag when it was not set by the user (i.e `filename_rule:<id>`)
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TaggingRule {
+    pub id: i64,
+    pub template: String,
+    pub enabled: bool,
+    pub position: i64,
+}
+
+impl TaggingRule {
+    /// Value of `metadata_tag.source` for tags written by the rule
+    pub fn source(rule_id: i64) -> String {
+        format!("filename_rule:{}", rule_id)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -55,6 +85,13 @@ pub struct Task {
     pub id: i64,
     pub status: String,
     pub payload: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub last_error: Option<String>,
+    pub next_run_at: Option<i64>,
+    pub priority: i64,
+    pub resource_class: String,
+    pub dedupe_key: Option<String>,
 }
 
 impl TagName {
@@ -67,6 +104,242 @@ impl TagName {
     pub const GENRE_ID: i64 = 7;
     pub const PLAYLIST_ID: i64 = 8;
     pub const TEMPO_ID: i64 = 9;
+    pub const LABEL_ID: i64 = 10;
+    pub const REMIX_ID: i64 = 11;
+    pub const TRACK_ID: i64 = 12;
+    pub const KEY_ID: i64 = 13;
+    pub const RATING_ID: i64 = 14;
+    pub const COMMENT_ID: i64 = 15;
+    pub const COLOR_ID: i64 = 16;
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Cue {
+    pub id: i64,
+    pub metadata_id: i64,
+    /// One of the `Cue::*_KIND` constants
+    pub kind: String,
+    /// Hot cue slot, `None` for memory cues
+    pub hot_cue_index: Option<i64>,
+    /// Position in seconds
+    pub start_time: f64,
+    /// End of the loop in seconds
+    pub end_time: Option<f64>,
+    pub name: Option<String>,
+    /// `#rrggbb`
+    pub color: Option<String>,
+    /// Origin of the cue when imported (i.e `rekordbox`)
+    pub source: Option<String>,
+}
+
+impl Cue {
+    pub const CUE_KIND: &str = "cue";
+    pub const LOOP_KIND: &str = "loop";
+    pub const FADE_IN_KIND: &str = "fade_in";
+    pub const FADE_OUT_KIND: &str = "fade_out";
+    pub const LOAD_KIND: &str = "load";
+}
+
+/// A cue not yet bound to a metadata, as read from or written to other DJ software
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CuePoint {
+    pub kind: String,
+    pub hot_cue_index: Option<i64>,
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+impl From<Cue> for CuePoint {
+    fn from(cue: Cue) -> Self {
+        Self {
+            kind: cue.kind,
+            hot_cue_index: cue.hot_cue_index,
+            start_time: cue.start_time,
+            end_time: cue.end_time,
+            name: cue.name,
+            color: cue.color,
+        }
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BeatGridMarker {
+    pub id: i64,
+    pub metadata_id: i64,
+    /// Position of the marker in seconds
+    pub position: f64,
+    /// Tempo from this marker up to the next one
+    pub bpm: f64,
+    /// Time signature (i.e `4/4`)
+    pub meter: Option<String>,
+    /// Beat of the bar the marker is on, starting at 1
+    pub beat: i64,
+    pub source: Option<String>,
+}
+
+/// A beat grid marker not yet bound to a metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TempoMarker {
+    pub position: f64,
+    pub bpm: f64,
+    pub meter: Option<String>,
+    pub beat: i64,
+}
+
+impl From<BeatGridMarker> for TempoMarker {
+    fn from(marker: BeatGridMarker) -> Self {
+        Self {
+            position: marker.position,
+            bpm: marker.bpm,
+            meter: marker.meter,
+            beat: marker.beat,
+        }
+    }
+}
+
+/// Picture shared by every track embedding (or sitting next to) the same image
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Cover {
+    pub id: i64,
+    /// Hex encoded SHA-256 of the picture, also its name in the cover cache
+    pub hash: String,
+    pub mime_type: String,
+    pub width: i64,
+    pub height: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Fingerprint {
+    pub metadata_id: i64,
+    pub duration: f64,
+    /// Little endian `u32` codes, see `ChromaFingerprint`
+    pub codes: Vec<u8>,
+}
+
+/// Job enqueued periodically by the worker, see `services::worker::scheduler`
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Schedule {
+    pub id: i64,
+    pub name: String,
+    /// Serialized `TaskPayload`
+    pub payload: String,
+    /// Cron expression in local time, set when `interval_seconds` is not
+    pub cron: Option<String>,
+    pub interval_seconds: Option<i64>,
+    pub enabled: bool,
+    pub last_run_at: Option<i64>,
+    pub next_run_at: i64,
+}
+
+/// Run of a task archived when it left the worker pool
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TaskHistory {
+    pub id: i64,
+    pub task_id: i64,
+    /// See `TaskPayload::name`
+    pub name: String,
+    pub payload: String,
+    /// `done`, `failed` or `cancelled`
+    pub status: String,
+    pub priority: i64,
+    pub resource_class: String,
+    pub attempt: i64,
+    pub worker_slot: i64,
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub duration_ms: i64,
+    pub error: Option<String>,
+    /// JSON outcome of the task
+    pub summary: Option<String>,
+}
+
+/// Run to archive with the task it ran, see `Database::archive_task_run`
+#[derive(Debug, Clone)]
+pub struct TaskRun {
+    pub task_id: i64,
+    pub name: String,
+    pub status: String,
+    pub worker_slot: i64,
+    pub started_at: i64,
+    pub duration_ms: i64,
+    pub error: Option<String>,
+    pub summary: Option<String>,
+}
+
+/// Filters of the task history, every run matches the default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskHistoryQuery {
+    pub name: Option<String>,
+    pub status: Option<String>,
+    /// Runs finished at or after this timestamp
+    pub since: Option<i64>,
+    /// Defaults to 100
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Runs of a kind of task in the task history
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TaskHistoryStats {
+    pub name: String,
+    pub runs: i64,
+    pub failed: i64,
+    pub cancelled: i64,
+    /// Of the successful runs
+    pub average_duration_ms: Option<f64>,
+    pub max_duration_ms: Option<i64>,
+}
+
+/// Results of the analysis pipeline, `None` until their stage ran
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MetadataAnalysis {
+    pub metadata_id: i64,
+    /// Peaks from 0 to 255
+    pub waveform: Option<Vec<u8>>,
+    /// Integrated loudness in dB relative to full scale
+    pub loudness: Option<f64>,
+    /// Highest sample in dB relative to full scale
+    pub peak: Option<f64>,
+    pub grid_confidence: Option<f64>,
+    pub key_confidence: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FileVerification {
+    pub file_id: i64,
+    /// See `VerifiedFileStatus`
+    pub status: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Work {
+    pub id: i64,
+    pub artist: String,
+    pub title: String,
+}
+
+/// Artist and title of an entry, along with its work when it is grouped
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MetadataVersion {
+    pub metadata_id: i64,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub work_id: Option<i64>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Playlist {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub name: String,
+    /// Folders only contain other playlists
+    pub is_folder: bool,
+    /// Position among the siblings
+    pub position: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +348,77 @@ pub struct FileVariationMetadata {
     pub stem: Option<String>,
 }
 
+/// Rules applied when a directory is walked for audio files, stored per library root
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ImportRules {
+    /// Accepted file extensions (lowercase, without the leading dot)
+    pub extensions: Vec<String>,
+    /// Gitignore-style patterns relative to the imported directory
+    pub ignore: Vec<String>,
+    /// Skip files and directories starting with a dot
+    pub skip_hidden: bool,
+    pub follow_symlinks: bool,
+    /// Size bounds in bytes
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Duration bounds in seconds (requires probing the file)
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+}
+
+impl Default for ImportRules {
+    fn default() -> Self {
+        Self {
+            extensions: AUDIO_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            ignore: vec!["*.stems/".to_string()],
+            skip_hidden: true,
+            follow_symlinks: false,
+            min_size: None,
+            max_size: None,
+            min_duration: None,
+            max_duration: None,
+        }
+    }
+}
+
+impl LibraryRoot {
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => Path::new(&self.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.path.clone()),
+        }
+    }
+
+    /// Path of `path` relative to the root, `None` when it is outside of it
+    pub fn relative_path<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        path.strip_prefix(&self.path).ok()
+    }
+
+    /// `path` moved along with the root to `to`, `None` when it is outside of the root
+    pub fn relocated_path(&self, path: &str, to: &Path) -> Option<String> {
+        let relative = self.relative_path(Path::new(path))?;
+        let relocated = match relative.as_os_str().is_empty() {
+            true => to.to_path_buf(),
+            false => to.join(relative),
+        };
+        Some(relocated.to_string_lossy().to_string())
+    }
+
+    pub fn import_rules(&self) -> ImportRules {
+        self.import_rules
+            .as_ref()
+            .and_then(|rules| serde_json::from_str(rules).ok())
+            .unwrap_or_default()
+    }
+}
+
 pub struct DecodedFileVariation {
     pub id: i64,
     pub file_id: i64,
@@ -5,7 +5,19 @@ use sqlx::{prelude::*, Execute, QueryBuilder};
 pub struct EntryView {
     pub metadata_id: i64,
     pub metadata_hash: String,
+    /// Hash of the cover picture in the cover cache
+    pub cover_hash: Option<String>,
     pub tags: sqlx::types::Json<Vec<MetadataTagView>>,
+    pub work_id: Option<i64>,
+    pub version: Option<String>,
+    /// Other versions of the work
+    pub versions: sqlx::types::Json<Vec<VersionView>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionView {
+    pub metadata_id: i64,
+    pub version: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,9 +27,11 @@ pub struct MetadataTagView {
     pub tag_name_id: i64,
     pub tag_value: String,
     pub metadata_id: i64,
+    #[serde(default)]
+    pub tag_source: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EntryViewFilter {
     directories: Vec<String>,
     tags: HashMap<i64, Vec<TagFilter>>,
@@ -42,15 +56,31 @@ impl PartialEq for EntryViewFilter {
 
 
 impl EntryView {
+    /// First value of the tag, if any
+    pub fn tag_value(&self, tag_name_id: i64) -> Option<String> {
+        self.tags
+            .0
+            .iter()
+            .find(|tag| tag.tag_name_id == tag_name_id)
+            .map(|tag| tag.tag_value.clone())
+    }
+
         const QUERY_HEAD: &str = r#"
-        SELECT metadata.id as metadata_id, metadata.hash as metadata_hash, json_group_array(json_object(
+        SELECT metadata.id as metadata_id, metadata.hash as metadata_hash, cover.hash as cover_hash, json_group_array(json_object(
             'tag_id', tagged_metadata.tag_id, 
             'metadata_tag_id', tagged_metadata.metadata_id,
             'tag_name_id', tagged_metadata.tag_name_id, 
             'tag_value', tagged_metadata.tag_value,
+            'tag_source', tagged_metadata.tag_source,
             'metadata_id', metadata.id
-        )) as tags FROM metadata
+        )) as tags, metadata_work.work_id as work_id, metadata_work.version as version, (
+            SELECT json_group_array(json_object('metadata_id', sibling.metadata_id, 'version', sibling.version))
+            FROM metadata_work as sibling WHERE sibling.work_id = metadata_work.work_id AND sibling.metadata_id != metadata.id
+        ) as versions FROM metadata
             LEFT JOIN tagged_metadata on tagged_metadata.metadata_id = metadata.id
+            LEFT JOIN metadata_cover on metadata_cover.metadata_id = metadata.id
+            LEFT JOIN cover on cover.id = metadata_cover.cover_id
+            LEFT JOIN metadata_work on metadata_work.metadata_id = metadata.id
         WHERE metadata.id IN (
             SELECT DISTINCT metadata_id FROM tagged_metadata as tm
     "#;
@@ -1,3 +1,3 @@
 mod path_ext;
 
-pub use path_ext::PathExt;
\ No newline at end of file
+pub use path_ext::{PathExt, AUDIO_EXTENSIONS};
@@ -1,19 +1,29 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
-const AUDIO_EXTENSIONS: [&str; 6] = ["mp3", "flac", "wav", "m4a", "aac", "ogg"];
+pub const AUDIO_EXTENSIONS: [&str; 11] = [
+    "mp3", "flac", "wav", "m4a", "aac", "ogg", "aiff", "aif", "alac", "opus", "wv",
+];
 
 pub trait PathExt {
     fn is_audio_file(&self) -> bool;
+    fn has_extension_in<S: AsRef<str>>(&self, extensions: &[S]) -> bool;
+    fn is_hidden(&self) -> bool;
 }
 
 impl PathExt for Path {
     fn is_audio_file(&self) -> bool {
-        self.extension().map_or(false, |ext| AUDIO_EXTENSIONS.contains(&ext.to_str().unwrap().to_lowercase().as_str()))
+        self.has_extension_in(&AUDIO_EXTENSIONS)
     }
-}
 
-impl PathExt for PathBuf {
-    fn is_audio_file(&self) -> bool {
-        self.as_path().is_audio_file()
+    fn has_extension_in<S: AsRef<str>>(&self, extensions: &[S]) -> bool {
+        self.extension().is_some_and(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            extensions.iter().any(|e| e.as_ref() == ext)
+        })
+    }
+
+    fn is_hidden(&self) -> bool {
+        self.file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
     }
 }
@@ -21,12 +21,21 @@ pub use theater::prelude::*;
 pub use crate::ext::*;
 pub use crate::models::{Tag, TagName};
 pub use crate::services::demuxer::{demuxer, Demuxer, DemuxerTask};
-pub use crate::services::file_loader::{file_loader, FileLoader, FileLoaderTask};
+pub use crate::services::file_loader::{file_loader, FileLoader, FileLoaderTask, HashStrategy};
+pub use crate::services::pipeline::{pipeline, Pipeline, PipelineTask};
 pub use crate::services::state_machine::{
     state_machine, IngestFileTask, StateMachine, StateMachineTask,
 };
 pub use crate::services::worker::{worker, Worker, WorkerEvent, WorkerTask};
 pub use crate::services::*;
+pub use crate::tasks::cover_art::CoverCache;
+pub use crate::tasks::duplicates::DuplicateFinder;
+pub use crate::tasks::filename_tagging::FilenameTagging;
+pub use crate::tasks::folder_export::FolderExport;
 pub use crate::tasks::load_directory::LoadDirectory;
+pub use crate::tasks::playlist_file::{PlaylistFileExport, PlaylistFileImport};
+pub use crate::tasks::rekordbox::{RekordboxExport, RekordboxImport};
+pub use crate::tasks::serato::SeratoExport;
+pub use crate::tasks::traktor::{TraktorExport, TraktorImport};
 
 pub type Timestamp = DateTime<Utc>;
@@ -38,6 +38,8 @@ impl PartialEq for DeckFile {
 #[derive(Clone, Debug)]
 pub struct DeckFileMetadataSnapshot {
     pub title: String,
+    /// Hash of the cover picture in the cover cache
+    pub cover_hash: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -108,9 +110,11 @@ impl PlayerDeck {
     pub async fn load_file(
         &self,
         path: impl AsRef<Path>,
+        cover_hash: Option<String>,
     ) -> eyre::Result<(DeckFile, Option<DeckFile>)> {
         let metadata = DeckFileMetadataSnapshot {
             title: path.as_ref().to_string_lossy().to_string(),
+            cover_hash,
         };
 
         let loaded_file = DeckFile {
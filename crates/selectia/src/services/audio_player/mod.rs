@@ -190,20 +190,27 @@ impl AudioPlayer {
                 let _ = callback.resolve(decks).await?;
             }
             AudioPlayerTask::LoadTrack { deck_id, target } => {
-                let file_path = match target {
+                let (file_path, metadata_id) = match target {
                     TrackTarget::Metadata { metadata_id } => {
                         let file = self.database.get_file_from_metadata_id(metadata_id).await?;
-                        file.path
+                        (file.path, metadata_id)
                     }
                     TrackTarget::FileVariation { file_variation_id } => {
-                        self.database
+                        let file_variation = self
+                            .database
                             .get_file_variation_from_id(file_variation_id)
-                            .await?
-                            .path
+                            .await?;
+                        let file = self.database.get_file(file_variation.file_id).await?;
+                        (file_variation.path, file.metadata_id)
                     }
                 };
+                let cover_hash = self
+                    .database
+                    .get_cover(metadata_id)
+                    .await?
+                    .map(|cover| cover.hash);
                 let deck: PlayerDeck = self.decks.get_deck(deck_id).await?;
-                let (loaded_file, previous) = deck.load_file(file_path).await?;
+                let (loaded_file, previous) = deck.load_file(file_path, cover_hash).await?;
                 let backend = self.backend.write().await;
                 let backend = backend.as_ref().ok_or_eyre("Backend not loaded")?;
                 if let Some(previous) = previous {
@@ -1,4 +1,5 @@
-use demucs::{backend::DemuxResult, Demucs};
+use demucs::{backend::DemuxResult, BackendKilled, Demucs};
+use tokio_util::sync::CancellationToken;
 
 use crate::prelude::*;
 
@@ -7,6 +8,10 @@ pub enum DemuxerTask {
     Demux {
         input: PathBuf,
         output: PathBuf,
+        /// Receives the progress of the separation, from 0 to 1
+        progress: Option<tokio::sync::mpsc::UnboundedSender<f64>>,
+        /// Stops the separation, the demuxer is loaded again afterward if it was running
+        cancel: CancellationToken,
         callback: TaskCallback<DemuxResult>,
     },
     StatusUpdate {
@@ -69,13 +74,25 @@ pub async fn demuxer(ctx: ServiceContext, mut rx: ServiceReceiver<DemuxerTask>,
             DemuxerTask::Demux {
                 input,
                 output,
+                progress,
+                cancel,
                 callback,
             } => {
                 match &current_status {
                     DemuxerStatus::Ready { demucs } => {
-                        tokio::fs::create_dir_all(&output).await?;
-                        let result = demucs.demux(input, output.clone()).await?;
-                        callback.resolve(result).await?;
+                        // Processed in the background to keep receiving tasks while waiting for the backend
+                        let demucs = demucs.clone();
+                        let address = introspect_address.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = demux(&demucs, input, output, progress, &cancel, callback).await {
+                                error!("Demux task failed: {:?}", e);
+                                if e.downcast_ref::<BackendKilled>().is_some() {
+                                    let _ = address.send(DemuxerTask::StatusUpdate {
+                                        status: DemuxerStatus::None,
+                                    }).await;
+                                }
+                            }
+                        });
                     }
                     _ => {
                         error!("Demuxer not ready, failed to process demux task");
@@ -87,6 +104,20 @@ pub async fn demuxer(ctx: ServiceContext, mut rx: ServiceReceiver<DemuxerTask>,
     Ok(())
 }
 
+async fn demux(
+    demucs: &Demucs,
+    input: PathBuf,
+    output: PathBuf,
+    progress: Option<tokio::sync::mpsc::UnboundedSender<f64>>,
+    cancel: &CancellationToken,
+    callback: TaskCallback<DemuxResult>,
+) -> Result<()> {
+    tokio::fs::create_dir_all(&output).await?;
+    let result = demucs.demux(input, output, progress, cancel).await?;
+    callback.resolve(result).await?;
+    Ok(())
+}
+
 async fn load_demuxer(data_path: PathBuf, sender: AddressableService<DemuxerTask>) -> Result<()> {
     sender
         .send(DemuxerTask::StatusUpdate {
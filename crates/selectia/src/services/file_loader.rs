@@ -1,6 +1,8 @@
+use selectia_audio_file::audio_file::EncodedAudioFile;
 use views::entry_view::EntryView;
 
 use crate::prelude::*;
+use crate::services::state_machine::StateMachineEvent;
 
 const MAX_CONCURRENT_LOADS: usize = 4;
 
@@ -12,10 +14,17 @@ pub enum FileLoaderTask {
         /// Callback that will be resolved with the metadata's id of the ingested file
         callback: Option<TaskCallback<i64>>,
     },
+    /// Rebuild the tagging rules applied to new files, to be sent once the rules changed
+    ReloadTaggingRules {
+        callback: Option<TaskCallback<()>>,
+    },
 }
 
 #[singleton_service(FileLoader)]
 pub async fn file_loader(ctx: ServiceContext, rx: ServiceReceiver<FileLoaderTask>) -> Result<()> {
+    let database = ctx.get_singleton::<Database>().await?;
+    // Shared by the concurrent loads instead of querying the rules for each file
+    let tagging = RwLock::new(Arc::new(FilenameTagging::new(database).await?));
     let stream = futures::stream::unfold(rx, |mut recv| async move {
         recv.recv().await.map(|task| (task, recv))
     })
@@ -23,11 +32,24 @@ pub async fn file_loader(ctx: ServiceContext, rx: ServiceReceiver<FileLoaderTask
         match file {
             FileLoaderTask::LoadFile { path, callback } => {
                 trace!(path = ?path, "Loading file ...");
-                match loader::LoadedFile::new(path).await {
+                let strategy = ctx.get_singleton::<HashStrategy>().await.unwrap_or_default();
+                match loader::LoadedFile::new(path, strategy).await {
                     Ok(loaded_file) => {
                         let database = ctx.get_singleton::<Database>().await.expect("database service");
-                        match ingest_file(database, &loaded_file.path, &loaded_file.hash).await {
-                            Ok(metadata_id) => {
+                        let covers = ctx.get_singleton::<CoverCache>().await.ok();
+                        let tagging = tagging.read().await.clone();
+                        match ingest_file(database, covers, &tagging, &loaded_file.path, &loaded_file.hash).await {
+                            Ok((file, created)) => {
+                                let metadata_id = file.metadata_id;
+                                // Listeners such as the analysis pipeline only exist once the state machine is spawned
+                                if let Ok(dispatcher) = ctx.get_singleton_dispatcher::<StateMachine, StateMachineEvent>().await {
+                                    if let Err(e) = dispatcher
+                                        .dispatch(StateMachineEvent::FileIngested { file, new: created })
+                                        .await
+                                    {
+                                        warn!(error = ?e, "Error dispatching ingested file");
+                                    }
+                                }
                                 if let Some(callback) = callback {
                                     let _ = callback.resolve(metadata_id).await;
                                 }
@@ -43,6 +65,17 @@ pub async fn file_loader(ctx: ServiceContext, rx: ServiceReceiver<FileLoaderTask
                 }
                 true
             }
+            FileLoaderTask::ReloadTaggingRules { callback } => {
+                let database = ctx.get_singleton::<Database>().await.expect("database service");
+                match FilenameTagging::new(database).await {
+                    Ok(reloaded) => *tagging.write().await = Arc::new(reloaded),
+                    Err(e) => error!(error = ?e, "Error loading tagging rules"),
+                }
+                if let Some(callback) = callback {
+                    let _ = callback.resolve(()).await;
+                }
+                true
+            }
         }
     })
     .buffer_unordered(MAX_CONCURRENT_LOADS);
@@ -56,11 +89,25 @@ pub async fn file_loader(ctx: ServiceContext, rx: ServiceReceiver<FileLoaderTask
     Ok(())
 }
 
-async fn ingest_file(database: Database, path: &Path, hash: &str) -> Result<i64> {
-    let (metadata, _created) = database
-        .get_or_create_metadata(hash)
-        .await?;
-    let _file = database
+/// Covers are only looked up when a cover cache is registered, returns the file and whether its metadata was created
+async fn ingest_file(
+    database: Database,
+    covers: Option<CoverCache>,
+    tagging: &FilenameTagging,
+    path: &Path,
+    hash: &str,
+) -> Result<(models::File, bool)> {
+    let stale_pin = database.delete_stale_file_pin(path, hash).await?;
+    let known = match database.find_metadata(path, hash).await? {
+        Some(metadata) => Some(metadata),
+        None if !stale_pin => unchanged_metadata(&database, path, hash).await?,
+        None => None,
+    };
+    let (metadata, created) = match known {
+        Some(metadata) => (metadata, false),
+        None => database.get_or_create_metadata(hash).await?,
+    };
+    let file = database
         .create_or_replace_file(path, metadata.id)
         .await?;
     let directory = path
@@ -80,16 +127,106 @@ async fn ingest_file(database: Database, path: &Path, hash: &str) -> Result<i64>
         .set_metadata_tag_by_tag_name_id(metadata.id, TagName::FILE_NAME_ID, file_name)
         .await?;
 
-    Ok(metadata.id)
+    if metadata.duration.is_none() {
+        let probed_path = path.to_path_buf();
+        let duration = tokio::task::spawn_blocking(move || {
+            EncodedAudioFile::from_file(&probed_path).map(|file| file.duration())
+        })
+        .await?;
+        match duration {
+            Ok(Some(duration)) => database.set_metadata_duration(metadata.id, duration).await?,
+            Ok(None) => trace!(path = ?path, "Unknown duration"),
+            Err(e) => trace!(path = ?path, error = ?e, "Failed to probe file duration"),
+        }
+    }
+
+    if created {
+        if let Err(e) = tagging.apply_to_file(metadata.id, path).await {
+            warn!(path = ?path, error = ?e, "Error applying tagging rules");
+        }
+    }
+
+    if let Some(covers) = covers {
+        if database.get_cover(metadata.id).await?.is_none() {
+            if let Err(e) = covers.ingest(&database, metadata.id, path).await {
+                warn!(path = ?path, error = ?e, "Error extracting cover art");
+            }
+        }
+    }
+
+    Ok((file, created))
+}
+
+/// Metadata of the file already known at `path` when it was hashed with another strategy and its content did not change,
+/// the file is then pinned with `hash` so the previous strategy is no longer used for it
+async fn unchanged_metadata(database: &Database, path: &Path, hash: &str) -> Result<Option<models::Metadata>> {
+    let Some(file) = database.get_file_by_path(path).await? else {
+        return Ok(None);
+    };
+    let metadata = database.get_metadata(file.metadata_id).await?;
+    let strategy = HashStrategy::of(&metadata.hash);
+    if strategy == HashStrategy::of(hash) {
+        return Ok(None);
+    }
+    let previous_hash = hash_file(path, strategy).await?;
+    match database.find_metadata(path, &previous_hash).await? {
+        Some(known) if known.id == metadata.id => {
+            database.set_file_pin(path, hash, metadata.id).await?;
+            Ok(Some(known))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// How the content of a file is identified, i.e how `metadata.hash` is computed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashStrategy {
+    /// Hash of the size and of three blocks of the file, fast but blind to edits in between
+    #[default]
+    Sampled,
+    /// Hash of the whole file
+    Full,
+    /// Hash of the decoded audio, tag edits do not change it
+    DecodedPcm,
+}
+
+impl HashStrategy {
+    /// Hashes are prefixed by their strategy, sampled hashes have no prefix
+    fn prefix(&self) -> &'static str {
+        match self {
+            HashStrategy::Sampled => "",
+            HashStrategy::Full => "full:",
+            HashStrategy::DecodedPcm => "pcm:",
+        }
+    }
+
+    /// Strategy that computed `hash`
+    pub fn of(hash: &str) -> Self {
+        [HashStrategy::Full, HashStrategy::DecodedPcm]
+            .into_iter()
+            .find(|strategy| hash.starts_with(strategy.prefix()))
+            .unwrap_or(HashStrategy::Sampled)
+    }
+}
+
+/// Hash identifying the content of a file (i.e `metadata.hash`)
+pub async fn hash_file(path: &Path, strategy: HashStrategy) -> Result<String> {
+    Ok(loader::LoadedFile::new(path.to_path_buf(), strategy).await?.hash)
 }
 
 mod loader {
     use base64ct::{Base64, Encoding};
+    use selectia_audio_file::audio_file::EncodedAudioFile;
     use sha2::{Digest, Sha256};
+    use symphonia::core::audio::Signal;
     use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
+    use super::HashStrategy;
     use crate::prelude::*;
 
+    const BLOCK_SIZE: u64 = 1024 * 128;
+
     pub struct LoadedFile {
         pub path: PathBuf,
         pub hash: String,
@@ -97,47 +234,97 @@ mod loader {
 
     impl LoadedFile {
         #[instrument]
-        pub async fn new(path: PathBuf) -> Result<Self> {
-            const BLOCK_SIZE: u64 = 1024 * 128;
+        pub async fn new(path: PathBuf, strategy: HashStrategy) -> Result<Self> {
+            let hash = match strategy {
+                HashStrategy::Sampled => sampled_hash(&path).await?,
+                HashStrategy::Full => full_hash(&path).await?,
+                HashStrategy::DecodedPcm => {
+                    let blocking_path = path.clone();
+                    tokio::task::spawn_blocking(move || decoded_pcm_hash(&blocking_path)).await??
+                }
+            };
+            let hash = format!("{}{}", strategy.prefix(), Base64::encode_string(&hash));
+            trace!(path = ?path, hash, "File hash computed");
+            Ok(Self { path, hash })
+        }
+    }
 
-            let mut file = tokio::fs::File::open(&path).await?;
-            let mut input_buffer = Vec::with_capacity(BLOCK_SIZE as usize);
+    async fn sampled_hash(path: &Path) -> Result<Vec<u8>> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut input_buffer = Vec::with_capacity(BLOCK_SIZE as usize);
 
-            let metadata = file.metadata().await?;
-            let size = metadata.len();
+        let metadata = file.metadata().await?;
+        let size = metadata.len();
 
-            let mut hasher = Sha256::new();
+        let mut hasher = Sha256::new();
 
-            hasher.update(&size.to_be_bytes());
+        hasher.update(size.to_be_bytes());
 
-            if size >= BLOCK_SIZE {
-                input_buffer.resize(BLOCK_SIZE as usize, 0);
+        if size >= BLOCK_SIZE {
+            input_buffer.resize(BLOCK_SIZE as usize, 0);
+            file.read_exact(&mut input_buffer).await?;
+            hasher.update(&input_buffer);
+
+            if size >= 2 * BLOCK_SIZE {
+                file.seek(std::io::SeekFrom::End(-(BLOCK_SIZE as i64)))
+                    .await?;
                 file.read_exact(&mut input_buffer).await?;
                 hasher.update(&input_buffer);
+            }
 
-                if size >= 2 * BLOCK_SIZE {
-                    file.seek(std::io::SeekFrom::End(-(BLOCK_SIZE as i64)))
-                        .await?;
-                    file.read_exact(&mut input_buffer).await?;
-                    hasher.update(&input_buffer);
-                }
-
-                if size >= 4 * BLOCK_SIZE {
-                    let offset = size / 2 - (BLOCK_SIZE / 2);
-                    file.seek(std::io::SeekFrom::Start(offset)).await?;
-                    file.read_exact(&mut input_buffer).await?;
-                    hasher.update(&input_buffer);
-                }
-            } else {
-                input_buffer.resize(size as usize, 0);
+            if size >= 4 * BLOCK_SIZE {
+                let offset = size / 2 - (BLOCK_SIZE / 2);
+                file.seek(std::io::SeekFrom::Start(offset)).await?;
                 file.read_exact(&mut input_buffer).await?;
                 hasher.update(&input_buffer);
             }
+        } else {
+            input_buffer.resize(size as usize, 0);
+            file.read_exact(&mut input_buffer).await?;
+            hasher.update(&input_buffer);
+        }
 
-            let hash = hasher.finalize();
-            let hash = Base64::encode_string(&hash);
-            trace!(path = ?path, hash, "File hash computed");
-            Ok(Self { path, hash })
+        Ok(hasher.finalize().to_vec())
+    }
+
+    async fn full_hash(path: &Path) -> Result<Vec<u8>> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut input_buffer = vec![0; BLOCK_SIZE as usize];
+        let mut hasher = Sha256::new();
+        loop {
+            let read = file.read(&mut input_buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&input_buffer[..read]);
+        }
+        Ok(hasher.finalize().to_vec())
+    }
+
+    fn decoded_pcm_hash(path: &Path) -> Result<Vec<u8>> {
+        let mut encoded_file = EncodedAudioFile::from_file(path)?;
+        let mut hasher = Sha256::new();
+        let mut spec_hashed = false;
+        let mut samples = vec![];
+        encoded_file.decoded_iterator(|buffer| {
+            let channels = buffer.spec().channels.count();
+            if !spec_hashed {
+                hasher.update(buffer.spec().rate.to_be_bytes());
+                hasher.update((channels as u32).to_be_bytes());
+                spec_hashed = true;
+            }
+            samples.clear();
+            for frame in 0..buffer.frames() {
+                for channel in 0..channels {
+                    samples.extend_from_slice(&buffer.chan(channel)[frame].to_le_bytes());
+                }
+            }
+            hasher.update(&samples);
+            Ok(true)
+        })?;
+        if !spec_hashed {
+            return Err(eyre!("{} holds no audio", path.display()));
         }
+        Ok(hasher.finalize().to_vec())
     }
 }
@@ -1,5 +1,6 @@
 pub mod audio_player;
 pub mod file_loader;
+pub mod pipeline;
 pub mod state_machine;
 pub mod worker;
 pub mod demuxer;
@@ -0,0 +1,98 @@
+use crate::prelude::*;
+use crate::services::state_machine::StateMachineEvent;
+use crate::services::worker::tasks::{AnalysisTask, TaskOptions, TaskPayload, TaskPriority};
+use crate::tasks::analysis::{pending_analysis_stages, AnalysisPipelineSettings, AnalysisStage};
+
+#[derive(Clone, Debug, Task)]
+pub enum PipelineTask {
+    /// Schedule the pending analysis stages of the metadata,
+    /// `callback` is resolved with the ids of the scheduled tasks in the order of `AnalysisStage::ALL`
+    Analyse {
+        metadata_id: i64,
+        callback: Option<TaskCallback<Vec<i64>>>,
+    },
+    SetSettings(AnalysisPipelineSettings),
+}
+
+/// Schedules the analysis stages of the new tracks as they are ingested
+#[singleton_service(Pipeline)]
+pub async fn pipeline(ctx: ServiceContext, mut rx: ServiceReceiver<PipelineTask>) -> Result<()> {
+    let database = ctx.get_singleton::<Database>().await?;
+    let worker = ctx.get_singleton_address::<Worker>().await?;
+    let mut settings = ctx
+        .get_singleton::<AnalysisPipelineSettings>()
+        .await
+        .unwrap_or_default();
+
+    let (events, mut ingested) = sync::mpsc::channel(4096);
+    ctx.get_singleton_dispatcher::<StateMachine, StateMachineEvent>()
+        .await?
+        .register(events)
+        .await;
+
+    loop {
+        tokio::select! {
+            Some(event) = ingested.recv() => match event {
+                StateMachineEvent::FileIngested { file, new: true } => {
+                    if let Err(e) = schedule(&database, &worker, &settings, file.metadata_id).await {
+                        error!(error = ?e, metadata_id = file.metadata_id, "Error scheduling analysis");
+                    }
+                }
+                StateMachineEvent::FileIngested { .. } => {}
+            },
+            task = rx.recv() => match task {
+                Some(PipelineTask::Analyse { metadata_id, callback }) => {
+                    match schedule(&database, &worker, &settings, metadata_id).await {
+                        Ok(ids) => {
+                            if let Some(callback) = callback {
+                                let _ = callback.resolve(ids).await;
+                            }
+                        }
+                        Err(e) => error!(error = ?e, metadata_id, "Error scheduling analysis"),
+                    }
+                }
+                Some(PipelineTask::SetSettings(new_settings)) => {
+                    info!("Analysis pipeline settings set to {:?}", new_settings);
+                    settings = new_settings;
+                }
+                None => break,
+            },
+        }
+    }
+    Ok(())
+}
+
+/// A stage only waits for the stage it depends on, so a stage failing for good does not hold back the others
+async fn schedule(
+    database: &Database,
+    worker: &AddressableService<WorkerTask>,
+    settings: &AnalysisPipelineSettings,
+    metadata_id: i64,
+) -> Result<Vec<i64>> {
+    let mut scheduled: HashMap<AnalysisStage, i64> = HashMap::new();
+    let mut ids = vec![];
+    for stage in pending_analysis_stages(database, metadata_id, settings).await? {
+        // A dependency not scheduled now is already done
+        let depends_on = stage
+            .depends_on()
+            .and_then(|dependency| scheduled.get(&dependency).copied())
+            .into_iter()
+            .collect();
+        let (callback, resolve) = TaskCallback::new();
+        worker
+            .send(WorkerTask::ScheduleWith {
+                payload: TaskPayload::Analysis(AnalysisTask { metadata_id, stage }),
+                options: TaskOptions {
+                    priority: TaskPriority::Background,
+                    depends_on,
+                    ..Default::default()
+                },
+                callback: Some(callback),
+            })
+            .await?;
+        let id = resolve.wait().await?;
+        scheduled.insert(stage, id);
+        ids.push(id);
+    }
+    Ok(ids)
+}
@@ -1,17 +1,89 @@
 use models::Task as TaskModel;
-use tasks::{BackgroundTask, TaskPayload, TaskStatus};
+use tasks::{
+    BackgroundTask, CancellationToken, ResourceClass, ResourceLimits, TaskOptions, TaskPayload,
+    TaskPriority, TaskProgress, TaskStatus, TaskSummary,
+};
 
 use crate::prelude::*;
 
+pub mod scheduler;
 pub mod tasks;
 
 const DEFAULT_WORKER_POOL_SIZE: usize = 1;
+/// Delay in seconds before the first retry of a failed task, doubled on each attempt
+const TASK_RETRY_DELAY: i64 = 30;
+const TASK_MAX_RETRY_DELAY: i64 = 60 * 60;
+
+/// Size of the worker pool and concurrency of each resource class within it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorkerConcurrency {
+    pub pool_size: usize,
+    pub limits: ResourceLimits,
+}
+
+impl Default for WorkerConcurrency {
+    fn default() -> Self {
+        Self {
+            pool_size: DEFAULT_WORKER_POOL_SIZE,
+            limits: ResourceLimits::default(),
+        }
+    }
+}
+
+/// Runs kept in the task history, the oldest are deleted once either limit is reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TaskHistoryRetention {
+    pub max_age_days: i64,
+    pub max_runs: i64,
+}
+
+impl Default for TaskHistoryRetention {
+    fn default() -> Self {
+        Self {
+            max_age_days: 30,
+            max_runs: 10_000,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub enum WorkerTask {
-    TaskDone { id: i64, success: bool },
+    /// `error` holds the error chain of a failed task
+    TaskDone {
+        id: i64,
+        error: Option<String>,
+        summary: TaskSummary,
+    },
     Poll,
     Schedule(TaskPayload),
+    /// `callback` is resolved with the id of the task
+    ScheduleWith {
+        payload: TaskPayload,
+        options: TaskOptions,
+        callback: Option<TaskCallback<i64>>,
+    },
+    /// Put a task in failed status back in the queue with the tasks failed with it,
+    /// `callback` is resolved with their ids, empty when the task was not failed
+    Retry {
+        id: i64,
+        callback: Option<TaskCallback<Vec<i64>>>,
+    },
+    /// Remove a pending task from the queue or stop a running one
+    Cancel { id: i64 },
+    /// `eta` is the estimated remaining time of the step in seconds
+    TaskProgress {
+        id: i64,
+        step: String,
+        progress: f64,
+        eta: Option<f64>,
+    },
+    /// Resolved with the ids of the tasks being processed
+    RunningTasks { callback: TaskCallback<Vec<i64>> },
+    /// Resize the pool, running tasks above the new limits are not stopped
+    SetConcurrency(WorkerConcurrency),
+    SetScheduleEnabled { id: i64, enabled: bool },
+    SetHistoryRetention(TaskHistoryRetention),
 }
 
 #[derive(Clone, Debug)]
@@ -25,6 +97,12 @@ pub enum WorkerEvent {
         status: TaskStatus,
         removed: bool,
     },
+    QueueTaskProgress {
+        id: i64,
+        step: String,
+        progress: f64,
+        eta: Option<f64>,
+    },
 }
 
 #[singleton_service(Worker)]
@@ -35,8 +113,16 @@ pub async fn worker(
 ) -> Result<()> {
     let database = ctx.get_singleton::<Database>().await?;
     let introspect_address = ctx.get_singleton_address::<Worker>().await?;
+    let concurrency = ctx
+        .get_singleton::<WorkerConcurrency>()
+        .await
+        .unwrap_or_default();
+    let mut retention = ctx
+        .get_singleton::<TaskHistoryRetention>()
+        .await
+        .unwrap_or_default();
     let mut pool = WorkerPool::new(
-        DEFAULT_WORKER_POOL_SIZE,
+        concurrency,
         introspect_address.clone(),
         dispatcher.clone(),
     );
@@ -50,142 +136,427 @@ pub async fn worker(
         );
     }
 
+    let mut retry_wakeup: Option<tokio::task::JoinHandle<()>> = None;
+
     // Send a poll message to the dispatcher to wake up the main loop and check if there is a task store
     introspect_address.send(WorkerTask::Poll).await?;
 
     while let Some(task) = rx.recv().await {
-        info!("Worker received task: {:?}", task);
-        match task {
-            WorkerTask::TaskDone { id, success } => {
-                pool.done(id).await;
-                if success {
-                    info!("Task done: {}", id);
-                } else {
-                    error!("Task failed: {}", id);
+        match &task {
+            // Sent on every tick of a running task
+            WorkerTask::TaskProgress { .. } => trace!("Worker received task: {:?}", task),
+            _ => info!("Worker received task: {:?}", task),
+        }
+        // An error handling a message must not stop the queue
+        let result: Result<()> = async {
+            match task {
+                WorkerTask::TaskDone { id, error, summary } => {
+                    let run = pool.done(id).await;
+                    let cancelled = run.as_ref().is_some_and(|run| run.cancelled);
+                    if let Some(run) = run {
+                        if let Err(e) = archive_run(&database, &run, error.as_deref(), summary, &retention).await {
+                            error!(id, error = ?e, "Error archiving task run");
+                        }
+                    }
+                    // The tasks waiting for a task that will not complete are cancelled or failed with it
+                    let (ids, status) = match error {
+                        // Even when the task ran to its end before noticing it
+                        _ if cancelled => {
+                            info!("Task cancelled: {}", id);
+                            (database.delete_task_with_dependents(id).await?, TaskStatus::Cancelled)
+                        }
+                        None => {
+                            info!("Task done: {}", id);
+                            database.delete_task(id).await?;
+                            (vec![id], TaskStatus::Done)
+                        }
+                        Some(error) => {
+                            let task = database
+                                .fail_task(id, &error, TASK_RETRY_DELAY, TASK_MAX_RETRY_DELAY)
+                                .await?;
+                            error!(
+                                "Task failed ({}/{} attempts): {}",
+                                task.attempts, task.max_attempts, id
+                            );
+                            let status = TaskStatus::try_from(task.status.as_str())?;
+                            let mut ids = vec![id];
+                            if matches!(status, TaskStatus::Failed) {
+                                ids.extend(database.get_task_dependents(id).await?);
+                            }
+                            (ids, status)
+                        }
+                    };
+                    dispatch_updates(&dispatcher, &ids, status).await?;
                 }
-                database.delete_task(id).await?;
-                dispatcher
-                    .dispatch(WorkerEvent::QueueTaskUpdated {
-                        id,
-                        status: TaskStatus::Done,
-                        removed: true,
-                    })
-                    .await?;
-            }
-            WorkerTask::Schedule(task) => match database.enqueue_task(task).await {
-                Ok(id) => {
+                WorkerTask::Cancel { id } => {
+                    if pool.cancel(id) {
+                        // The cancelled status is dispatched once the task stopped
+                        info!("Cancelling running task: {}", id);
+                        return Ok(());
+                    }
+                    let ids = database.delete_pending_task(id).await?;
+                    if ids.is_empty() {
+                        warn!("Task {} is not pending nor running, not cancelled", id);
+                    }
+                    dispatch_updates(&dispatcher, &ids, TaskStatus::Cancelled).await?;
+                    return Ok(());
+                }
+                WorkerTask::Retry { id, callback } => {
+                    let ids = database.retry_failed_task(id).await?;
+                    if ids.is_empty() {
+                        warn!("Task {} is not in failed status, not retried", id);
+                    }
+                    dispatch_updates(&dispatcher, &ids, TaskStatus::Queued).await?;
+                    if let Some(callback) = callback {
+                        let _ = callback.resolve(ids).await;
+                    }
+                }
+                WorkerTask::Schedule(task) => {
+                    schedule(&database, &dispatcher, &pool, task, TaskOptions::default(), None).await?;
+                }
+                WorkerTask::ScheduleWith {
+                    payload,
+                    options,
+                    callback,
+                } => {
+                    schedule(&database, &dispatcher, &pool, payload, options, callback).await?;
+                }
+                WorkerTask::TaskProgress {
+                    id,
+                    step,
+                    progress,
+                    eta,
+                } => {
                     dispatcher
-                        .dispatch(WorkerEvent::QueueTaskCreated {
+                        .dispatch(WorkerEvent::QueueTaskProgress {
                             id,
-                            status: TaskStatus::Queued,
+                            step,
+                            progress,
+                            eta,
                         })
                         .await?;
+                    return Ok(());
                 }
-                Err(e) => {
-                    error!("Error enqueuing task: {:?}", e);
+                WorkerTask::RunningTasks { callback } => {
+                    let _ = callback.resolve(pool.running_task_ids()).await;
+                    return Ok(());
                 }
-            },
-            WorkerTask::Poll => {}
+                WorkerTask::SetConcurrency(concurrency) => {
+                    info!("Worker concurrency set to {:?}", concurrency);
+                    pool.concurrency = concurrency;
+                }
+                WorkerTask::SetScheduleEnabled { id, enabled } => {
+                    if let Err(e) = scheduler::set_schedule_enabled(&database, id, enabled).await {
+                        error!(id, error = ?e, "Error updating schedule");
+                    }
+                }
+                WorkerTask::SetHistoryRetention(new_retention) => {
+                    info!("Task history retention set to {:?}", new_retention);
+                    retention = new_retention;
+                    return Ok(());
+                }
+                WorkerTask::Poll => {}
+            }
+
+            for payload in scheduler::claim_due_schedules(&database).await? {
+                info!("Scheduled task due: {}", payload.name());
+                let options = TaskOptions::priority(TaskPriority::Background);
+                schedule(&database, &dispatcher, &pool, payload, options, None).await?;
+            }
+
+            while pool.has_empty_slots() {
+                let full_classes = pool.full_classes();
+                if let Some(task) = database.dequeue_task_excluding(&full_classes).await? {
+                    dispatcher
+                        .dispatch(WorkerEvent::QueueTaskUpdated {
+                            id: task.id,
+                            status: TaskStatus::Processing,
+                            removed: false,
+                        })
+                        .await?;
+                    if let Err(e) = pool
+                        .spawn(
+                            task,
+                            ctx.clone(),
+                        )
+                        .await
+                    {
+                        error!("Error spawning task: {:?}", e);
+                    }
+                } else {
+                    info!("No task to process, waiting for a worker to finish");
+                    break;
+                }
+            }
+
+            // Wake up the loop when the next task waiting for a retry or the next schedule is due
+            let next_run_at = [
+                database.get_next_task_run_at().await?,
+                database.get_next_schedule_run_at().await?,
+            ]
+            .into_iter()
+            .flatten()
+            .min();
+            if let Some(next_run_at) = next_run_at {
+                if let Some(wakeup) = retry_wakeup.take() {
+                    wakeup.abort();
+                }
+                let delay = (next_run_at - chrono::Utc::now().timestamp()).max(0) as u64;
+                let address = introspect_address.clone();
+                retry_wakeup = Some(tokio::spawn(async move {
+                    tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+                    let _ = address.send(WorkerTask::Poll).await;
+                }));
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            error!(error = ?e, "Error handling worker task");
         }
+    }
+    Ok(())
+}
+
+async fn dispatch_updates(
+    dispatcher: &EventDispatcher<WorkerEvent>,
+    ids: &[i64],
+    status: TaskStatus,
+) -> Result<()> {
+    for id in ids {
+        dispatcher
+            .dispatch(WorkerEvent::QueueTaskUpdated {
+                id: *id,
+                removed: matches!(status, TaskStatus::Done | TaskStatus::Cancelled),
+                status: status.clone(),
+            })
+            .await?;
+    }
+    Ok(())
+}
 
-        while pool.has_empty_slots() {
-            if let Some(task) = database.dequeue_task().await? {
+async fn schedule(
+    database: &Database,
+    dispatcher: &EventDispatcher<WorkerEvent>,
+    pool: &WorkerPool,
+    payload: TaskPayload,
+    options: TaskOptions,
+    callback: Option<TaskCallback<i64>>,
+) -> Result<()> {
+    let existing = database
+        .get_active_task_by_dedupe_key(&payload.dedupe_key())
+        .await?;
+    match database.enqueue_task_with(payload, &options).await {
+        Ok(id) => {
+            let existing = existing.map(|existing| existing.id);
+            if existing == Some(id) {
+                info!("Task already queued: {}", id);
+            } else {
+                if let Some(existing) = existing {
+                    // Superseded, the enqueue deleted it unless it is running
+                    if !pool.cancel(existing) {
+                        dispatcher
+                            .dispatch(WorkerEvent::QueueTaskUpdated {
+                                id: existing,
+                                status: TaskStatus::Cancelled,
+                                removed: true,
+                            })
+                            .await?;
+                    }
+                }
                 dispatcher
-                    .dispatch(WorkerEvent::QueueTaskUpdated {
-                        id: task.id,
-                        status: TaskStatus::Processing,
-                        removed: false,
+                    .dispatch(WorkerEvent::QueueTaskCreated {
+                        id,
+                        status: TaskStatus::Queued,
                     })
                     .await?;
-                if let Err(e) = pool
-                    .spawn(
-                        task,
-                        ctx.clone(),
-                    )
-                    .await
-                {
-                    error!("Error spawning task: {:?}", e);
-                }
-            } else {
-                info!("No task to process, waiting for a worker to finish");
-                break;
+            }
+            if let Some(callback) = callback {
+                let _ = callback.resolve(id).await;
             }
         }
+        Err(e) => {
+            error!("Error enqueuing task: {:?}", e);
+        }
     }
     Ok(())
 }
 
+/// Keep the run in the task history, it is archived before the task is deleted or put back in the queue
+async fn archive_run(
+    database: &Database,
+    run: &FinishedRun,
+    error: Option<&str>,
+    summary: TaskSummary,
+    retention: &TaskHistoryRetention,
+) -> Result<()> {
+    let status = match (error, run.cancelled) {
+        (_, true) => TaskStatus::Cancelled,
+        (None, false) => TaskStatus::Done,
+        (Some(_), false) => TaskStatus::Failed,
+    };
+    let duration = chrono::Utc::now() - run.started_at;
+    database
+        .archive_task_run(&models::TaskRun {
+            task_id: run.task.id,
+            name: run.task.payload.name().to_string(),
+            status: status.as_str().to_string(),
+            worker_slot: run.slot as i64,
+            started_at: run.started_at.timestamp(),
+            duration_ms: duration.num_milliseconds(),
+            error: error.map(|error| error.to_string()),
+            summary: (!summary.is_null()).then(|| summary.to_string()),
+        })
+        .await?;
+    database
+        .prune_task_history(retention.max_age_days * 24 * 60 * 60, retention.max_runs)
+        .await?;
+    Ok(())
+}
+
+struct RunningTask {
+    handle: tokio::task::JoinHandle<Result<()>>,
+    task: BackgroundTask,
+    cancel: CancellationToken,
+    /// Index of the pool slot running the task
+    slot: usize,
+    started_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Task that left the pool
+struct FinishedRun {
+    task: BackgroundTask,
+    slot: usize,
+    started_at: chrono::DateTime<chrono::Utc>,
+    cancelled: bool,
+}
+
 #[allow(dead_code)]
 struct WorkerPool {
-    max_size: usize,
+    concurrency: WorkerConcurrency,
     notify: AddressableService<WorkerTask>,
     dispatcher: EventDispatcher<WorkerEvent>,
-    background_handles: HashMap<i64, (tokio::task::JoinHandle<Result<()>>, BackgroundTask)>,
+    background_handles: HashMap<i64, RunningTask>,
 }
 
 impl WorkerPool {
     pub fn new(
-        nbr_worker: usize,
+        concurrency: WorkerConcurrency,
         notify: AddressableService<WorkerTask>,
         dispatcher: EventDispatcher<WorkerEvent>,
     ) -> Self {
         Self {
-            max_size: nbr_worker,
+            concurrency,
             notify,
             dispatcher,
             background_handles: HashMap::new(),
         }
     }
 
-    pub async fn done(&mut self, id: i64) {
+    pub async fn done(&mut self, id: i64) -> Option<FinishedRun> {
         match self.background_handles.remove(&id) {
-            Some((handle, task)) => {
-                if let Err(e) = handle.await {
-                    //TODO: retry task here based on task.retries or similar
-                    error!(task_id = task.id, "Task failed: {:?}", e);
+            Some(running) => {
+                if let Err(e) = running.handle.await {
+                    error!(task_id = running.task.id, "Task failed: {:?}", e);
                 }
+                Some(FinishedRun {
+                    cancelled: running.cancel.is_cancelled(),
+                    task: running.task,
+                    slot: running.slot,
+                    started_at: running.started_at,
+                })
             }
             None => {
                 error!("Worker pool: task not found: {}", id);
+                None
             }
-        };
+        }
     }
 
     pub async fn spawn(&mut self, task: TaskModel, context: ServiceContext) -> Result<()> {
         info!("Worker spawning task: {:?}", task);
         let task = BackgroundTask::try_from(task)?;
-        let handle = tokio::spawn(process_task(context, task.clone(), self.notify.clone()));
-        self.background_handles.insert(task.id, (handle, task));
+        let cancel = CancellationToken::new();
+        let slot = (0..)
+            .find(|slot| !self.background_handles.values().any(|running| running.slot == *slot))
+            .unwrap_or_default();
+        let handle = tokio::spawn(process_task(
+            context,
+            task.clone(),
+            cancel.clone(),
+            self.notify.clone(),
+        ));
+        self.background_handles.insert(
+            task.id,
+            RunningTask {
+                handle,
+                task,
+                cancel,
+                slot,
+                started_at: chrono::Utc::now(),
+            },
+        );
         Ok(())
     }
 
+    /// Returns false if the task is not running
+    pub fn cancel(&self, id: i64) -> bool {
+        match self.background_handles.get(&id) {
+            Some(running) => {
+                running.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn running_task_ids(&self) -> Vec<i64> {
+        self.background_handles.keys().copied().collect()
+    }
+
     pub fn has_empty_slots(&self) -> bool {
-        self.background_handles.len() < self.max_size
+        self.background_handles.len() < self.concurrency.pool_size
+    }
+
+    /// Resource classes that reached their concurrency limit
+    pub fn full_classes(&self) -> Vec<&'static str> {
+        ResourceClass::ALL
+            .into_iter()
+            .filter(|class| {
+                let running = self
+                    .background_handles
+                    .values()
+                    .filter(|running| running.task.payload.resource_class() == *class)
+                    .count();
+                running >= self.concurrency.limits.limit(*class)
+            })
+            .map(|class| class.as_str())
+            .collect()
     }
 }
 
 async fn process_task(
     context: ServiceContext,
     task: BackgroundTask,
+    cancel: CancellationToken,
     notify: AddressableService<WorkerTask>,
 ) -> Result<()> {
     info!("Processing task: {:?}", task);
-    if let Err(e) = task.process(context).await {
-        error!("Error processing task: {:?}", e);
-        notify
-            .send(WorkerTask::TaskDone {
-                id: task.id,
-                success: false,
-            })
-            .await?;
-    } else {
-        notify
-            .send(WorkerTask::TaskDone {
-                id: task.id,
-                success: true,
-            })
-            .await?;
-    }
+    let progress = TaskProgress::new(task.id, notify.clone());
+    let (error, summary) = match task.process(context, progress, cancel).await {
+        Ok(summary) => (None, summary),
+        Err(e) => {
+            error!("Error processing task: {:?}", e);
+            (Some(format!("{:#}", e)), TaskSummary::Null)
+        }
+    };
+    notify
+        .send(WorkerTask::TaskDone {
+            id: task.id,
+            error,
+            summary,
+        })
+        .await?;
     Ok(())
 }
 
@@ -195,11 +566,46 @@ impl Task for WorkerEvent {}
 
 pub trait WorkerDatabaseExt {
     fn enqueue_task(&self, task: TaskPayload) -> impl Future<Output = Result<i64>> + Send;
+    fn enqueue_task_with(
+        &self,
+        task: TaskPayload,
+        options: &TaskOptions,
+    ) -> impl Future<Output = Result<i64>> + Send;
 }
 
 impl WorkerDatabaseExt for Database {
     async fn enqueue_task(&self, task: TaskPayload) -> Result<i64> {
+        self.enqueue_task_with(task, &TaskOptions::default()).await
+    }
+
+    /// Returns the id of the queued or processing task with the same dedupe key if any,
+    /// unless `options.supersede` is set. A queued task takes the highest of both priorities
+    /// and waits for the dependencies of both
+    async fn enqueue_task_with(&self, task: TaskPayload, options: &TaskOptions) -> Result<i64> {
+        let dedupe_key = task.dedupe_key();
+        let existing = self.get_active_task_by_dedupe_key(&dedupe_key).await?;
+        if let Some(existing) = &existing {
+            if !options.supersede {
+                if existing.status == "queued" {
+                    self.merge_queued_task(existing.id, options.priority as i64, &options.depends_on)
+                        .await?;
+                }
+                return Ok(existing.id);
+            }
+        }
         let payload = serde_json::to_string(&task)?;
-        self.create_task(payload).await
+        let id = self
+            .create_task_with_dependencies(
+                payload,
+                Some(&dedupe_key),
+                task.resource_class().as_str(),
+                options.priority as i64,
+                &options.depends_on,
+            )
+            .await?;
+        if let Some(existing) = existing {
+            self.supersede_task(existing.id, id).await?;
+        }
+        Ok(id)
     }
 }
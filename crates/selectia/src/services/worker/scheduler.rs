@@ -0,0 +1,267 @@
+//! Jobs enqueued periodically by the worker, their next run is stored so a restart neither skips nor repeats them:
+//! a run missed while the app was closed is enqueued once on startup
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, Local, NaiveDateTime, TimeZone, Timelike};
+use eyre::bail;
+
+use super::tasks::{
+    DatabaseBackupTask, LibraryGcTask, ReanalyseGridsTask, RescanRootsTask, TaskPayload,
+};
+use crate::prelude::*;
+
+/// Searching further than that for the next run of a cron expression means it never matches (i.e `0 0 31 2 *`)
+const MAX_CRON_SEARCH_DAYS: i64 = 366 * 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScheduleSpec {
+    Cron(CronExpression),
+    /// In seconds
+    Interval(i64),
+}
+
+impl ScheduleSpec {
+    pub fn from_model(schedule: &models::Schedule) -> Result<Self> {
+        match (&schedule.cron, schedule.interval_seconds) {
+            (Some(cron), _) => Ok(ScheduleSpec::Cron(cron.parse()?)),
+            (None, Some(interval)) if interval > 0 => Ok(ScheduleSpec::Interval(interval)),
+            _ => bail!("Schedule {} has no valid cron expression nor interval", schedule.id),
+        }
+    }
+
+    /// First run strictly after the `timestamp`
+    pub fn next_run_after(&self, timestamp: i64) -> Result<i64> {
+        match self {
+            ScheduleSpec::Cron(cron) => cron.next_run_after(timestamp),
+            ScheduleSpec::Interval(interval) => Ok(timestamp + interval),
+        }
+    }
+
+    /// Values of the `cron` and `interval_seconds` columns
+    pub fn columns(&self) -> (Option<String>, Option<i64>) {
+        match self {
+            ScheduleSpec::Cron(cron) => (Some(cron.source.clone()), None),
+            ScheduleSpec::Interval(interval) => (None, Some(*interval)),
+        }
+    }
+}
+
+/// Standard five fields expression (minute, hour, day of month, month, day of week) supporting `*`, lists, ranges and steps
+#[derive(Clone, Debug, PartialEq)]
+pub struct CronExpression {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    /// Sunday is 0 (and 7)
+    weekdays: u64,
+    /// Restricted days of month and of week match when either does, as in crontab
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for CronExpression {
+    type Err = eyre::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let fields = source.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            bail!("Cron expression {:?} does not have five fields", source);
+        };
+        let mut weekdays_bits = parse_field(weekdays, 0, 7)?;
+        if weekdays_bits & (1 << 7) != 0 {
+            weekdays_bits = (weekdays_bits & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            source: source.to_string(),
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days: parse_field(days, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            weekdays: weekdays_bits,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+}
+
+impl CronExpression {
+    pub fn next_run_after(&self, timestamp: i64) -> Result<i64> {
+        let Some(after) = Local.timestamp_opt(timestamp, 0).single() else {
+            bail!("Invalid timestamp {}", timestamp);
+        };
+        let mut time = after.naive_local().with_second(0).unwrap() + Duration::minutes(1);
+        let limit = time + Duration::days(MAX_CRON_SEARCH_DAYS);
+        while time < limit {
+            if !contains(self.months, time.month()) {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = start_of_day(year, month, 1);
+            } else if !self.matches_day(&time) {
+                time = (time.date() + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap();
+            } else if !contains(self.hours, time.hour()) {
+                time = time.with_minute(0).unwrap() + Duration::hours(1);
+            } else if !contains(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                // Times skipped by a daylight saving change do not exist
+                match Local.from_local_datetime(&time).earliest() {
+                    Some(run) if run.timestamp() > timestamp => return Ok(run.timestamp()),
+                    _ => time += Duration::minutes(1),
+                }
+            }
+        }
+        bail!("Cron expression {:?} never matches", self.source)
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let day = contains(self.days, time.day());
+        let weekday = contains(self.weekdays, time.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+fn contains(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+fn start_of_day(year: i32, month: u32, day: u32) -> NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+/// Bits of the values matched by a comma separated list of `*`, `a`, `a-b`, each optionally followed by `/step`
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>()?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            bail!("Invalid step in cron field {:?}", field);
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (start.parse::<u32>()?, end.parse::<u32>()?),
+                // `a/step` goes up to the maximum
+                None if part.contains('/') => (range.parse::<u32>()?, max),
+                None => {
+                    let value = range.parse::<u32>()?;
+                    (value, value)
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            bail!("Cron field {:?} out of range {}-{}", field, min, max);
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+/// Register `payload` to be enqueued on `spec`, nothing is done when a schedule with the same name exists
+pub async fn create_schedule(
+    database: &Database,
+    name: &str,
+    payload: &TaskPayload,
+    spec: &ScheduleSpec,
+) -> Result<Option<i64>> {
+    let (cron, interval_seconds) = spec.columns();
+    let next_run_at = spec.next_run_after(chrono::Utc::now().timestamp())?;
+    database
+        .create_schedule(
+            name,
+            &serde_json::to_string(payload)?,
+            cron.as_deref(),
+            interval_seconds,
+            next_run_at,
+        )
+        .await
+}
+
+/// Maintenance jobs of the library, backups are written to `backup_directory`
+pub async fn install_default_schedules(database: &Database, backup_directory: PathBuf) -> Result<()> {
+    let schedules = [
+        (
+            "Rescan library roots",
+            TaskPayload::RescanRoots(RescanRootsTask {}),
+            ScheduleSpec::Cron("0 3 * * *".parse()?),
+        ),
+        (
+            "Library garbage collection",
+            TaskPayload::LibraryGc(LibraryGcTask {}),
+            ScheduleSpec::Cron("30 3 * * 0".parse()?),
+        ),
+        (
+            "Re-analyse low confidence grids",
+            TaskPayload::ReanalyseGrids(ReanalyseGridsTask::default()),
+            ScheduleSpec::Cron("0 4 * * 0".parse()?),
+        ),
+        (
+            "Database backup",
+            TaskPayload::DatabaseBackup(DatabaseBackupTask {
+                directory: backup_directory,
+                keep: DatabaseBackupTask::DEFAULT_KEEP,
+            }),
+            ScheduleSpec::Interval(24 * 60 * 60),
+        ),
+    ];
+    for (name, payload, spec) in schedules.iter() {
+        if let Some(id) = create_schedule(database, name, payload, spec).await? {
+            info!(id, name, "Schedule created");
+        }
+    }
+    Ok(())
+}
+
+/// Move the due schedules to their next run and return the payloads to enqueue,
+/// a schedule claimed by a concurrent call is left to it
+pub async fn claim_due_schedules(database: &Database) -> Result<Vec<TaskPayload>> {
+    let now = chrono::Utc::now().timestamp();
+    let mut payloads = vec![];
+    for schedule in database.get_due_schedules().await? {
+        let next_run_at = match ScheduleSpec::from_model(&schedule).and_then(|spec| spec.next_run_after(now)) {
+            Ok(next_run_at) => next_run_at,
+            Err(e) => {
+                error!(id = schedule.id, error = ?e, "Invalid schedule, disabling it");
+                database
+                    .set_schedule_enabled(schedule.id, false, schedule.next_run_at)
+                    .await?;
+                continue;
+            }
+        };
+        if !database
+            .claim_schedule_run(schedule.id, schedule.next_run_at, next_run_at)
+            .await?
+        {
+            continue;
+        }
+        match serde_json::from_str::<TaskPayload>(&schedule.payload) {
+            Ok(payload) => payloads.push(payload),
+            Err(e) => error!(id = schedule.id, error = ?e, "Invalid schedule payload"),
+        }
+    }
+    Ok(payloads)
+}
+
+/// Enabling a schedule starts it from now, runs missed while it was disabled are skipped
+pub async fn set_schedule_enabled(database: &Database, id: i64, enabled: bool) -> Result<()> {
+    let schedule = database.get_schedule(id).await?;
+    let next_run_at = match enabled {
+        true => ScheduleSpec::from_model(&schedule)?.next_run_after(chrono::Utc::now().timestamp())?,
+        false => schedule.next_run_at,
+    };
+    database.set_schedule_enabled(id, enabled, next_run_at).await
+}
@@ -0,0 +1,58 @@
+use crate::prelude::*;
+use crate::tasks::analysis::{analyse, AnalysisStage};
+
+use super::{until_cancelled, CancellationToken, StemExtractionTask, TaskProgress, TaskSummary};
+
+/// Stage of the analysis pipeline, skipped when it was already done for the content of the metadata
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct AnalysisTask {
+    pub metadata_id: i64,
+    pub stage: AnalysisStage,
+}
+
+impl AnalysisTask {
+    #[instrument(skip(context, progress, cancel))]
+    pub async fn process<T: ServiceHostContext>(
+        &self,
+        context: &T,
+        progress: &TaskProgress,
+        cancel: &CancellationToken,
+    ) -> Result<TaskSummary> {
+        let database = context.get_singleton::<Database>().await?;
+        let metadata = database.get_metadata(self.metadata_id).await?;
+        let completed = database.get_completed_analysis_stages(&metadata.hash).await?;
+        if completed.iter().any(|stage| stage == self.stage.as_str()) {
+            info!("Analysis stage already done for this content");
+            return Ok(serde_json::json!({ "stage": self.stage, "skipped": true }));
+        }
+        let detected = match self.stage {
+            AnalysisStage::Stems if has_stems(&database, self.metadata_id).await? => {
+                info!("Stems already extracted");
+                false
+            }
+            AnalysisStage::Stems => {
+                StemExtractionTask {
+                    metadata_id: self.metadata_id,
+                }
+                .process(context, progress, cancel)
+                .await?;
+                true
+            }
+            stage => until_cancelled(cancel, analyse(&database, self.metadata_id, stage)).await?,
+        };
+        database
+            .complete_analysis_stage(&metadata.hash, self.stage.as_str())
+            .await?;
+        Ok(serde_json::json!({ "stage": self.stage, "skipped": false, "detected": detected }))
+    }
+}
+
+/// Stems extracted before the stage was recorded
+async fn has_stems(database: &Database, metadata_id: i64) -> Result<bool> {
+    let file = database.get_file_from_metadata_id(metadata_id).await?;
+    let variations = database.get_file_variations(file.id).await?;
+    Ok(variations
+        .iter()
+        .any(|variation| variation.metadata.as_ref().is_some_and(|metadata| metadata.stem.is_some())))
+}
@@ -0,0 +1,24 @@
+use crate::prelude::*;
+use crate::tasks::backup::backup_database;
+
+use super::TaskSummary;
+
+/// Copy the database to `directory`, only the `keep` most recent copies are kept
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct DatabaseBackupTask {
+    pub directory: PathBuf,
+    pub keep: usize,
+}
+
+impl DatabaseBackupTask {
+    pub const DEFAULT_KEEP: usize = 7;
+
+    #[instrument(skip(context))]
+    pub async fn process<T: ServiceHostContext>(&self, context: &T) -> Result<TaskSummary> {
+        let database = context.get_singleton::<Database>().await?;
+        let path = backup_database(&database, &self.directory, self.keep).await?;
+        info!(path = ?path, "Database backed up");
+        Ok(serde_json::json!({ "path": path }))
+    }
+}
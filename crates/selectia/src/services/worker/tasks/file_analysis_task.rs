@@ -1,4 +1,4 @@
-use std::{borrow::Cow, result};
+use std::{borrow::Cow, ops::ControlFlow, result};
 
 use dasp::{signal::rms::SignalRms, Sample, Signal};
 use selectia_audio_file::{audio_file::{AudioFilePayload, EncodedAudioFile}, error::AudioFileResult};
@@ -8,6 +8,8 @@ use wavision::prelude::*;
 
 use crate::{analyser::bpm_analyser::{BpmAnalyser, BpmAnalyserOptions}, prelude::*};
 
+use super::{CancellationToken, TaskProgress};
+
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(tag = "type")]
@@ -16,8 +18,14 @@ pub struct FileAnalysisTask {
 }
 
 impl FileAnalysisTask {
-    #[instrument(skip(context))]
-    pub async fn process<T: ServiceHostContext>(&self, context: &T) -> Result<()> {
+    /// The stem separation stops between two segments once `cancel` is triggered
+    #[instrument(skip(context, progress, cancel))]
+    pub async fn process<T: ServiceHostContext>(
+        &self,
+        context: &T,
+        progress: &TaskProgress,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
         use fundsp::prelude::*;
         const MIN_ANALYSIS_DURATION: f64 = 180.0;
 
@@ -44,22 +52,34 @@ impl FileAnalysisTask {
         .await??;
 
         //Demux
-        let spleeter_model =
-            spleeter::get_models_from_index(PathBuf::from("../../../models/spleeter/index.json"))
+        let separation_progress = progress.clone();
+        let separation_cancel = cancel.clone();
+        let (payload, result) = tokio::task::spawn_blocking(move || {
+            let spleeter_model =
+                spleeter::get_models_from_index(PathBuf::from("../../../models/spleeter/index.json"))
+                    .unwrap();
+            let model = spleeter_model
+                .iter()
+                .find(|m| m.info.name == "4stems")
                 .unwrap();
-        let model = spleeter_model
-            .iter()
-            .find(|m| m.info.name == "4stems")
-            .unwrap();
-        let result = spleeter::split_pcm_audio(
-            &AudioData {
-                sample_rate: payload.sample_rate as usize,
-                nb_channels: payload.channels as usize,
-                samples: Cow::Borrowed(&payload.buffer.buffer),
-            },
-            &model,
-        )
-        .unwrap();
+            let result = spleeter::split_pcm_audio_with_progress(
+                &AudioData {
+                    sample_rate: payload.sample_rate as usize,
+                    nb_channels: payload.channels as usize,
+                    samples: Cow::Borrowed(&payload.buffer.buffer),
+                },
+                model,
+                |done, count| {
+                    let _ = separation_progress.blocking_report("Separating stems", done as f64 / count as f64);
+                    match separation_cancel.is_cancelled() {
+                        true => ControlFlow::Break(()),
+                        false => ControlFlow::Continue(()),
+                    }
+                },
+            )?;
+            eyre::Ok((payload, result))
+        })
+        .await??;
 
         let drums_stem = result.iter().find(|s| s.name == "drums").cloned().unwrap().data;
         let payload = AudioFilePayload::from_interleaved_samples(
@@ -0,0 +1,18 @@
+use crate::prelude::*;
+use crate::tasks::duplicates::fingerprint_metadata;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct FingerprintTask {
+    pub metadata_id: i64,
+}
+
+impl FingerprintTask {
+    #[instrument(skip(context))]
+    pub async fn process<T: ServiceHostContext>(&self, context: &T) -> Result<()> {
+        let database = context.get_singleton::<Database>().await?;
+        let fingerprint = fingerprint_metadata(&database, self.metadata_id).await?;
+        info!(codes = fingerprint.codes.len(), "Fingerprint computed");
+        Ok(())
+    }
+}
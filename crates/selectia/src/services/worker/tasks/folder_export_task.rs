@@ -0,0 +1,28 @@
+use crate::prelude::*;
+use crate::tasks::folder_export::{ExportedFileStatus, FolderExport, FolderExportOptions};
+use crate::tasks::selection::ExportSelection;
+
+use super::TaskProgress;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct FolderExportTask {
+    pub selection: ExportSelection,
+    pub options: FolderExportOptions,
+}
+
+impl FolderExportTask {
+    #[instrument(skip(context, progress))]
+    pub async fn process<T: ServiceHostContext>(&self, context: &T, progress: &TaskProgress) -> Result<()> {
+        let database = context.get_singleton::<Database>().await?;
+        let report = FolderExport::new(database, self.selection.clone(), self.options.clone())?
+            .run(Some(progress))
+            .await?;
+        info!(
+            exported = report.files.len() - report.count(ExportedFileStatus::Failed),
+            failed = report.count(ExportedFileStatus::Failed),
+            "Folder export done"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use crate::prelude::*;
+use crate::tasks::doctor::{Doctor, DoctorIssueKind};
+
+use super::TaskSummary;
+
+/// Apply the safe fixes of the doctor, tasks in processing status are left to the worker running them
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct LibraryGcTask {}
+
+impl LibraryGcTask {
+    #[instrument(skip(context))]
+    pub async fn process<T: ServiceHostContext>(&self, context: &T) -> Result<TaskSummary> {
+        let database = context.get_singleton::<Database>().await?;
+        let doctor = Doctor::new(database);
+        let mut report = doctor.check().await?;
+        report
+            .issues
+            .retain(|issue| issue.kind.is_fixable() && issue.kind != DoctorIssueKind::StuckTask);
+        let report = doctor.fix(&report).await?;
+        info!(fixed = report.fixed, remaining = report.issues.len(), "Library garbage collection done");
+        Ok(serde_json::json!({ "fixed": report.fixed, "remaining": report.issues.len() }))
+    }
+}
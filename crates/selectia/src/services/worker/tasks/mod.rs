@@ -1,12 +1,33 @@
+use std::time::Instant;
+
 use crate::prelude::*;
 use eyre::bail;
 use models::Task;
 
+use crate::tasks::analysis::AnalysisStage;
+
+mod analysis_task;
+mod database_backup_task;
 mod file_analysis_task;
+mod fingerprint_task;
+mod folder_export_task;
+mod library_gc_task;
+mod reanalyse_grids_task;
+mod rescan_roots_task;
 mod stem_extraction_task;
+mod verify_library_task;
 
+pub use analysis_task::AnalysisTask;
+pub use database_backup_task::DatabaseBackupTask;
 pub use file_analysis_task::FileAnalysisTask;
+pub use fingerprint_task::FingerprintTask;
+pub use folder_export_task::FolderExportTask;
+pub use library_gc_task::LibraryGcTask;
+pub use reanalyse_grids_task::ReanalyseGridsTask;
+pub use rescan_roots_task::RescanRootsTask;
 pub use stem_extraction_task::StemExtractionTask;
+pub use verify_library_task::VerifyLibraryTask;
+pub use tokio_util::sync::CancellationToken;
 
 #[derive(Clone, Debug)]
 pub struct BackgroundTask {
@@ -21,6 +42,22 @@ pub enum TaskStatus {
     Queued,
     Processing,
     Done,
+    /// Ran out of attempts, kept until retried by the user
+    Failed,
+    /// Removed from the queue or stopped by the user
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Queued => "queued",
+            TaskStatus::Processing => "processing",
+            TaskStatus::Done => "done",
+            TaskStatus::Failed => "failed",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
 }
 
 impl TryFrom<&str> for TaskStatus {
@@ -31,15 +68,234 @@ impl TryFrom<&str> for TaskStatus {
             "queued" => Ok(TaskStatus::Queued),
             "processing" => Ok(TaskStatus::Processing),
             "done" => Ok(TaskStatus::Done),
+            "failed" => Ok(TaskStatus::Failed),
+            "cancelled" => Ok(TaskStatus::Cancelled),
             _ => bail!("Invalid task status: {}", value),
         }
     }
 }
 
+/// Outcome of a task kept in the task history, such as the counts of a library verification,
+/// `Null` for the tasks with nothing to report
+pub type TaskSummary = serde_json::Value;
+
+/// Queued tasks of higher priority are processed first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TaskPriority {
+    /// Bulk or automatic work
+    Background = -10,
+    #[default]
+    Normal = 0,
+    /// Started by the user, who is waiting for it
+    User = 10,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TaskOptions {
+    pub priority: TaskPriority,
+    /// Tasks that have to leave the queue before this one starts
+    pub depends_on: Vec<i64>,
+    /// Replace the queued or processing task with the same dedupe key instead of reusing it
+    pub supersede: bool,
+}
+
+impl TaskOptions {
+    pub fn priority(priority: TaskPriority) -> Self {
+        Self {
+            priority,
+            ..Default::default()
+        }
+    }
+}
+
+/// Resource a task mostly uses, each class has its own concurrency limit within the worker pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceClass {
+    /// CPU heavy stem separation
+    Separation,
+    /// File copies and reads
+    Io,
+    /// Light analysis
+    Analysis,
+}
+
+impl ResourceClass {
+    pub const ALL: [ResourceClass; 3] = [ResourceClass::Separation, ResourceClass::Io, ResourceClass::Analysis];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceClass::Separation => "separation",
+            ResourceClass::Io => "io",
+            ResourceClass::Analysis => "analysis",
+        }
+    }
+}
+
+/// Tasks of each resource class allowed to run at the same time
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    pub separation: usize,
+    pub io: usize,
+    pub analysis: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            separation: 1,
+            io: 2,
+            analysis: 2,
+        }
+    }
+}
+
+impl ResourceLimits {
+    pub fn limit(&self, class: ResourceClass) -> usize {
+        match class {
+            ResourceClass::Separation => self.separation,
+            ResourceClass::Io => self.io,
+            ResourceClass::Analysis => self.analysis,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum TaskPayload {
     FileAnalysis(FileAnalysisTask),
     StemExtraction(StemExtractionTask),
+    FolderExport(FolderExportTask),
+    Fingerprint(FingerprintTask),
+    VerifyLibrary(VerifyLibraryTask),
+    Analysis(AnalysisTask),
+    RescanRoots(RescanRootsTask),
+    LibraryGc(LibraryGcTask),
+    ReanalyseGrids(ReanalyseGridsTask),
+    DatabaseBackup(DatabaseBackupTask),
+}
+
+impl TaskPayload {
+    pub fn resource_class(&self) -> ResourceClass {
+        match self {
+            TaskPayload::FileAnalysis(_) => ResourceClass::Separation,
+            TaskPayload::StemExtraction(_) => ResourceClass::Separation,
+            TaskPayload::FolderExport(_) => ResourceClass::Io,
+            TaskPayload::Fingerprint(_) => ResourceClass::Analysis,
+            TaskPayload::VerifyLibrary(_) => ResourceClass::Io,
+            TaskPayload::Analysis(task) => match task.stage {
+                AnalysisStage::Metadata => ResourceClass::Io,
+                AnalysisStage::Stems => ResourceClass::Separation,
+                _ => ResourceClass::Analysis,
+            },
+            TaskPayload::RescanRoots(_) => ResourceClass::Io,
+            TaskPayload::LibraryGc(_) => ResourceClass::Io,
+            TaskPayload::ReanalyseGrids(_) => ResourceClass::Analysis,
+            TaskPayload::DatabaseBackup(_) => ResourceClass::Io,
+        }
+    }
+
+    /// Tasks with the same key do the same work
+    pub fn dedupe_key(&self) -> String {
+        match self {
+            TaskPayload::FileAnalysis(task) => format!("FileAnalysis:{}", task.metadata_id),
+            TaskPayload::StemExtraction(task) => format!("StemExtraction:{}", task.metadata_id),
+            TaskPayload::FolderExport(task) => format!(
+                "FolderExport:{}",
+                serde_json::to_string(task).unwrap_or_default()
+            ),
+            TaskPayload::Fingerprint(task) => format!("Fingerprint:{}", task.metadata_id),
+            TaskPayload::VerifyLibrary(_) => "VerifyLibrary".to_string(),
+            // Stems are extracted by the same task as a standalone stem extraction
+            TaskPayload::Analysis(AnalysisTask {
+                metadata_id,
+                stage: AnalysisStage::Stems,
+            }) => format!("StemExtraction:{}", metadata_id),
+            TaskPayload::Analysis(task) => format!("Analysis:{}:{}", task.stage.as_str(), task.metadata_id),
+            TaskPayload::RescanRoots(_) => "RescanRoots".to_string(),
+            TaskPayload::LibraryGc(_) => "LibraryGc".to_string(),
+            TaskPayload::ReanalyseGrids(_) => "ReanalyseGrids".to_string(),
+            TaskPayload::DatabaseBackup(task) => format!("DatabaseBackup:{}", task.directory.display()),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskPayload::FileAnalysis(_) => "FileAnalysis",
+            TaskPayload::StemExtraction(_) => "StemExtraction",
+            TaskPayload::FolderExport(_) => "FolderExport",
+            TaskPayload::Fingerprint(_) => "Fingerprint",
+            TaskPayload::VerifyLibrary(_) => "VerifyLibrary",
+            TaskPayload::Analysis(_) => "Analysis",
+            TaskPayload::RescanRoots(_) => "RescanRoots",
+            TaskPayload::LibraryGc(_) => "LibraryGc",
+            TaskPayload::ReanalyseGrids(_) => "ReanalyseGrids",
+            TaskPayload::DatabaseBackup(_) => "DatabaseBackup",
+        }
+    }
+}
+
+/// Handle given to a running task to report its progress through `WorkerEvent::QueueTaskProgress`
+#[derive(Clone)]
+pub struct TaskProgress {
+    id: i64,
+    notify: AddressableService<WorkerTask>,
+    /// Current step and when it started, to estimate the remaining time
+    step: Arc<std::sync::Mutex<(String, Instant)>>,
+}
+
+impl TaskProgress {
+    pub fn new(id: i64, notify: AddressableService<WorkerTask>) -> Self {
+        Self {
+            id,
+            notify,
+            step: Arc::new(std::sync::Mutex::new((String::new(), Instant::now()))),
+        }
+    }
+
+    /// `progress` goes from 0 to 1 within `step`
+    pub async fn report(&self, step: &str, progress: f64) -> Result<()> {
+        self.notify.send(self.progress_task(step, progress)).await?;
+        Ok(())
+    }
+
+    /// Same as `report`, from outside of the async runtime
+    pub fn blocking_report(&self, step: &str, progress: f64) -> Result<()> {
+        self.notify.blocking_send(self.progress_task(step, progress))?;
+        Ok(())
+    }
+
+    /// Forward the progress sent to the returned channel until it is dropped
+    pub fn forward(&self, step: &str) -> tokio::sync::mpsc::UnboundedSender<f64> {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let progress = self.clone();
+        let step = step.to_string();
+        tokio::spawn(async move {
+            while let Some(value) = receiver.recv().await {
+                if progress.report(&step, value).await.is_err() {
+                    break;
+                }
+            }
+        });
+        sender
+    }
+
+    fn progress_task(&self, step: &str, progress: f64) -> WorkerTask {
+        let progress = progress.clamp(0.0, 1.0);
+        let mut current = self.step.lock().unwrap();
+        if current.0 != step {
+            *current = (step.to_string(), Instant::now());
+        }
+        // Linear estimation from the time spent in the current step
+        let elapsed = current.1.elapsed().as_secs_f64();
+        let eta = (progress > 0.0).then(|| elapsed * (1.0 - progress) / progress);
+        WorkerTask::TaskProgress {
+            id: self.id,
+            step: step.to_string(),
+            progress,
+            eta,
+        }
+    }
 }
 
 impl TryFrom<Task> for BackgroundTask {
@@ -55,10 +311,48 @@ impl TryFrom<Task> for BackgroundTask {
 }
 
 impl BackgroundTask {
-    pub async fn process<T: ServiceHostContext>(&self, context: T) -> Result<()> {
+    /// Tasks stop at their next cancellation point once `cancel` is triggered,
+    /// returns the summary kept in the task history
+    pub async fn process<T: ServiceHostContext>(
+        &self,
+        context: T,
+        progress: TaskProgress,
+        cancel: CancellationToken,
+    ) -> Result<TaskSummary> {
         match &self.payload {
-            TaskPayload::FileAnalysis(task) => task.process(&context).await,
-            TaskPayload::StemExtraction(task) => task.process(&context).await,
+            TaskPayload::FileAnalysis(task) => {
+                task.process(&context, &progress, &cancel).await.map(TaskSummary::from)
+            }
+            TaskPayload::StemExtraction(task) => {
+                task.process(&context, &progress, &cancel).await.map(TaskSummary::from)
+            }
+            TaskPayload::FolderExport(task) => {
+                until_cancelled(&cancel, task.process(&context, &progress)).await.map(TaskSummary::from)
+            }
+            TaskPayload::Fingerprint(task) => {
+                until_cancelled(&cancel, task.process(&context)).await.map(TaskSummary::from)
+            }
+            TaskPayload::VerifyLibrary(task) => {
+                until_cancelled(&cancel, task.process(&context, &progress)).await
+            }
+            TaskPayload::Analysis(task) => task.process(&context, &progress, &cancel).await,
+            TaskPayload::RescanRoots(task) => until_cancelled(&cancel, task.process(&context)).await,
+            TaskPayload::LibraryGc(task) => until_cancelled(&cancel, task.process(&context)).await,
+            TaskPayload::ReanalyseGrids(task) => {
+                until_cancelled(&cancel, task.process(&context, &progress)).await
+            }
+            TaskPayload::DatabaseBackup(task) => until_cancelled(&cancel, task.process(&context)).await,
         }
     }
 }
+
+/// Drop `future` at its next await point once `cancel` is triggered
+async fn until_cancelled<T>(
+    cancel: &CancellationToken,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        result = future => result,
+        _ = cancel.cancelled() => bail!("Task cancelled"),
+    }
+}
@@ -0,0 +1,49 @@
+use crate::prelude::*;
+use crate::tasks::analysis::{analyse, AnalysisStage};
+
+use super::{TaskProgress, TaskSummary};
+
+/// Detect the grid again of the tracks whose grid confidence is low, once their last analysis is old enough
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct ReanalyseGridsTask {
+    pub min_confidence: f64,
+    /// In seconds since the last grid analysis
+    pub min_age: i64,
+}
+
+impl Default for ReanalyseGridsTask {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.5,
+            min_age: 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl ReanalyseGridsTask {
+    #[instrument(skip(context, progress))]
+    pub async fn process<T: ServiceHostContext>(&self, context: &T, progress: &TaskProgress) -> Result<TaskSummary> {
+        let database = context.get_singleton::<Database>().await?;
+        let metadata_ids = database
+            .get_low_confidence_grid_metadata_ids(self.min_confidence, self.min_age)
+            .await?;
+        let mut failed = 0;
+        for (idx, metadata_id) in metadata_ids.iter().enumerate() {
+            progress
+                .report("Analysing grids", idx as f64 / metadata_ids.len() as f64)
+                .await?;
+            if let Err(e) = analyse(&database, *metadata_id, AnalysisStage::Grid).await {
+                error!(metadata_id, error = ?e, "Error analysing grid");
+                failed += 1;
+                continue;
+            }
+            let metadata = database.get_metadata(*metadata_id).await?;
+            database
+                .complete_analysis_stage(&metadata.hash, AnalysisStage::Grid.as_str())
+                .await?;
+        }
+        info!(count = metadata_ids.len(), failed, "Grids analysed again");
+        Ok(serde_json::json!({ "analysed": metadata_ids.len() - failed, "failed": failed }))
+    }
+}
@@ -0,0 +1,28 @@
+use crate::prelude::*;
+
+use super::TaskSummary;
+
+/// Load every library root again, with its import rules, to pick up the files added since the last scan
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct RescanRootsTask {}
+
+impl RescanRootsTask {
+    #[instrument(skip(context))]
+    pub async fn process<T: ServiceHostContext>(&self, context: &T) -> Result<TaskSummary> {
+        let database = context.get_singleton::<Database>().await?;
+        let file_loader = context.get_singleton_address::<FileLoader>().await?;
+        let roots = database.get_library_roots().await?;
+        let mut missing = vec![];
+        for root in roots.iter() {
+            // The drive holding it may only be unplugged
+            let Ok(load) = LoadDirectory::new(file_loader.clone(), PathBuf::from(&root.path)) else {
+                warn!(path = root.path, "Library root not found, skipping it");
+                missing.push(root.path.clone());
+                continue;
+            };
+            load.with_rules(root.import_rules()).load().await?;
+        }
+        Ok(serde_json::json!({ "roots": roots.len(), "missing": missing }))
+    }
+}
@@ -1,6 +1,10 @@
 use models::FileVariationMetadata;
 
 use crate::prelude::*;
+use crate::tasks::analysis::AnalysisStage;
+use eyre::bail;
+
+use super::{CancellationToken, TaskProgress};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(tag = "type")]
@@ -10,7 +14,12 @@ pub struct StemExtractionTask {
 
 
 impl StemExtractionTask {
-    pub async fn process<T: ServiceHostContext>(&self, context: &T) -> Result<()> {
+    pub async fn process<T: ServiceHostContext>(
+        &self,
+        context: &T,
+        progress: &TaskProgress,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
         info!("Processing stem extraction task: {:?}", self);
 
         let database = context.get_singleton::<Database>().await?;
@@ -29,11 +38,17 @@ impl StemExtractionTask {
             .send(DemuxerTask::Demux {
                 input: PathBuf::from(file.path),
                 output: output_path.clone(),
+                progress: Some(progress.forward("Separating stems")),
+                cancel: cancel.clone(),
                 callback,
             })
             .await?;
         info!("Waiting for stem extraction task to complete");
-        let result = recv.wait().await?;
+        let result = tokio::select! {
+            result = recv.wait() => result?,
+            // The demuxer stops the separation with the same token
+            _ = cancel.cancelled() => bail!("Stem extraction cancelled"),
+        };
         info!("Stem extraction task completed, creating file variations");
         for variation in result.stems.iter() {
             let metadata = FileVariationMetadata {
@@ -53,6 +68,11 @@ impl StemExtractionTask {
                 Err(e) => error!("Failed to create file variation: {}", e),
             }
         }
+        // The analysis pipeline does not extract them again
+        let metadata = database.get_metadata(self.metadata_id).await?;
+        database
+            .complete_analysis_stage(&metadata.hash, AnalysisStage::Stems.as_str())
+            .await?;
         Ok(())
     }
 }
@@ -0,0 +1,24 @@
+use crate::prelude::*;
+use crate::tasks::verify_library::{LibraryVerification, VerifiedFileStatus};
+
+use super::{TaskProgress, TaskSummary};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct VerifyLibraryTask {}
+
+impl VerifyLibraryTask {
+    #[instrument(skip(context, progress))]
+    pub async fn process<T: ServiceHostContext>(&self, context: &T, progress: &TaskProgress) -> Result<TaskSummary> {
+        let database = context.get_singleton::<Database>().await?;
+        let report = LibraryVerification::new(database).run(Some(progress)).await?;
+        let summary = serde_json::json!({
+            "unchanged": report.count(VerifiedFileStatus::Unchanged),
+            "changed": report.count(VerifiedFileStatus::Changed),
+            "missing": report.count(VerifiedFileStatus::Missing),
+            "failed": report.count(VerifiedFileStatus::Failed),
+        });
+        info!(%summary, "Library verification done");
+        Ok(summary)
+    }
+}
@@ -0,0 +1,258 @@
+//! Analysis pipeline run on the newly ingested tracks, each stage is a worker task waiting only for the stage
+//! whose output it needs, and a stage done for a content is not run again for another file with the same hash
+use selectia_audio_file::audio_file::{AudioFilePayload, EncodedAudioFile};
+use symphonia::core::meta::{StandardTagKey, Tag as FileTag};
+
+use crate::analyser::{
+    bpm_analyser::{BpmAnalyser, BpmAnalyserOptions},
+    chroma_fingerprint,
+    key_analyser::KeyAnalysis,
+    loudness_analyser::LoudnessAnalysis,
+    waveform_preview::{waveform_preview, WAVEFORM_PREVIEW_SIZE},
+};
+use crate::prelude::*;
+use crate::tasks::reconciliation::format_bpm;
+use eyre::bail;
+
+/// Source of the tags and beat grid found by the analysis
+pub const ANALYSIS_SOURCE: &str = "analysis";
+/// Source of the tags read from the file
+pub const FILE_TAGS_SOURCE: &str = "file";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalysisStage {
+    /// Tags embedded in the file
+    Metadata,
+    Waveform,
+    /// Tempo and beat grid
+    Grid,
+    Key,
+    Loudness,
+    Stems,
+}
+
+impl AnalysisStage {
+    /// In the order they are chained
+    pub const ALL: [AnalysisStage; 6] = [
+        AnalysisStage::Metadata,
+        AnalysisStage::Waveform,
+        AnalysisStage::Grid,
+        AnalysisStage::Key,
+        AnalysisStage::Loudness,
+        AnalysisStage::Stems,
+    ];
+
+    /// Stage that has to complete first, the tags read from the file take precedence over the detected ones
+    pub fn depends_on(&self) -> Option<AnalysisStage> {
+        match self {
+            AnalysisStage::Grid | AnalysisStage::Key => Some(AnalysisStage::Metadata),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnalysisStage::Metadata => "metadata",
+            AnalysisStage::Waveform => "waveform",
+            AnalysisStage::Grid => "grid",
+            AnalysisStage::Key => "key",
+            AnalysisStage::Loudness => "loudness",
+            AnalysisStage::Stems => "stems",
+        }
+    }
+}
+
+/// Stages run on the new tracks, stems are costly and only extracted when asked for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AnalysisPipelineSettings {
+    pub enabled: bool,
+    pub metadata: bool,
+    pub waveform: bool,
+    pub grid: bool,
+    pub key: bool,
+    pub loudness: bool,
+    pub stems: bool,
+}
+
+impl Default for AnalysisPipelineSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            metadata: true,
+            waveform: true,
+            grid: true,
+            key: true,
+            loudness: true,
+            stems: false,
+        }
+    }
+}
+
+impl AnalysisPipelineSettings {
+    pub fn is_enabled(&self, stage: AnalysisStage) -> bool {
+        self.enabled
+            && match stage {
+                AnalysisStage::Metadata => self.metadata,
+                AnalysisStage::Waveform => self.waveform,
+                AnalysisStage::Grid => self.grid,
+                AnalysisStage::Key => self.key,
+                AnalysisStage::Loudness => self.loudness,
+                AnalysisStage::Stems => self.stems,
+            }
+    }
+}
+
+/// Enabled stages not done yet for the content of the metadata, in the order of `AnalysisStage::ALL`
+pub async fn pending_analysis_stages(
+    database: &Database,
+    metadata_id: i64,
+    settings: &AnalysisPipelineSettings,
+) -> Result<Vec<AnalysisStage>> {
+    let metadata = database.get_metadata(metadata_id).await?;
+    let completed = database.get_completed_analysis_stages(&metadata.hash).await?;
+    Ok(AnalysisStage::ALL
+        .into_iter()
+        .filter(|stage| settings.is_enabled(*stage) && !completed.iter().any(|done| done == stage.as_str()))
+        .collect())
+}
+
+/// Run `stage` on the first file of the metadata, stems are extracted by the demuxer instead
+///
+/// Returns false when nothing was detected (i.e no key in a drum loop), the stage is still done
+pub async fn analyse(database: &Database, metadata_id: i64, stage: AnalysisStage) -> Result<bool> {
+    let file = database.get_file_from_metadata_id(metadata_id).await?;
+    let path = PathBuf::from(&file.path);
+    match stage {
+        AnalysisStage::Metadata => {
+            let tags = tokio::task::spawn_blocking(move || {
+                eyre::Ok(EncodedAudioFile::from_file(&path)?.tags())
+            })
+            .await??;
+            let tags = tags.iter().filter_map(file_tag).collect();
+            set_missing_tags(database, metadata_id, tags, FILE_TAGS_SOURCE).await?;
+        }
+        AnalysisStage::Waveform => {
+            let payload = tokio::task::spawn_blocking(move || decode_mono(&path)).await??;
+            let waveform = waveform_preview(&payload.buffer.buffer, WAVEFORM_PREVIEW_SIZE);
+            database.set_waveform_preview(metadata_id, &waveform).await?;
+        }
+        AnalysisStage::Grid => {
+            let (first_onset, result) = tokio::task::spawn_blocking(move || {
+                let onsets = decode_mono(&path)?.detect_onesets(512, 256)?;
+                let first_onset = onsets.first().map(|onset| onset.seconds).unwrap_or_default();
+                // Only fails when there are too few or too irregular onsets
+                let result = BpmAnalyser::new(BpmAnalyserOptions { range: (80.0, 280.0) }, onsets).get_result();
+                eyre::Ok((first_onset, result))
+            })
+            .await??;
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    info!(path = file.path, reason = %e, "No tempo detected");
+                    return Ok(false);
+                }
+            };
+            set_missing_tags(
+                database,
+                metadata_id,
+                vec![(TagName::TEMPO_ID, format_bpm(result.average_bpm))],
+                ANALYSIS_SOURCE,
+            )
+            .await?;
+            // Grids imported from other software are kept
+            let grid = database.get_beat_grid(metadata_id).await?;
+            if grid.iter().all(|marker| marker.source.as_deref() == Some(ANALYSIS_SOURCE)) {
+                let marker = models::TempoMarker {
+                    position: first_onset,
+                    bpm: result.average_bpm,
+                    meter: Some("4/4".to_string()),
+                    beat: 1,
+                };
+                database.replace_beat_grid(metadata_id, ANALYSIS_SOURCE, &[marker]).await?;
+            }
+            database.set_grid_confidence(metadata_id, result.confidence).await?;
+        }
+        AnalysisStage::Key => {
+            let key = tokio::task::spawn_blocking(move || {
+                let payload = decode_mono(&path)?.resample(chroma_fingerprint::SAMPLE_RATE)?;
+                eyre::Ok(KeyAnalysis::from_samples(&payload.buffer.buffer))
+            })
+            .await??;
+            let Some(key) = key else {
+                info!(path = file.path, "No key detected");
+                return Ok(false);
+            };
+            set_missing_tags(database, metadata_id, vec![(TagName::KEY_ID, key.name())], ANALYSIS_SOURCE).await?;
+            database.set_key_confidence(metadata_id, key.confidence).await?;
+        }
+        AnalysisStage::Loudness => {
+            let loudness = tokio::task::spawn_blocking(move || {
+                let payload = decode_mono(&path)?;
+                eyre::Ok(LoudnessAnalysis::from_samples(payload.sample_rate, &payload.buffer.buffer))
+            })
+            .await??;
+            let Some(loudness) = loudness else {
+                info!(path = file.path, "Silent, no loudness measured");
+                return Ok(false);
+            };
+            database
+                .set_loudness(metadata_id, loudness.integrated, loudness.peak)
+                .await?;
+        }
+        AnalysisStage::Stems => bail!("Stems are extracted by the stem extraction task"),
+    }
+    Ok(true)
+}
+
+fn decode_mono(path: &Path) -> Result<AudioFilePayload<'static>> {
+    Ok(EncodedAudioFile::from_file(path)?.read_into_payload()?.into_mono()?)
+}
+
+/// Tag name and value of the tags having a tag name in the library
+fn file_tag(tag: &FileTag) -> Option<(i64, String)> {
+    let tag_name_id = match tag.std_key {
+        Some(StandardTagKey::TrackTitle) => TagName::TITLE_ID,
+        Some(StandardTagKey::Artist) => TagName::ARTIST_ID,
+        Some(StandardTagKey::Album) => TagName::ALBUM_ID,
+        Some(StandardTagKey::Genre) => TagName::GENRE_ID,
+        Some(StandardTagKey::Bpm) => TagName::TEMPO_ID,
+        Some(StandardTagKey::Label) => TagName::LABEL_ID,
+        Some(StandardTagKey::Remixer) => TagName::REMIX_ID,
+        Some(StandardTagKey::TrackNumber) => TagName::TRACK_ID,
+        Some(StandardTagKey::Comment) => TagName::COMMENT_ID,
+        // Symphonia has no standard key for the musical key
+        None if ["TKEY", "INITIALKEY"].contains(&tag.key.to_uppercase().as_str()) => TagName::KEY_ID,
+        _ => return None,
+    };
+    Some((tag_name_id, tag.value.to_string()))
+}
+
+/// Tags already set on the metadata are not overridden
+async fn set_missing_tags(
+    database: &Database,
+    metadata_id: i64,
+    tags: Vec<(i64, String)>,
+    source: &str,
+) -> Result<()> {
+    let entry = database.get_entry_by_metadata_id(metadata_id).await?;
+    let mut existing = entry
+        .tags
+        .0
+        .iter()
+        .map(|tag| tag.tag_name_id)
+        .collect::<HashSet<_>>();
+    for (tag_name_id, value) in tags {
+        let value = value.trim().to_string();
+        if value.is_empty() || existing.contains(&tag_name_id) {
+            continue;
+        }
+        let tag_id = database.get_or_create_tag(tag_name_id, value).await?;
+        database
+            .set_metadata_tag_with_source(metadata_id, tag_id, source)
+            .await?;
+        existing.insert(tag_name_id);
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+//! Copies of the database written while it is in use, named after their date so they sort by age
+use crate::prelude::*;
+
+const BACKUP_PREFIX: &str = "selectia-";
+const BACKUP_EXTENSION: &str = "db";
+
+/// Write a copy of the database to `directory` and delete the oldest copies beyond `keep`
+pub async fn backup_database(database: &Database, directory: &Path, keep: usize) -> Result<PathBuf> {
+    fs::create_dir_all(directory).await?;
+    let name = format!(
+        "{}{}.{}",
+        BACKUP_PREFIX,
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%3f"),
+        BACKUP_EXTENSION
+    );
+    let path = directory.join(&name);
+    // Written under another name first, a backup stopped halfway is not taken for a complete one
+    let partial = directory.join(format!("{}.partial", name));
+    database.backup(&partial).await?;
+    fs::rename(&partial, &path).await?;
+
+    let mut backups = list_backups(directory).await?;
+    let outdated = backups.len().saturating_sub(keep.max(1));
+    for old in backups.drain(..outdated) {
+        fs::remove_file(&old).await?;
+    }
+    Ok(path)
+}
+
+/// Backups found in `directory`, oldest first
+pub async fn list_backups(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut backups = vec![];
+    let mut entries = fs::read_dir(directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_backup = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(BACKUP_PREFIX))
+            && path.extension().is_some_and(|extension| extension == BACKUP_EXTENSION);
+        if is_backup {
+            backups.push(path);
+        }
+    }
+    backups.sort();
+    Ok(backups)
+}
@@ -0,0 +1,206 @@
+//! Cover art of the tracks, pulled out of the files on ingest and kept in a cache directory
+use std::io::Cursor;
+use std::sync::atomic::AtomicU64;
+
+use image::{DynamicImage, ImageFormat};
+use selectia_audio_file::audio_file::EncodedAudioFile;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+use crate::prelude::*;
+use crate::tasks::serato::id3;
+
+/// Pictures looked up next to the file when none is embedded, by order of preference
+pub const COVER_FILE_NAMES: &[&str] = &["cover.jpg", "folder.jpg"];
+/// Largest side of the generated thumbnails, in pixels
+pub const THUMBNAIL_SIZES: &[u32] = &[64, 256];
+/// Value of `metadata_cover.source` for pictures found in the file tags
+pub const EMBEDDED_SOURCE: &str = "embedded";
+
+const ID3_FRONT_COVER: u8 = 3;
+
+/// Picture found for a file, not decoded yet
+#[derive(Debug, Clone)]
+pub struct Picture {
+    pub data: Vec<u8>,
+    /// `embedded` or the name of the picture file
+    pub source: String,
+}
+
+/// Directory holding the pictures, named after their hash, and their thumbnails
+#[derive(Debug, Clone)]
+pub struct CoverCache {
+    root: PathBuf,
+}
+
+impl CoverCache {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn picture_path(&self, cover: &models::Cover) -> PathBuf {
+        let extension = ImageFormat::from_mime_type(&cover.mime_type)
+            .and_then(|format| format.extensions_str().first().copied())
+            .unwrap_or("img");
+        self.root.join(format!("{}.{}", cover.hash, extension))
+    }
+
+    /// Thumbnails are JPEG pictures fitting in a `size` pixels square
+    pub fn thumbnail_path(&self, hash: &str, size: u32) -> PathBuf {
+        self.root.join(size.to_string()).join(format!("{}.jpg", hash))
+    }
+
+    /// Smallest thumbnail covering `size` pixels, the picture itself when every thumbnail is smaller
+    pub fn fitting_path(&self, cover: &models::Cover, size: u32) -> PathBuf {
+        match THUMBNAIL_SIZES.iter().find(|thumbnail_size| **thumbnail_size >= size) {
+            Some(thumbnail_size) => self.thumbnail_path(&cover.hash, *thumbnail_size),
+            None => self.picture_path(cover),
+        }
+    }
+
+    /// Look for the cover of the file at `path` and attach it to the metadata, returns `None` when there is none
+    pub async fn ingest(&self, database: &Database, metadata_id: i64, path: &Path) -> Result<Option<models::Cover>> {
+        let Some(picture) = find_picture(path).await? else {
+            return Ok(None);
+        };
+        let cover = self.store(database, &picture.data).await?;
+        database
+            .set_metadata_cover(metadata_id, cover.id, &picture.source)
+            .await?;
+        Ok(Some(cover))
+    }
+
+    /// Write the picture and its thumbnails unless the cache already holds them
+    pub async fn store(&self, database: &Database, data: &[u8]) -> Result<models::Cover> {
+        let hash = format!("{:x}", Sha256::digest(data));
+        if let Some(cover) = database.get_cover_by_hash(&hash).await? {
+            if self.is_complete(&cover).await? {
+                return Ok(cover);
+            }
+        }
+
+        let cache = self.clone();
+        let data = data.to_vec();
+        let blocking_hash = hash.clone();
+        let (mime_type, width, height) = tokio::task::spawn_blocking(move || {
+            let format = image::guess_format(&data)?;
+            let picture = image::load_from_memory_with_format(&data, format)?;
+            let mime_type = format.to_mime_type().to_string();
+            let cover = models::Cover {
+                id: 0,
+                hash: blocking_hash,
+                mime_type: mime_type.clone(),
+                width: picture.width() as i64,
+                height: picture.height() as i64,
+            };
+            std::fs::create_dir_all(&cache.root)?;
+            write_atomically(&cache.picture_path(&cover), &data)?;
+            for size in THUMBNAIL_SIZES.iter().copied() {
+                let path = cache.thumbnail_path(&cover.hash, size);
+                std::fs::create_dir_all(path.parent().unwrap())?;
+                write_atomically(&path, &thumbnail(&picture, size)?)?;
+            }
+            Result::<_>::Ok((mime_type, cover.width, cover.height))
+        })
+        .await??;
+
+        database
+            .get_or_create_cover(&hash, &mime_type, width, height)
+            .await
+    }
+
+    async fn is_complete(&self, cover: &models::Cover) -> Result<bool> {
+        let mut paths = vec![self.picture_path(cover)];
+        paths.extend(
+            THUMBNAIL_SIZES
+                .iter()
+                .map(|size| self.thumbnail_path(&cover.hash, *size)),
+        );
+        for path in paths {
+            if !fs::try_exists(&path).await? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Embedded picture of the file, or a cover picture in its directory
+pub async fn find_picture(path: &Path) -> Result<Option<Picture>> {
+    if let Some(data) = embedded_picture(path).await? {
+        return Ok(Some(Picture {
+            data,
+            source: EMBEDDED_SOURCE.to_string(),
+        }));
+    }
+
+    let Some(directory) = path.parent() else {
+        return Ok(None);
+    };
+    let mut candidates = vec![];
+    let mut entries = fs::read_dir(directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        if let Some(rank) = COVER_FILE_NAMES.iter().position(|name| *name == file_name) {
+            candidates.push((rank, entry.path()));
+        }
+    }
+    candidates.sort();
+    match candidates.into_iter().next() {
+        Some((rank, path)) => Ok(Some(Picture {
+            data: fs::read(&path).await?,
+            source: COVER_FILE_NAMES[rank].to_string(),
+        })),
+        None => Ok(None),
+    }
+}
+
+/// ID3v2 tags are read directly, the other containers go through symphonia
+async fn embedded_picture(path: &Path) -> Result<Option<Vec<u8>>> {
+    let mut file = fs::File::open(path).await?;
+    let mut header = [0u8; 10];
+    let read = file.read(&mut header).await?;
+    if let Some(tag_len) = id3::tag_len(&header[..read]) {
+        let mut tag = header.to_vec();
+        tag.resize(tag_len, 0);
+        file.read_exact(&mut tag[header.len()..]).await?;
+        let pictures = id3::attached_pictures(&tag)?;
+        let picture = pictures
+            .iter()
+            .find(|picture| picture.picture_type == ID3_FRONT_COVER)
+            .or_else(|| pictures.first());
+        if let Some(picture) = picture {
+            return Ok(Some(picture.data.clone()));
+        }
+    }
+
+    let path = path.to_path_buf();
+    let picture = tokio::task::spawn_blocking(move || {
+        EncodedAudioFile::from_file(&path)
+            .ok()
+            .and_then(|mut file| file.embedded_picture())
+    })
+    .await?;
+    Ok(picture.map(|picture| picture.data))
+}
+
+fn thumbnail(picture: &DynamicImage, size: u32) -> Result<Vec<u8>> {
+    let resized = if picture.width() > size || picture.height() > size {
+        picture.thumbnail(size, size)
+    } else {
+        picture.clone()
+    };
+    let mut output = Cursor::new(vec![]);
+    DynamicImage::ImageRgb8(resized.to_rgb8()).write_to(&mut output, ImageFormat::Jpeg)?;
+    Ok(output.into_inner())
+}
+
+/// Concurrent ingests may store the same picture, readers never see a partial file
+fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let temporary = path.with_extension(format!("{}-{}.tmp", std::process::id(), id));
+    std::fs::write(&temporary, data)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
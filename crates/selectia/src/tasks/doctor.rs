@@ -0,0 +1,174 @@
+//! Health check of the library, auditing the tables against the filesystem and the schema invariants
+use std::fmt;
+
+use crate::prelude::*;
+use crate::services::worker::tasks::{TaskPayload, TaskStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoctorIssueKind {
+    /// Not fixed automatically, the drive holding it may only be unplugged (see `LibraryRelocation`)
+    MissingFile,
+    /// Stems whose folder was deleted, they can be extracted again.
+    /// Not reported while their track or library root is missing
+    MissingFileVariation,
+    /// Task in processing status the worker is not running, i.e left over by a crash
+    StuckTask,
+    /// Task whose payload cannot be read anymore
+    InvalidTask,
+    /// Row referencing a missing row, such as a tag without `tag_name`
+    BrokenReference,
+    UnusedTag,
+    /// Entry none of the files resolves to, its tags and cues are kept in case the file comes back
+    EntryWithoutFile,
+}
+
+impl DoctorIssueKind {
+    pub fn is_fixable(&self) -> bool {
+        !matches!(self, DoctorIssueKind::MissingFile | DoctorIssueKind::EntryWithoutFile)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoctorIssue {
+    pub kind: DoctorIssueKind,
+    pub table: String,
+    pub row_id: i64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DoctorReport {
+    pub issues: Vec<DoctorIssue>,
+    /// Issues fixed before the report was made
+    pub fixed: usize,
+}
+
+impl DoctorReport {
+    pub fn count(&self, kind: DoctorIssueKind) -> usize {
+        self.issues.iter().filter(|issue| issue.kind == kind).count()
+    }
+
+    pub fn fixable(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.kind.is_fixable()).count()
+    }
+
+    fn push(&mut self, kind: DoctorIssueKind, table: &str, row_id: i64, detail: String) {
+        self.issues.push(DoctorIssue {
+            kind,
+            table: table.to_string(),
+            row_id,
+            detail,
+        });
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} issue(s), {} fixable, {} fixed",
+            self.issues.len(),
+            self.fixable(),
+            self.fixed
+        )?;
+        for issue in self.issues.iter() {
+            writeln!(f, "{:?} {}#{}: {}", issue.kind, issue.table, issue.row_id, issue.detail)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Doctor {
+    database: Database,
+    running_task_ids: Vec<i64>,
+}
+
+impl Doctor {
+    pub fn new(database: Database) -> Self {
+        Self {
+            database,
+            running_task_ids: vec![],
+        }
+    }
+
+    /// Tasks the worker is processing, every other task in processing status is reported as stuck
+    pub fn with_running_tasks(mut self, running_task_ids: Vec<i64>) -> Self {
+        self.running_task_ids = running_task_ids;
+        self
+    }
+
+    pub async fn check(&self) -> Result<DoctorReport> {
+        let mut report = DoctorReport::default();
+
+        let mut missing_files = HashSet::new();
+        for file in self.database.list_files().await? {
+            if !fs::try_exists(&file.path).await? {
+                missing_files.insert(file.id);
+                report.push(DoctorIssueKind::MissingFile, "file", file.id, file.path);
+            }
+        }
+        let mut offline_roots = vec![];
+        for root in self.database.get_library_roots().await? {
+            if !fs::try_exists(&root.path).await? {
+                offline_roots.push(PathBuf::from(root.path));
+            }
+        }
+        for variation in self.database.list_file_variations().await? {
+            // Stems are stored next to their track, both are missing when its drive is unplugged
+            let offline = missing_files.contains(&variation.file_id)
+                || offline_roots.iter().any(|root| Path::new(&variation.path).starts_with(root));
+            if !offline && !fs::try_exists(&variation.path).await? {
+                report.push(DoctorIssueKind::MissingFileVariation, "file_variation", variation.id, variation.path);
+            }
+        }
+
+        for task in self.database.get_tasks().await? {
+            if let Err(e) = serde_json::from_str::<TaskPayload>(&task.payload) {
+                report.push(DoctorIssueKind::InvalidTask, "task", task.id, e.to_string());
+                continue;
+            }
+            let processing = matches!(TaskStatus::try_from(task.status.as_str()), Ok(TaskStatus::Processing));
+            if processing && !self.running_task_ids.contains(&task.id) {
+                report.push(DoctorIssueKind::StuckTask, "task", task.id, task.status);
+            }
+        }
+
+        for (table, rowid) in self.database.get_broken_references().await? {
+            report.push(DoctorIssueKind::BrokenReference, &table, rowid, "missing referenced row".to_string());
+        }
+        for id in self.database.get_unused_tag_ids().await? {
+            report.push(DoctorIssueKind::UnusedTag, "tag", id, "no entry uses it".to_string());
+        }
+        for id in self.database.get_metadata_ids_without_file().await? {
+            report.push(DoctorIssueKind::EntryWithoutFile, "metadata", id, "no file".to_string());
+        }
+        Ok(report)
+    }
+
+    /// Apply the safe fixes to the issues of `report` and check again
+    pub async fn fix(&self, report: &DoctorReport) -> Result<DoctorReport> {
+        let mut fixed = 0;
+        // Broken references first, deleting them may leave tags unused
+        if report.count(DoctorIssueKind::BrokenReference) > 0 {
+            self.database.delete_broken_references().await?;
+            fixed += report.count(DoctorIssueKind::BrokenReference);
+        }
+        for issue in report.issues.iter() {
+            match issue.kind {
+                DoctorIssueKind::MissingFileVariation => self.database.delete_file_variation(issue.row_id).await?,
+                DoctorIssueKind::StuckTask => self.database.requeue_task(issue.row_id).await?,
+                DoctorIssueKind::InvalidTask => self.database.delete_task(issue.row_id).await?,
+                DoctorIssueKind::UnusedTag => self.database.delete_unused_tag(issue.row_id).await?,
+                DoctorIssueKind::BrokenReference
+                | DoctorIssueKind::MissingFile
+                | DoctorIssueKind::EntryWithoutFile => continue,
+            }
+            fixed += 1;
+        }
+        info!(fixed, "Library issues fixed");
+
+        let mut report = self.check().await?;
+        report.fixed = fixed;
+        Ok(report)
+    }
+}
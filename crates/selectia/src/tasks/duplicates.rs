@@ -0,0 +1,133 @@
+//! Find the metadata entries holding the same track in different files (other encoding, other tags ...)
+use std::collections::BTreeMap;
+
+use selectia_audio_file::audio_file::{AudioFilePayload, EncodedAudioFile};
+
+use crate::analyser::chroma_fingerprint::{self, ChromaFingerprint};
+use crate::prelude::*;
+
+/// Similarity above which two tracks are reported as duplicates
+pub const DEFAULT_MIN_SIMILARITY: f64 = 0.85;
+/// Tracks whose durations differ by more seconds are not compared
+const MAX_DURATION_DIFFERENCE: f64 = 10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateEntry {
+    pub metadata_id: i64,
+    /// Similarity with the first entry of the group, 1 for the first entry itself
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub entries: Vec<DuplicateEntry>,
+}
+
+/// Decode the file of the metadata and store its fingerprint
+pub async fn fingerprint_metadata(database: &Database, metadata_id: i64) -> Result<ChromaFingerprint> {
+    let file = database.get_file_from_metadata_id(metadata_id).await?;
+    let path = PathBuf::from(&file.path);
+    let fingerprint = tokio::task::spawn_blocking(move || fingerprint_file(&path)).await??;
+    database
+        .set_fingerprint(metadata_id, fingerprint.duration, &fingerprint.codes_to_bytes())
+        .await?;
+    Ok(fingerprint)
+}
+
+/// Only the first `MAX_DURATION` seconds are decoded
+pub fn fingerprint_file(path: &Path) -> Result<ChromaFingerprint> {
+    let encoded_file = EncodedAudioFile::from_file(path)?;
+    let container_duration = encoded_file.duration();
+    let wave = encoded_file
+        .read_mono_wave_until(|wave| Ok(wave.duration() < chroma_fingerprint::MAX_DURATION))?;
+    let decoded_duration = wave.duration();
+    let payload = AudioFilePayload::from_wave(wave)?.resample(chroma_fingerprint::SAMPLE_RATE)?;
+    Ok(ChromaFingerprint::from_samples(
+        container_duration.unwrap_or(decoded_duration),
+        &payload.buffer.buffer,
+    ))
+}
+
+pub struct DuplicateFinder {
+    database: Database,
+    min_similarity: f64,
+}
+
+impl DuplicateFinder {
+    pub fn new(database: Database, min_similarity: f64) -> Self {
+        Self {
+            database,
+            min_similarity,
+        }
+    }
+
+    /// Groups of likely duplicates among the fingerprinted entries, the best matches first
+    pub async fn find(&self) -> Result<Vec<DuplicateGroup>> {
+        let fingerprints = self
+            .database
+            .get_fingerprints()
+            .await?
+            .into_iter()
+            .map(|fingerprint| {
+                (
+                    fingerprint.metadata_id,
+                    ChromaFingerprint::from_bytes(fingerprint.duration, &fingerprint.codes),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Union-find over the matching pairs
+        let mut parents = (0..fingerprints.len()).collect::<Vec<_>>();
+        fn root(parents: &mut [usize], idx: usize) -> usize {
+            let mut root = idx;
+            while parents[root] != root {
+                root = parents[root];
+            }
+            parents[idx] = root;
+            root
+        }
+        let mut similarities = HashMap::new();
+        for (a_idx, (_, a)) in fingerprints.iter().enumerate() {
+            for (b_idx, (_, b)) in fingerprints.iter().enumerate().skip(a_idx + 1) {
+                if (a.duration - b.duration).abs() > MAX_DURATION_DIFFERENCE {
+                    continue;
+                }
+                let similarity = a.similarity(b);
+                if similarity >= self.min_similarity {
+                    similarities.insert((a_idx, b_idx), similarity);
+                    let (a_root, b_root) = (root(&mut parents, a_idx), root(&mut parents, b_idx));
+                    parents[b_root] = a_root;
+                }
+            }
+        }
+
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for idx in 0..fingerprints.len() {
+            members.entry(root(&mut parents, idx)).or_default().push(idx);
+        }
+        let mut groups = members
+            .into_values()
+            .filter(|members| members.len() > 1)
+            .map(|members| {
+                let (_, first) = &fingerprints[members[0]];
+                let mut entries = members
+                    .iter()
+                    .map(|idx| DuplicateEntry {
+                        metadata_id: fingerprints[*idx].0,
+                        similarity: match *idx == members[0] {
+                            true => 1.0,
+                            false => similarities
+                                .get(&(members[0], *idx))
+                                .copied()
+                                .unwrap_or_else(|| first.similarity(&fingerprints[*idx].1)),
+                        },
+                    })
+                    .collect::<Vec<_>>();
+                entries[1..].sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+                DuplicateGroup { entries }
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by(|a, b| b.entries[1].similarity.total_cmp(&a.entries[1].similarity));
+        Ok(groups)
+    }
+}
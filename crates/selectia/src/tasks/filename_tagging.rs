@@ -0,0 +1,126 @@
+use crate::analyser::path_pattern_analyser::{ExtractedTag, PathPattern, PathPatternAnalyser};
+use crate::prelude::*;
+
+/// Apply the enabled tagging rules (see `tagging_rule` table) to files
+pub struct FilenameTagging {
+    database: Database,
+    analyser: PathPatternAnalyser,
+    tag_names: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilenameTagPreview {
+    pub metadata_id: i64,
+    pub path: String,
+    pub rule_id: i64,
+    pub tags: Vec<ExtractedTag>,
+}
+
+impl FilenameTagging {
+    pub async fn new(database: Database) -> Result<Self> {
+        let mut patterns = vec![];
+        for rule in database.get_tagging_rules().await? {
+            if !rule.enabled {
+                continue;
+            }
+            match PathPattern::parse(&rule.template) {
+                Ok(pattern) => patterns.push((rule.id, pattern)),
+                Err(e) => warn!(rule_id = rule.id, error = ?e, "Invalid tagging rule, skipping it"),
+            }
+        }
+        let tag_names = database
+            .get_tag_names()
+            .await?
+            .into_iter()
+            .map(|tag_name| (tag_name.name, tag_name.id))
+            .collect();
+        Ok(Self {
+            database,
+            analyser: PathPatternAnalyser::new(patterns),
+            tag_names,
+        })
+    }
+
+    /// Returns what the rules would extract for the given entries without writing anything
+    pub async fn preview(&self, metadata_ids: &[i64]) -> Result<Vec<FilenameTagPreview>> {
+        let mut previews = vec![];
+        for metadata_id in metadata_ids.iter().copied() {
+            let file = self.database.get_file_from_metadata_id(metadata_id).await?;
+            if let Some((rule_id, tags)) = self.analyser.extract(Path::new(&file.path)) {
+                previews.push(FilenameTagPreview {
+                    metadata_id,
+                    path: file.path,
+                    rule_id,
+                    tags,
+                });
+            }
+        }
+        Ok(previews)
+    }
+
+    /// Write the extracted tags of every given entries
+    ///
+    /// Returns the id of the entries that got new tags
+    pub async fn apply(&self, metadata_ids: &[i64]) -> Result<Vec<i64>> {
+        let mut updated = vec![];
+        for preview in self.preview(metadata_ids).await? {
+            if self.write(&preview).await? {
+                updated.push(preview.metadata_id);
+            }
+        }
+        Ok(updated)
+    }
+
+    /// Write the extracted tags of a freshly ingested file
+    pub async fn apply_to_file(&self, metadata_id: i64, path: &Path) -> Result<bool> {
+        match self.analyser.extract(path) {
+            Some((rule_id, tags)) => {
+                self.write(&FilenameTagPreview {
+                    metadata_id,
+                    path: path.to_string_lossy().to_string(),
+                    rule_id,
+                    tags,
+                })
+                .await
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Tags already set on the entry are kept untouched so user edits are never overridden
+    async fn write(&self, preview: &FilenameTagPreview) -> Result<bool> {
+        let existing = match self
+            .database
+            .get_entry_by_metadata_id(preview.metadata_id)
+            .await
+        {
+            Ok(entry) => entry
+                .tags
+                .0
+                .iter()
+                .map(|tag| tag.tag_name_id)
+                .collect::<HashSet<_>>(),
+            Err(_) => HashSet::new(),
+        };
+        let source = models::TaggingRule::source(preview.rule_id);
+        let mut written = false;
+        for tag in preview.tags.iter() {
+            let Some(tag_name_id) = self.tag_names.get(&tag.name).copied() else {
+                warn!(tag_name = tag.name, "Unknown tag name in tagging rule");
+                continue;
+            };
+            if existing.contains(&tag_name_id) || tag.value.is_empty() {
+                continue;
+            }
+            let tag_id = self
+                .database
+                .get_or_create_tag(tag_name_id, tag.value.clone())
+                .await?;
+            self.database
+                .set_metadata_tag_with_source(preview.metadata_id, tag_id, &source)
+                .await?;
+            written = true;
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,323 @@
+//! Copy (or transcode) a selection of the library into a folder, typically a USB stick, along with a M3U8 playlist
+use std::path::Component;
+
+use selectia_audio_file::audio_file::EncodedAudioFile;
+
+use crate::prelude::*;
+use crate::services::file_loader::{hash_file, HashStrategy};
+use crate::services::worker::tasks::TaskProgress;
+use crate::tasks::playlist_file::entry_title;
+use crate::tasks::playlist_file::format::{PlaylistFile, PlaylistFileEntry};
+use crate::tasks::selection::ExportSelection;
+
+/// Hashes of the exported files, used to skip unchanged tracks when exporting again to the same folder
+const MANIFEST_FILE_NAME: &str = ".selectia-export.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranscodeFormat {
+    Wav,
+    Flac,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderExportOptions {
+    pub target: PathBuf,
+    /// Path of the exported files relative to the target such as `{artist}/{title}.{ext}`,
+    /// placeholders are tag names, `file_name` (source file name without extension) and `ext`
+    pub layout: String,
+    /// Files are copied as is when not set
+    pub transcode: Option<TranscodeFormat>,
+    /// Name of the M3U8 playlist written at the root of the target
+    pub playlist_name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportedFileStatus {
+    Copied,
+    Transcoded,
+    /// The target already holds the same content
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedFile {
+    pub metadata_id: i64,
+    pub source: PathBuf,
+    /// Path relative to the target
+    pub destination: String,
+    pub status: ExportedFileStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FolderExportReport {
+    pub files: Vec<ExportedFile>,
+}
+
+impl FolderExportReport {
+    pub fn count(&self, status: ExportedFileStatus) -> usize {
+        self.files.iter().filter(|file| file.status == status).count()
+    }
+}
+
+impl TranscodeFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscodeFormat::Wav => "wav",
+            TranscodeFormat::Flac => "flac",
+        }
+    }
+}
+
+pub struct FolderExport {
+    database: Database,
+    selection: ExportSelection,
+    options: FolderExportOptions,
+}
+
+impl FolderExport {
+    pub fn new(database: Database, selection: ExportSelection, options: FolderExportOptions) -> Result<Self> {
+        validate_layout(&options.layout)?;
+        Ok(Self {
+            database,
+            selection,
+            options,
+        })
+    }
+
+    pub async fn run(&self, progress: Option<&TaskProgress>) -> Result<FolderExportReport> {
+        fs::create_dir_all(&self.options.target).await?;
+        let manifest_path = self.options.target.join(MANIFEST_FILE_NAME);
+        let mut manifest: HashMap<String, String> = match fs::read(&manifest_path).await {
+            Ok(content) => serde_json::from_slice(&content).unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        };
+
+        let tag_names = self
+            .database
+            .get_tag_names()
+            .await?
+            .into_iter()
+            .map(|tag_name| (tag_name.name, tag_name.id))
+            .collect::<HashMap<_, _>>();
+        let metadata_ids = self.selection.metadata_ids(&self.database).await?;
+        let mut report = FolderExportReport::default();
+        let mut playlist = PlaylistFile {
+            name: Some(self.options.playlist_name.clone()),
+            entries: vec![],
+        };
+        let mut destinations = HashSet::new();
+        for (idx, metadata_id) in metadata_ids.iter().copied().enumerate() {
+            if let Some(progress) = progress {
+                progress
+                    .report("export", idx as f64 / metadata_ids.len() as f64)
+                    .await?;
+            }
+            let Some(file) = self.database.get_first_file_from_metadata_id(metadata_id).await? else {
+                warn!(metadata_id, "Entry without a file, not exported");
+                report.files.push(ExportedFile {
+                    metadata_id,
+                    source: PathBuf::new(),
+                    destination: String::new(),
+                    status: ExportedFileStatus::Failed,
+                    error: Some("The entry has no file".to_string()),
+                });
+                continue;
+            };
+            let entry = self.database.get_entry_by_metadata_id(metadata_id).await?;
+            let source = PathBuf::from(&file.path);
+            let extension = match self.options.transcode {
+                Some(format) => format.extension().to_string(),
+                None => extension(&source),
+            };
+            let placeholder = |name: &str| match name {
+                "ext" => extension.clone(),
+                "file_name" => file_stem(&source),
+                name => tag_names
+                    .get(name)
+                    .and_then(|tag_name_id| entry.tag_value(*tag_name_id))
+                    .or_else(|| (name == "title").then(|| file_stem(&source)))
+                    .unwrap_or_else(|| format!("Unknown {}", name)),
+            };
+            let rendered = render_layout(&self.options.layout, placeholder);
+            if !stays_inside(Path::new(&rendered)) {
+                warn!(path = ?source, destination = rendered, "Export destination outside of the target");
+                report.files.push(ExportedFile {
+                    metadata_id,
+                    source,
+                    error: Some(format!("The destination `{}` is outside of the target", rendered)),
+                    destination: rendered,
+                    status: ExportedFileStatus::Failed,
+                });
+                continue;
+            }
+            let destination = unique_destination(rendered, &mut destinations);
+
+            let mut exported = ExportedFile {
+                metadata_id,
+                source: source.clone(),
+                destination: destination.clone(),
+                status: ExportedFileStatus::Failed,
+                error: None,
+            };
+            match self.export_file(&source, &destination, &entry.metadata_hash, &manifest).await {
+                Ok(status) => {
+                    exported.status = status;
+                    manifest.insert(destination.clone(), entry.metadata_hash.clone());
+                    playlist.entries.push(PlaylistFileEntry {
+                        location: destination,
+                        title: Some(entry_title(&entry, &source)),
+                        duration: None,
+                    });
+                }
+                Err(e) => {
+                    warn!(path = ?source, error = ?e, "Error exporting file");
+                    exported.error = Some(e.to_string());
+                }
+            }
+            report.files.push(exported);
+        }
+
+        let playlist_path = self
+            .options
+            .target
+            .join(format!("{}.m3u8", sanitize(&self.options.playlist_name)));
+        fs::write(playlist_path, playlist.to_m3u8()).await?;
+        fs::write(manifest_path, serde_json::to_vec_pretty(&manifest)?).await?;
+        if let Some(progress) = progress {
+            progress.report("export", 1.0).await?;
+        }
+        Ok(report)
+    }
+
+    async fn export_file(
+        &self,
+        source: &Path,
+        destination: &str,
+        hash: &str,
+        manifest: &HashMap<String, String>,
+    ) -> Result<ExportedFileStatus> {
+        let target = self.options.target.join(destination);
+        let format = self
+            .options
+            .transcode
+            .filter(|format| !extension(source).eq_ignore_ascii_case(format.extension()));
+        if fs::try_exists(&target).await? {
+            // Transcoded files can only be recognized through the manifest
+            let unchanged = match format {
+                Some(_) => manifest.get(destination).map(String::as_str) == Some(hash),
+                None => hash_file(&target, HashStrategy::of(hash)).await? == hash,
+            };
+            if unchanged {
+                return Ok(ExportedFileStatus::Skipped);
+            }
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let Some(format) = format else {
+            fs::copy(source, &target).await?;
+            return Ok(ExportedFileStatus::Copied);
+        };
+        let source = source.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let payload = EncodedAudioFile::from_file(&source)?.read_into_payload()?;
+            match format {
+                TranscodeFormat::Wav => payload.wav_export(payload.sample_rate as u32, &target),
+                TranscodeFormat::Flac => payload.flac_export(&target),
+            }
+        })
+        .await??;
+        Ok(ExportedFileStatus::Transcoded)
+    }
+}
+
+fn validate_layout(layout: &str) -> Result<()> {
+    if layout.trim().is_empty() {
+        return Err(eyre!("The export layout is empty"));
+    }
+    if !stays_inside(Path::new(layout)) {
+        return Err(eyre!("The export layout `{}` must stay inside the target", layout));
+    }
+    if layout.matches('{').count() != layout.matches('}').count() {
+        return Err(eyre!("Unbalanced placeholders in `{}`", layout));
+    }
+    Ok(())
+}
+
+/// Only plain names, a root, a drive prefix or a parent component would leave the target once joined to it
+fn stays_inside(path: &Path) -> bool {
+    path.components().all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Placeholder values are sanitized so that they cannot add path components
+fn render_layout(layout: &str, placeholder: impl Fn(&str) -> String) -> String {
+    let mut rendered = String::new();
+    let mut rest = layout;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&sanitize(&placeholder(rest[start + 1..end].trim())));
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Replace the characters that are invalid on FAT32/exFAT file systems
+fn sanitize(value: &str) -> String {
+    let sanitized = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let sanitized = sanitized.trim().trim_end_matches('.').to_string();
+    if sanitized.is_empty() {
+        "_".to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// Two tracks rendering to the same path get a numbered suffix
+fn unique_destination(destination: String, destinations: &mut HashSet<String>) -> String {
+    if destinations.insert(destination.to_lowercase()) {
+        return destination;
+    }
+    let path = Path::new(&destination);
+    let stem = file_stem(path);
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy()));
+    let parent = path
+        .parent()
+        .map(|parent| parent.to_string_lossy().to_string())
+        .filter(|parent| !parent.is_empty());
+    (2..)
+        .map(|idx| {
+            let file_name = format!("{} ({}){}", stem, idx, extension.as_deref().unwrap_or_default());
+            match &parent {
+                Some(parent) => format!("{}/{}", parent, file_name),
+                None => file_name,
+            }
+        })
+        .find(|candidate| destinations.insert(candidate.to_lowercase()))
+        .unwrap()
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,82 @@
+//! Move a library root to another location, typically an external drive mounted elsewhere on another computer
+use crate::prelude::*;
+
+/// Paths of the root rewritten by the relocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelocationPlan {
+    pub root_id: i64,
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub files: usize,
+    pub file_variations: usize,
+    /// Files or file variations of the root not found at the new location
+    pub missing: Vec<PathBuf>,
+}
+
+pub struct LibraryRelocation {
+    database: Database,
+    root_id: i64,
+    to: PathBuf,
+}
+
+impl LibraryRelocation {
+    pub fn new(database: Database, root_id: i64, to: PathBuf) -> Self {
+        Self {
+            database,
+            root_id,
+            to,
+        }
+    }
+
+    /// Look for every file of the root at the new location, without changing anything
+    pub async fn plan(&self) -> Result<RelocationPlan> {
+        let root = self.database.get_library_root(self.root_id).await?;
+        let files = self
+            .database
+            .list_files()
+            .await?
+            .into_iter()
+            .filter_map(|file| root.relocated_path(&file.path, &self.to))
+            .collect::<Vec<_>>();
+        let file_variations = self
+            .database
+            .list_file_variations()
+            .await?
+            .into_iter()
+            .filter_map(|variation| root.relocated_path(&variation.path, &self.to))
+            .collect::<Vec<_>>();
+
+        let mut missing = vec![];
+        for path in files.iter().chain(file_variations.iter()) {
+            if !fs::try_exists(path).await? {
+                missing.push(PathBuf::from(path));
+            }
+        }
+        Ok(RelocationPlan {
+            root_id: root.id,
+            from: PathBuf::from(&root.path),
+            to: self.to.clone(),
+            files: files.len(),
+            file_variations: file_variations.len(),
+            missing,
+        })
+    }
+
+    /// Rewrite the paths of the root, fails without changing anything when files are missing at the new location
+    pub async fn relocate(&self) -> Result<RelocationPlan> {
+        let plan = self.plan().await?;
+        if let Some(first) = plan.missing.first() {
+            return Err(eyre!(
+                "{} files are missing at {}, e.g {}",
+                plan.missing.len(),
+                plan.to.display(),
+                first.display()
+            ));
+        }
+        self.database
+            .relocate_library_root(self.root_id, &self.to)
+            .await?;
+        info!(from = ?plan.from, to = ?plan.to, files = plan.files, "Library root relocated");
+        Ok(plan)
+    }
+}
@@ -1,8 +1,16 @@
 use crate::prelude::*;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use models::ImportRules;
+use selectia_audio_file::audio_file::EncodedAudioFile;
+
+/// Name of the per-directory ignore file (gitignore syntax), honored whatever the import rules are
+pub const IGNORE_FILE_NAME: &str = ".selectiaignore";
 
 pub struct LoadDirectory {
     file_loader: AddressableService<FileLoaderTask>,
     directory: PathBuf,
+    rules: ImportRules,
 }
 
 impl LoadDirectory {
@@ -10,29 +18,129 @@ impl LoadDirectory {
         if !directory.exists() {
             return Err(eyre!("Directory does not exist"));
         }
-        Ok(Self { file_loader, directory })
+        Ok(Self { file_loader, directory, rules: ImportRules::default() })
+    }
+
+    pub fn with_rules(mut self, rules: ImportRules) -> Self {
+        self.rules = rules;
+        self
     }
 
     pub async fn load(&self) -> Result<()> {
+        for path in self.collect().await? {
+            if let Err(e) = self.file_loader.send(file_loader::FileLoaderTask::LoadFile {
+                path: path.clone(),
+                callback: None,
+            }).await {
+                error!(path = ?path, error = ?e, "Error loading file");
+            }
+        }
+        Ok(())
+    }
+
+    /// Walk the directory and return every file accepted by the import rules
+    pub async fn collect(&self) -> Result<Vec<PathBuf>> {
+        let root_ignore = Arc::new(build_ignore(&self.directory, &self.rules.ignore)?);
+        let mut visited = HashSet::new();
+        let mut files = vec![];
         let mut to_be_processed = VecDeque::new();
-        to_be_processed.push_back(self.directory.clone());
-        while let Some(path) = to_be_processed.pop_front() {
+        to_be_processed.push_back((self.directory.clone(), vec![root_ignore]));
+        while let Some((path, mut ignores)) = to_be_processed.pop_front() {
+            // Directories are identified by their canonical path so a symlink pointing to one of its parents is only walked once
+            if !visited.insert(fs::canonicalize(&path).await?) {
+                warn!(path = ?path, "Symlink loop detected, skipping directory");
+                continue;
+            }
             info!(path = ?path, "Loading directory ...");
+            let ignore_file = path.join(IGNORE_FILE_NAME);
+            if fs::try_exists(&ignore_file).await? {
+                let mut builder = GitignoreBuilder::new(&path);
+                builder.case_insensitive(true)?;
+                if let Some(e) = builder.add(&ignore_file) {
+                    warn!(path = ?ignore_file, error = ?e, "Malformed ignore file");
+                }
+                ignores.push(Arc::new(builder.build()?));
+            }
             let mut entries = fs::read_dir(path).await?;
             while let Some(entry) = entries.next_entry().await? {
                 let path = entry.path();
-                if path.is_dir() {
-                    to_be_processed.push_back(path);
-                } else if path.is_audio_file() {
-                    if let Err(e) = self.file_loader.send(file_loader::FileLoaderTask::LoadFile {
-                        path: path.to_path_buf(),
-                        callback: None,
-                    }).await {
-                        error!(path = ?path, error = ?e, "Error loading file");
-                    }
+                if entry.file_type().await?.is_symlink() && !self.rules.follow_symlinks {
+                    trace!(path = ?path, "Skipping symlink");
+                    continue;
+                }
+                if self.rules.skip_hidden && path.is_hidden() {
+                    continue;
+                }
+                let is_dir = path.is_dir();
+                if is_ignored(&ignores, &path, is_dir) {
+                    trace!(path = ?path, "Ignored by import rules");
+                    continue;
+                }
+                if is_dir {
+                    to_be_processed.push_back((path, ignores.clone()));
+                } else if path.has_extension_in(&self.rules.extensions) && self.accept_file(&path).await? {
+                    files.push(path);
                 }
             }
         }
-        Ok(())
+        Ok(files)
+    }
+
+    async fn accept_file(&self, path: &Path) -> Result<bool> {
+        // A dangling symlink or a file removed during the walk is skipped
+        let size = match fs::metadata(path).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                warn!(path = ?path, error = ?e, "Failed to read file metadata, skipping");
+                return Ok(false);
+            }
+        };
+        if self.rules.min_size.is_some_and(|min| size < min)
+            || self.rules.max_size.is_some_and(|max| size > max)
+        {
+            return Ok(false);
+        }
+        if self.rules.min_duration.is_none() && self.rules.max_duration.is_none() {
+            return Ok(true);
+        }
+
+        let probed_path = path.to_path_buf();
+        let duration = tokio::task::spawn_blocking(move || {
+            EncodedAudioFile::from_file(&probed_path).map(|file| file.duration())
+        })
+        .await?;
+        match duration {
+            Ok(Some(duration)) => Ok(self.rules.min_duration.is_none_or(|min| duration >= min)
+                && self.rules.max_duration.is_none_or(|max| duration <= max)),
+            Ok(None) => {
+                warn!(path = ?path, "Unknown duration, duration rules are not applied");
+                Ok(true)
+            }
+            Err(e) => {
+                warn!(path = ?path, error = ?e, "Failed to probe file, skipping it");
+                Ok(false)
+            }
+        }
+    }
+}
+
+fn build_ignore(root: &Path, patterns: &[String]) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    builder.case_insensitive(true)?;
+    for pattern in patterns {
+        builder.add_line(None, pattern)?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Deepest ignore file wins, as with git
+fn is_ignored(ignores: &[Arc<Gitignore>], path: &Path, is_dir: bool) -> bool {
+    for ignore in ignores.iter().rev() {
+        match ignore.matched(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
     }
+    false
 }
@@ -1 +1,18 @@
-pub mod load_directory;
\ No newline at end of file
+pub mod analysis;
+pub mod backup;
+pub mod cover_art;
+pub mod doctor;
+pub mod duplicates;
+pub mod library_relocation;
+pub mod load_directory;
+pub mod playlist_file;
+pub mod filename_tagging;
+pub mod folder_export;
+pub mod reconciliation;
+pub mod rekordbox;
+pub mod selection;
+pub mod serato;
+pub mod statistics;
+pub mod traktor;
+pub mod verify_library;
+pub mod works;
@@ -0,0 +1,75 @@
+use selectia_audio_file::audio_file::EncodedAudioFile;
+
+use super::entry_title;
+use super::format::{PlaylistFile, PlaylistFileEntry};
+use crate::prelude::*;
+use crate::tasks::selection::ExportSelection;
+
+/// Write the selected entries as an extended M3U8 playlist, a folder is flattened into a single playlist
+pub struct PlaylistFileExport {
+    database: Database,
+    selection: ExportSelection,
+}
+
+impl PlaylistFileExport {
+    pub fn new(database: Database, selection: ExportSelection) -> Self {
+        Self {
+            database,
+            selection,
+        }
+    }
+
+    pub async fn build(&self) -> Result<PlaylistFile> {
+        let name = match self.selection {
+            ExportSelection::Playlist(_) => self
+                .selection
+                .playlists(&self.database)
+                .await?
+                .first()
+                .map(|playlist| playlist.name.clone()),
+            _ => None,
+        };
+        let mut entries = vec![];
+        for metadata_id in self.selection.metadata_ids(&self.database).await? {
+            match self.entry(metadata_id).await? {
+                Some(entry) => entries.push(entry),
+                None => warn!(metadata_id, "Entry without a file, not exported"),
+            }
+        }
+        Ok(PlaylistFile { name, entries })
+    }
+
+    pub async fn write(&self, path: &Path) -> Result<()> {
+        let m3u8 = self.build().await?.to_m3u8();
+        fs::write(path, m3u8).await?;
+        Ok(())
+    }
+
+    async fn entry(&self, metadata_id: i64) -> Result<Option<PlaylistFileEntry>> {
+        let Some(file) = self.database.get_first_file_from_metadata_id(metadata_id).await? else {
+            return Ok(None);
+        };
+        let entry = self.database.get_entry_by_metadata_id(metadata_id).await?;
+        let path = PathBuf::from(&file.path);
+        Ok(Some(PlaylistFileEntry {
+            location: file.path,
+            title: Some(entry_title(&entry, &path)),
+            duration: duration(path).await,
+        }))
+    }
+}
+
+async fn duration(path: PathBuf) -> Option<i64> {
+    let duration = tokio::task::spawn_blocking(move || {
+        EncodedAudioFile::from_file(&path).map(|file| file.duration())
+    })
+    .await;
+    match duration {
+        Ok(Ok(duration)) => duration.map(|duration| duration.round() as i64),
+        Ok(Err(e)) => {
+            debug!(error = ?e, "Failed to probe file, duration is unknown");
+            None
+        }
+        Err(_) => None,
+    }
+}
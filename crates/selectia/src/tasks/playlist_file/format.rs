@@ -0,0 +1,190 @@
+//! Reading of M3U (plain or extended) and PLS playlists, writing of extended M3U8 playlists
+use std::collections::BTreeMap;
+use std::path::Component;
+
+use crate::prelude::*;
+use crate::tasks::rekordbox::xml::location_to_path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlaylistFormat {
+    M3u,
+    Pls,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaylistFile {
+    /// Name set by the `#PLAYLIST:` directive
+    pub name: Option<String>,
+    pub entries: Vec<PlaylistFileEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistFileEntry {
+    /// Path or url as written in the playlist
+    pub location: String,
+    pub title: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<i64>,
+}
+
+impl PlaylistFormat {
+    /// PLS files are recognized by their `[playlist]` header, anything else is read as M3U
+    pub fn detect(content: &str) -> Self {
+        let is_pls = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .is_some_and(|line| line.eq_ignore_ascii_case("[playlist]"));
+        if is_pls {
+            PlaylistFormat::Pls
+        } else {
+            PlaylistFormat::M3u
+        }
+    }
+}
+
+impl PlaylistFile {
+    pub fn parse(content: &str) -> Result<Self> {
+        match PlaylistFormat::detect(content) {
+            PlaylistFormat::M3u => Ok(Self::parse_m3u(content)),
+            PlaylistFormat::Pls => Self::parse_pls(content),
+        }
+    }
+
+    fn parse_m3u(content: &str) -> Self {
+        let mut playlist = PlaylistFile::default();
+        let mut info = None;
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+                // `#EXTINF:<duration> [attributes],<title>`
+                let (duration, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+                let duration = duration
+                    .split_whitespace()
+                    .next()
+                    .and_then(parse_duration);
+                info = Some((duration, Some(title.trim().to_string()).filter(|t| !t.is_empty())));
+            } else if let Some(name) = line.strip_prefix("#PLAYLIST:") {
+                playlist.name = Some(name.trim().to_string()).filter(|name| !name.is_empty());
+            } else if !line.starts_with('#') {
+                let (duration, title) = info.take().unwrap_or_default();
+                playlist.entries.push(PlaylistFileEntry {
+                    location: line.to_string(),
+                    title,
+                    duration,
+                });
+            }
+        }
+        playlist
+    }
+
+    fn parse_pls(content: &str) -> Result<Self> {
+        let mut entries = BTreeMap::<usize, (Option<String>, Option<String>, Option<i64>)>::new();
+        for line in content.lines().map(str::trim) {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim().to_string();
+            let (field, idx) = match key.find(|c: char| c.is_ascii_digit()) {
+                Some(start) => (&key[..start], &key[start..]),
+                None => continue,
+            };
+            let idx = idx
+                .parse::<usize>()
+                .map_err(|_| eyre!("Invalid PLS key `{}`", key))?;
+            let entry = entries.entry(idx).or_default();
+            match field {
+                "file" => entry.0 = Some(value),
+                "title" => entry.1 = Some(value).filter(|title| !title.is_empty()),
+                "length" => entry.2 = parse_duration(&value),
+                _ => {}
+            }
+        }
+        let entries = entries
+            .into_iter()
+            .filter_map(|(_, (location, title, duration))| {
+                Some(PlaylistFileEntry {
+                    location: location?,
+                    title,
+                    duration,
+                })
+            })
+            .collect();
+        Ok(PlaylistFile {
+            name: None,
+            entries,
+        })
+    }
+
+    /// Extended M3U, always UTF-8 encoded
+    pub fn to_m3u8(&self) -> String {
+        let mut content = "#EXTM3U\n".to_string();
+        if let Some(name) = &self.name {
+            content.push_str(&format!("#PLAYLIST:{}\n", name));
+        }
+        for entry in self.entries.iter() {
+            content.push_str(&format!(
+                "#EXTINF:{},{}\n{}\n",
+                entry.duration.unwrap_or(-1),
+                entry.title.as_deref().unwrap_or_default(),
+                entry.location
+            ));
+        }
+        content
+    }
+}
+
+/// Negative durations mean unknown (i.e streams)
+fn parse_duration(value: &str) -> Option<i64> {
+    let duration = value.trim().parse::<f64>().ok()?;
+    (duration >= 0.0).then(|| duration.round() as i64)
+}
+
+/// Decode a playlist, `.m3u` files written by older software are usually Latin-1 encoded
+pub fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(content) => content.to_string(),
+        Err(_) => bytes.iter().map(|byte| *byte as char).collect(),
+    }
+}
+
+/// Path of an entry, relative locations are resolved against the directory of the playlist.
+/// Returns `None` for remote locations
+pub fn resolve_location(location: &str, base_dir: &Path) -> Option<PathBuf> {
+    if location.starts_with("file:") {
+        return location_to_path(location);
+    }
+    if location.contains("://") {
+        return None;
+    }
+    // Playlists written on Windows
+    let location = if cfg!(windows) {
+        location.to_string()
+    } else {
+        location.replace('\\', "/")
+    };
+    let path = Path::new(&location);
+    if path.is_absolute() {
+        return Some(normalize(path));
+    }
+    Some(normalize(&base_dir.join(path)))
+}
+
+/// Remove the `.` and `..` components so that the path matches the one stored in the library
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,133 @@
+use super::format::{decode, resolve_location, PlaylistFile, PlaylistFileEntry};
+use super::PLAYLIST_FILE_SOURCE;
+use crate::prelude::*;
+use crate::tasks::reconciliation::*;
+
+pub struct PlaylistFileImport {
+    database: Database,
+    file_loader: AddressableService<FileLoaderTask>,
+    playlist: PlaylistFile,
+    /// Directory relative locations are resolved against
+    base_dir: PathBuf,
+    name: String,
+}
+
+impl PlaylistFileImport {
+    /// `path` is the location of the playlist, its name is used when the playlist has no `#PLAYLIST:` directive
+    pub fn new(
+        database: Database,
+        file_loader: AddressableService<FileLoaderTask>,
+        content: &str,
+        path: &Path,
+    ) -> Result<Self> {
+        let playlist = PlaylistFile::parse(content)?;
+        let name = match &playlist.name {
+            Some(name) => name.clone(),
+            None => path
+                .file_stem()
+                .ok_or_else(|| eyre!("Invalid playlist path {:?}", path))?
+                .to_string_lossy()
+                .to_string(),
+        };
+        Ok(Self {
+            database,
+            file_loader,
+            playlist,
+            base_dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            name,
+        })
+    }
+
+    pub async fn from_file(
+        database: Database,
+        file_loader: AddressableService<FileLoaderTask>,
+        path: &Path,
+    ) -> Result<Self> {
+        let content = decode(&fs::read(path).await?);
+        Self::new(database, file_loader, &content, path)
+    }
+
+    /// Import the entries then create the playlist, keeping the order of the file
+    pub async fn run(&self) -> Result<ImportReport> {
+        let mut report = ImportReport::default();
+        let mut metadata_ids = HashMap::new();
+        // The same file may be written with different locations (i.e `a.mp3` and `./a.mp3`)
+        let mut resolved = HashMap::new();
+        for entry in self.playlist.entries.iter() {
+            let path = resolve_location(&entry.location, &self.base_dir);
+            let metadata_id = match path.as_ref().and_then(|path| resolved.get(path)) {
+                Some(metadata_id) => *metadata_id,
+                None => {
+                    let reconciliation = self.import_entry(entry, path.as_deref()).await;
+                    let metadata_id = reconciliation.metadata_id;
+                    if let Some(path) = path {
+                        resolved.insert(path, metadata_id);
+                    }
+                    report.tracks.push(reconciliation);
+                    metadata_id
+                }
+            };
+            if let Some(metadata_id) = metadata_id {
+                metadata_ids.insert(entry.location.clone(), metadata_id);
+            }
+        }
+
+        let playlist = ImportedPlaylist {
+            name: self.name.clone(),
+            is_folder: false,
+            children: vec![],
+            keys: self
+                .playlist
+                .entries
+                .iter()
+                .map(|entry| entry.location.clone())
+                .collect(),
+        };
+        report.playlists =
+            import_playlists(&self.database, &[playlist], &metadata_ids, PLAYLIST_FILE_SOURCE)
+                .await?;
+        Ok(report)
+    }
+
+    async fn import_entry(&self, entry: &PlaylistFileEntry, path: Option<&Path>) -> TrackReconciliation {
+        let Some(path) = path else {
+            let mut reconciliation =
+                TrackReconciliation::new(entry.location.clone(), Path::new(&entry.location));
+            reconciliation.error = Some("Remote locations are not supported".to_string());
+            return reconciliation;
+        };
+        let mut reconciliation = TrackReconciliation::new(entry.location.clone(), path);
+        if let Err(e) = self.reconcile_entry(entry, path, &mut reconciliation).await {
+            warn!(path = ?path, error = ?e, "Error importing playlist entry");
+            reconciliation.status = ReconciliationStatus::Failed;
+            reconciliation.error = Some(e.to_string());
+        }
+        reconciliation
+    }
+
+    async fn reconcile_entry(
+        &self,
+        entry: &PlaylistFileEntry,
+        path: &Path,
+        reconciliation: &mut TrackReconciliation,
+    ) -> Result<()> {
+        let (status, metadata_id) = resolve_file(&self.database, &self.file_loader, path).await?;
+        reconciliation.status = status;
+        reconciliation.metadata_id = metadata_id;
+        merge_tags(&self.database, reconciliation, entry_tags(entry), PLAYLIST_FILE_SOURCE).await
+    }
+}
+
+/// The `#EXTINF` title is usually `Artist - Title`
+fn entry_tags(entry: &PlaylistFileEntry) -> Vec<(i64, String)> {
+    let Some(title) = &entry.title else {
+        return vec![];
+    };
+    match title.split_once(" - ") {
+        Some((artist, title)) => vec![
+            (TagName::ARTIST_ID, artist.to_string()),
+            (TagName::TITLE_ID, title.to_string()),
+        ],
+        None => vec![(TagName::TITLE_ID, title.clone())],
+    }
+}
@@ -0,0 +1,25 @@
+//! Interop with plain playlists (`.m3u`, `.m3u8` and `.pls`)
+use crate::prelude::*;
+use crate::views::entry_view::EntryView;
+
+mod export;
+pub mod format;
+mod import;
+
+pub use export::*;
+pub use import::*;
+
+/// Value of the `source` columns for data coming from playlist files
+pub const PLAYLIST_FILE_SOURCE: &str = "playlist_file";
+
+/// `Artist - Title` as usually found in `#EXTINF` lines, the file name being used when the entry has no title
+pub fn entry_title(entry: &EntryView, path: &Path) -> String {
+    match (entry.tag_value(TagName::ARTIST_ID), entry.tag_value(TagName::TITLE_ID)) {
+        (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+        (None, Some(title)) => title,
+        _ => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    }
+}
@@ -0,0 +1,240 @@
+//! Shared bookkeeping of the collection importers (Rekordbox, Traktor, ...)
+use crate::prelude::*;
+
+/// Outcome of a collection import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub tracks: Vec<TrackReconciliation>,
+    pub playlists: Vec<PlaylistReconciliation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReconciliationStatus {
+    /// The file was already known
+    Matched,
+    /// The file was not known at this path but its content was (i.e `metadata.hash`)
+    MatchedByHash,
+    /// The file exists on disk and has been added to the library
+    Ingested,
+    /// The file does not exist on disk
+    Missing,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackReconciliation {
+    /// Identifier of the track in the imported collection
+    pub source_id: String,
+    pub path: String,
+    pub metadata_id: Option<i64>,
+    pub status: ReconciliationStatus,
+    /// Names of the tags added to the entry
+    pub updated: Vec<String>,
+    pub conflicts: Vec<TagConflict>,
+    pub cues: usize,
+    pub beat_grid_markers: usize,
+    pub error: Option<String>,
+}
+
+/// The entry already had a different value for the tag, the existing value is kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagConflict {
+    pub name: String,
+    pub existing: Vec<String>,
+    pub imported: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistReconciliation {
+    /// Names of the parent folders followed by the name of the playlist
+    pub path: Vec<String>,
+    pub playlist_id: i64,
+    pub entries: usize,
+    /// Entries referencing tracks that could not be resolved
+    pub missing_entries: usize,
+}
+
+impl ImportReport {
+    pub fn count(&self, status: ReconciliationStatus) -> usize {
+        self.tracks
+            .iter()
+            .filter(|track| track.status == status)
+            .count()
+    }
+}
+
+impl TrackReconciliation {
+    pub fn new(source_id: String, path: &Path) -> Self {
+        Self {
+            source_id,
+            path: path.to_string_lossy().to_string(),
+            metadata_id: None,
+            status: ReconciliationStatus::Failed,
+            updated: vec![],
+            conflicts: vec![],
+            cues: 0,
+            beat_grid_markers: 0,
+            error: None,
+        }
+    }
+}
+
+/// A playlist tree as read from another DJ software, entries being the keys of the tracks in the imported collection
+#[derive(Debug, Clone)]
+pub struct ImportedPlaylist {
+    pub name: String,
+    pub is_folder: bool,
+    pub children: Vec<ImportedPlaylist>,
+    pub keys: Vec<String>,
+}
+
+/// Find the metadata of the file at `path`, ingesting the file when it is not yet part of the library
+pub async fn resolve_file(
+    database: &Database,
+    file_loader: &AddressableService<FileLoaderTask>,
+    path: &Path,
+) -> Result<(ReconciliationStatus, Option<i64>)> {
+    if let Some(file) = database.get_file_by_path(path).await? {
+        return Ok((ReconciliationStatus::Matched, Some(file.metadata_id)));
+    }
+    if !fs::try_exists(path).await? {
+        return Ok((ReconciliationStatus::Missing, None));
+    }
+    let (callback, resolve) = TaskCallback::new();
+    file_loader
+        .send(FileLoaderTask::LoadFile {
+            path: path.to_path_buf(),
+            callback: Some(callback),
+        })
+        .await?;
+    let metadata_id = resolve.wait().await?;
+    // The content is known when the entry has other files, i.e the file moved or has a copy
+    let files = database.get_files_from_metadata_id(metadata_id).await?;
+    let status = match files.iter().any(|file| Path::new(&file.path) != path) {
+        true => ReconciliationStatus::MatchedByHash,
+        false => ReconciliationStatus::Ingested,
+    };
+    Ok((status, Some(metadata_id)))
+}
+
+/// Write imported tags, tags already set with another value are reported as conflicts instead of being overridden
+pub async fn merge_tags(
+    database: &Database,
+    track: &mut TrackReconciliation,
+    tags: Vec<(i64, String)>,
+    source: &str,
+) -> Result<()> {
+    let Some(metadata_id) = track.metadata_id else {
+        return Ok(());
+    };
+    let tag_names = database
+        .get_tag_names()
+        .await?
+        .into_iter()
+        .map(|tag_name| (tag_name.id, tag_name.name))
+        .collect::<HashMap<_, _>>();
+    let entry = database.get_entry_by_metadata_id(metadata_id).await?;
+    for (tag_name_id, value) in tags {
+        let value = value.trim().to_string();
+        if value.is_empty() {
+            continue;
+        }
+        let existing = entry
+            .tags
+            .0
+            .iter()
+            .filter(|tag| tag.tag_name_id == tag_name_id)
+            .map(|tag| tag.tag_value.clone())
+            .collect::<Vec<_>>();
+        let name = tag_names
+            .get(&tag_name_id)
+            .cloned()
+            .unwrap_or_else(|| tag_name_id.to_string());
+        if existing.contains(&value) {
+            continue;
+        }
+        if !existing.is_empty() {
+            track.conflicts.push(TagConflict {
+                name,
+                existing,
+                imported: value,
+            });
+            continue;
+        }
+        let tag_id = database.get_or_create_tag(tag_name_id, value).await?;
+        database
+            .set_metadata_tag_with_source(metadata_id, tag_id, source)
+            .await?;
+        track.updated.push(name);
+    }
+    Ok(())
+}
+
+/// Create the playlists (reusing the ones with the same name at the same place) and bind their entries,
+/// the entries also get a `playlist` tag so they can be filtered on
+pub async fn import_playlists(
+    database: &Database,
+    playlists: &[ImportedPlaylist],
+    metadata_ids: &HashMap<String, i64>,
+    source: &str,
+) -> Result<Vec<PlaylistReconciliation>> {
+    let mut reconciliations = vec![];
+    let mut to_be_processed = playlists
+        .iter()
+        .rev()
+        .map(|playlist| (playlist, None, vec![]))
+        .collect::<Vec<_>>();
+    while let Some((playlist, parent_id, mut path)) = to_be_processed.pop() {
+        path.push(playlist.name.clone());
+        let created = database
+            .get_or_create_playlist(parent_id, &playlist.name, playlist.is_folder)
+            .await?;
+        if playlist.is_folder {
+            for child in playlist.children.iter().rev() {
+                to_be_processed.push((child, Some(created.id), path.clone()));
+            }
+            continue;
+        }
+        let entries = playlist
+            .keys
+            .iter()
+            .filter_map(|key| metadata_ids.get(key).copied())
+            .collect::<Vec<_>>();
+        database.set_playlist_entries(created.id, &entries).await?;
+        let tag_id = database
+            .get_or_create_tag(TagName::PLAYLIST_ID, playlist.name.clone())
+            .await?;
+        for metadata_id in entries.iter().copied() {
+            database
+                .set_metadata_tag_with_source(metadata_id, tag_id, source)
+                .await?;
+        }
+        reconciliations.push(PlaylistReconciliation {
+            path,
+            playlist_id: created.id,
+            entries: entries.len(),
+            missing_entries: playlist.keys.len() - entries.len(),
+        });
+    }
+    Ok(reconciliations)
+}
+
+/// Some exports write numbers with the decimal separator of the system locale
+pub fn parse_number(value: &str) -> Option<f64> {
+    value.trim().replace(',', ".").parse().ok()
+}
+
+pub fn format_bpm(bpm: f64) -> String {
+    ((bpm * 100.0).round() / 100.0).to_string()
+}
+
+/// Rekordbox and Traktor store ratings from 0 to 255 by steps of 51, the `rating` tag holds the number of stars
+pub fn rating_to_stars(rating: &str) -> Option<String> {
+    let stars = (parse_number(rating)? / 51.0).round() as i64;
+    (stars > 0).then(|| stars.min(5).to_string())
+}
+
+pub fn stars_to_rating(stars: &str) -> Option<String> {
+    let stars = stars.trim().parse::<u32>().ok()?;
+    Some((stars.min(5) * 51).to_string())
+}
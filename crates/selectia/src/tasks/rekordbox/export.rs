@@ -0,0 +1,185 @@
+use super::xml::{
+    path_to_location, Collection, DjPlaylists, Node, PlaylistTrack, Playlists, PositionMark,
+    Product, Tempo, Track,
+};
+use crate::prelude::*;
+use crate::tasks::reconciliation::stars_to_rating;
+use crate::tasks::selection::{ExportSelection, PlaylistNode};
+use models::{Cue, CuePoint, TempoMarker};
+
+pub struct RekordboxExport {
+    database: Database,
+    selection: ExportSelection,
+}
+
+impl RekordboxExport {
+    pub fn new(database: Database, selection: ExportSelection) -> Self {
+        Self {
+            database,
+            selection,
+        }
+    }
+
+    /// Build the collection, tracks are identified by their metadata id.
+    /// Entries without a file are left out of the collection and of the playlists
+    pub async fn build(&self) -> Result<DjPlaylists> {
+        let mut tracks = vec![];
+        let mut exported = HashSet::new();
+        for metadata_id in self.selection.metadata_ids(&self.database).await? {
+            match self.track(metadata_id).await? {
+                Some(track) => {
+                    tracks.push(track);
+                    exported.insert(metadata_id);
+                }
+                None => warn!(metadata_id, "Entry without a file, not exported"),
+            }
+        }
+
+        let roots = self
+            .selection
+            .playlist_tree(&self.database)
+            .await?
+            .iter()
+            .map(|playlist| playlist_node(playlist, &exported))
+            .collect::<Vec<_>>();
+
+        Ok(DjPlaylists {
+            version: "1.0.0".to_string(),
+            product: Product {
+                name: "selectia".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                company: String::new(),
+            },
+            collection: Collection {
+                entries: tracks.len(),
+                tracks,
+            },
+            playlists: Playlists {
+                nodes: vec![Node {
+                    kind: Node::FOLDER.to_string(),
+                    name: "ROOT".to_string(),
+                    count: Some(roots.len()),
+                    nodes: roots,
+                    ..Default::default()
+                }],
+            },
+        })
+    }
+
+    pub async fn write(&self, path: &Path) -> Result<()> {
+        let xml = self.build().await?.to_xml()?;
+        fs::write(path, xml).await?;
+        Ok(())
+    }
+
+    async fn track(&self, metadata_id: i64) -> Result<Option<Track>> {
+        let Some(file) = self.database.get_first_file_from_metadata_id(metadata_id).await? else {
+            return Ok(None);
+        };
+        let metadata = self.database.get_metadata(metadata_id).await?;
+        let entry = self.database.get_entry_by_metadata_id(metadata_id).await?;
+        let tag = |tag_name_id: i64| entry.tag_value(tag_name_id);
+        let position_marks = self
+            .database
+            .get_cues(metadata_id)
+            .await?
+            .into_iter()
+            .map(|cue| position_mark(cue.into()))
+            .collect();
+        let tempos = self
+            .database
+            .get_beat_grid(metadata_id)
+            .await?
+            .into_iter()
+            .map(|marker| tempo(marker.into()))
+            .collect();
+        Ok(Some(Track {
+            track_id: metadata_id.to_string(),
+            name: tag(TagName::TITLE_ID),
+            artist: tag(TagName::ARTIST_ID),
+            album: tag(TagName::ALBUM_ID),
+            genre: tag(TagName::GENRE_ID),
+            total_time: metadata.duration.map(|duration| (duration.round() as i64).to_string()),
+            average_bpm: tag(TagName::TEMPO_ID)
+                .and_then(|bpm| bpm.parse::<f64>().ok())
+                .map(|bpm| format!("{:.2}", bpm)),
+            comments: tag(TagName::COMMENT_ID),
+            rating: tag(TagName::RATING_ID).and_then(|stars| stars_to_rating(&stars)),
+            location: path_to_location(Path::new(&file.path)),
+            tonality: tag(TagName::KEY_ID),
+            label: tag(TagName::LABEL_ID),
+            remixer: tag(TagName::REMIX_ID),
+            colour: tag(TagName::COLOR_ID)
+                .map(|color| format!("0x{}", color.trim_start_matches('#').to_uppercase())),
+            tempos,
+            position_marks,
+        }))
+    }
+}
+
+/// Tracks missing from `exported` are left out
+fn playlist_node(playlist: &PlaylistNode, exported: &HashSet<i64>) -> Node {
+    if playlist.is_folder {
+        let nodes = playlist
+            .children
+            .iter()
+            .map(|child| playlist_node(child, exported))
+            .collect::<Vec<_>>();
+        return Node {
+            kind: Node::FOLDER.to_string(),
+            name: playlist.name.clone(),
+            count: Some(nodes.len()),
+            nodes,
+            ..Default::default()
+        };
+    }
+    let tracks = playlist
+        .metadata_ids
+        .iter()
+        .filter(|metadata_id| exported.contains(metadata_id))
+        .map(|metadata_id| PlaylistTrack {
+            key: metadata_id.to_string(),
+        })
+        .collect::<Vec<_>>();
+    Node {
+        kind: Node::PLAYLIST.to_string(),
+        name: playlist.name.clone(),
+        key_type: Some(Node::KEY_TYPE_TRACK_ID.to_string()),
+        entries: Some(tracks.len()),
+        tracks,
+        ..Default::default()
+    }
+}
+
+fn position_mark(cue: CuePoint) -> PositionMark {
+    let kind = match cue.kind.as_str() {
+        Cue::FADE_IN_KIND => "1",
+        Cue::FADE_OUT_KIND => "2",
+        Cue::LOAD_KIND => "3",
+        Cue::LOOP_KIND => "4",
+        _ => "0",
+    };
+    let rgb = cue
+        .color
+        .as_deref()
+        .and_then(|color| u32::from_str_radix(color.trim_start_matches('#'), 16).ok());
+    PositionMark {
+        name: cue.name.unwrap_or_default(),
+        kind: kind.to_string(),
+        start: format!("{:.3}", cue.start_time),
+        end: cue.end_time.map(|end| format!("{:.3}", end)),
+        num: cue.hot_cue_index.unwrap_or(-1).to_string(),
+        red: rgb.map(|rgb| (rgb >> 16 & 0xff).to_string()),
+        green: rgb.map(|rgb| (rgb >> 8 & 0xff).to_string()),
+        blue: rgb.map(|rgb| (rgb & 0xff).to_string()),
+    }
+}
+
+fn tempo(marker: TempoMarker) -> Tempo {
+    Tempo {
+        inizio: format!("{:.3}", marker.position),
+        bpm: format!("{:.2}", marker.bpm),
+        metro: marker.meter.unwrap_or_else(|| "4/4".to_string()),
+        battito: marker.beat.to_string(),
+    }
+}
@@ -0,0 +1,196 @@
+use super::xml::{location_to_path, DjPlaylists, Node, PositionMark, Tempo, Track};
+use super::REKORDBOX_SOURCE;
+use crate::prelude::*;
+use crate::tasks::reconciliation::*;
+use models::{Cue, CuePoint, TempoMarker};
+
+pub struct RekordboxImport {
+    database: Database,
+    file_loader: AddressableService<FileLoaderTask>,
+    collection: DjPlaylists,
+}
+
+impl RekordboxImport {
+    pub fn new(
+        database: Database,
+        file_loader: AddressableService<FileLoaderTask>,
+        xml: &str,
+    ) -> Result<Self> {
+        Ok(Self {
+            database,
+            file_loader,
+            collection: DjPlaylists::parse(xml)?,
+        })
+    }
+
+    pub async fn from_file(
+        database: Database,
+        file_loader: AddressableService<FileLoaderTask>,
+        path: &Path,
+    ) -> Result<Self> {
+        let xml = fs::read_to_string(path).await?;
+        Self::new(database, file_loader, &xml)
+    }
+
+    /// Import the tracks of the collection then the playlist tree
+    pub async fn run(&self) -> Result<ImportReport> {
+        let mut report = ImportReport::default();
+        let mut metadata_ids = HashMap::new();
+        for track in self.collection.collection.tracks.iter() {
+            let reconciliation = self.import_track(track).await;
+            if let Some(metadata_id) = reconciliation.metadata_id {
+                metadata_ids.insert(track.track_id.clone(), metadata_id);
+                metadata_ids.insert(track.location.clone(), metadata_id);
+            }
+            report.tracks.push(reconciliation);
+        }
+
+        // The tree has a single `ROOT` folder which is not imported
+        let playlists = self
+            .collection
+            .playlists
+            .nodes
+            .iter()
+            .flat_map(|root| root.nodes.iter())
+            .map(imported_playlist)
+            .collect::<Vec<_>>();
+        report.playlists =
+            import_playlists(&self.database, &playlists, &metadata_ids, REKORDBOX_SOURCE).await?;
+        Ok(report)
+    }
+
+    async fn import_track(&self, track: &Track) -> TrackReconciliation {
+        let path =
+            location_to_path(&track.location).unwrap_or_else(|| PathBuf::from(&track.location));
+        let mut reconciliation = TrackReconciliation::new(track.track_id.clone(), &path);
+        if let Err(e) = self.reconcile_track(track, &path, &mut reconciliation).await {
+            warn!(path = ?path, error = ?e, "Error importing Rekordbox track");
+            reconciliation.status = ReconciliationStatus::Failed;
+            reconciliation.error = Some(e.to_string());
+        }
+        reconciliation
+    }
+
+    async fn reconcile_track(
+        &self,
+        track: &Track,
+        path: &Path,
+        reconciliation: &mut TrackReconciliation,
+    ) -> Result<()> {
+        let (status, metadata_id) = resolve_file(&self.database, &self.file_loader, path).await?;
+        reconciliation.status = status;
+        reconciliation.metadata_id = metadata_id;
+        let Some(metadata_id) = metadata_id else {
+            return Ok(());
+        };
+
+        merge_tags(&self.database, reconciliation, track_tags(track), REKORDBOX_SOURCE).await?;
+
+        let cues = track
+            .position_marks
+            .iter()
+            .filter_map(cue_point)
+            .collect::<Vec<_>>();
+        self.database
+            .replace_cues(metadata_id, REKORDBOX_SOURCE, &cues)
+            .await?;
+        reconciliation.cues = cues.len();
+
+        let markers = track
+            .tempos
+            .iter()
+            .filter_map(tempo_marker)
+            .collect::<Vec<_>>();
+        self.database
+            .replace_beat_grid(metadata_id, REKORDBOX_SOURCE, &markers)
+            .await?;
+        reconciliation.beat_grid_markers = markers.len();
+        Ok(())
+    }
+}
+
+fn imported_playlist(node: &Node) -> ImportedPlaylist {
+    ImportedPlaylist {
+        name: node.name.clone(),
+        is_folder: node.kind == Node::FOLDER,
+        children: node.nodes.iter().map(imported_playlist).collect(),
+        keys: node.tracks.iter().map(|track| track.key.clone()).collect(),
+    }
+}
+
+fn track_tags(track: &Track) -> Vec<(i64, String)> {
+    let mut tags = vec![];
+    let mut text = |tag_name_id: i64, value: &Option<String>| {
+        if let Some(value) = value {
+            tags.push((tag_name_id, value.clone()));
+        }
+    };
+    text(TagName::TITLE_ID, &track.name);
+    text(TagName::ARTIST_ID, &track.artist);
+    text(TagName::ALBUM_ID, &track.album);
+    text(TagName::GENRE_ID, &track.genre);
+    text(TagName::LABEL_ID, &track.label);
+    text(TagName::REMIX_ID, &track.remixer);
+    text(TagName::KEY_ID, &track.tonality);
+    text(TagName::COMMENT_ID, &track.comments);
+
+    if let Some(bpm) = track.average_bpm.as_deref().and_then(parse_number) {
+        if bpm > 0.0 {
+            tags.push((TagName::TEMPO_ID, format_bpm(bpm)));
+        }
+    }
+    if let Some(stars) = track.rating.as_deref().and_then(rating_to_stars) {
+        tags.push((TagName::RATING_ID, stars));
+    }
+    if let Some(color) = track.colour.as_deref().and_then(parse_colour) {
+        tags.push((TagName::COLOR_ID, color));
+    }
+    tags
+}
+
+fn cue_point(mark: &PositionMark) -> Option<CuePoint> {
+    let kind = match mark.kind.as_str() {
+        "0" => Cue::CUE_KIND,
+        "1" => Cue::FADE_IN_KIND,
+        "2" => Cue::FADE_OUT_KIND,
+        "3" => Cue::LOAD_KIND,
+        "4" => Cue::LOOP_KIND,
+        kind => {
+            warn!(kind, "Unknown Rekordbox position mark type");
+            return None;
+        }
+    };
+    let color = match (&mark.red, &mark.green, &mark.blue) {
+        (Some(red), Some(green), Some(blue)) => Some(format!(
+            "#{:02x}{:02x}{:02x}",
+            red.parse::<u8>().ok()?,
+            green.parse::<u8>().ok()?,
+            blue.parse::<u8>().ok()?
+        )),
+        _ => None,
+    };
+    Some(CuePoint {
+        kind: kind.to_string(),
+        hot_cue_index: mark.num.parse::<i64>().ok().filter(|num| *num >= 0),
+        start_time: parse_number(&mark.start)?,
+        end_time: mark.end.as_deref().and_then(parse_number),
+        name: Some(mark.name.clone()).filter(|name| !name.is_empty()),
+        color,
+    })
+}
+
+fn tempo_marker(tempo: &Tempo) -> Option<TempoMarker> {
+    Some(TempoMarker {
+        position: parse_number(&tempo.inizio)?,
+        bpm: parse_number(&tempo.bpm)?,
+        meter: Some(tempo.metro.clone()).filter(|metro| !metro.is_empty()),
+        beat: tempo.battito.parse().unwrap_or(1),
+    })
+}
+
+/// `0xRRGGBB` to `#rrggbb`
+fn parse_colour(colour: &str) -> Option<String> {
+    let hex = colour.trim().trim_start_matches("0x").trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(format!("#{:06x}", value & 0xffffff))
+}
@@ -0,0 +1,10 @@
+//! Interop with Rekordbox collections exported as xml (`File > Export Collection in xml format`)
+mod export;
+mod import;
+pub mod xml;
+
+pub use export::*;
+pub use import::*;
+
+/// Value of the `source` columns for data coming from Rekordbox
+pub const REKORDBOX_SOURCE: &str = "rekordbox";
@@ -0,0 +1,220 @@
+//! Serde model of the `rekordbox.xml` collection format
+//!
+//! Attributes are kept as strings since Rekordbox is lax with their content (empty numbers, localized values)
+use crate::prelude::*;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename = "DJ_PLAYLISTS")]
+pub struct DjPlaylists {
+    #[serde(rename = "@Version")]
+    pub version: String,
+    #[serde(rename = "PRODUCT")]
+    pub product: Product,
+    #[serde(rename = "COLLECTION")]
+    pub collection: Collection,
+    #[serde(rename = "PLAYLISTS", default)]
+    pub playlists: Playlists,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Product {
+    #[serde(rename = "@Name")]
+    pub name: String,
+    #[serde(rename = "@Version", default)]
+    pub version: String,
+    #[serde(rename = "@Company", default)]
+    pub company: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Collection {
+    #[serde(rename = "@Entries")]
+    pub entries: usize,
+    #[serde(rename = "TRACK", default)]
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Track {
+    #[serde(rename = "@TrackID")]
+    pub track_id: String,
+    #[serde(rename = "@Name", default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "@Artist", default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(rename = "@Album", default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(rename = "@Genre", default, skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    #[serde(rename = "@TotalTime", default, skip_serializing_if = "Option::is_none")]
+    pub total_time: Option<String>,
+    #[serde(rename = "@AverageBpm", default, skip_serializing_if = "Option::is_none")]
+    pub average_bpm: Option<String>,
+    #[serde(rename = "@Comments", default, skip_serializing_if = "Option::is_none")]
+    pub comments: Option<String>,
+    /// 0 to 255 by steps of 51 (i.e 0 to 5 stars)
+    #[serde(rename = "@Rating", default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<String>,
+    #[serde(rename = "@Location")]
+    pub location: String,
+    #[serde(rename = "@Tonality", default, skip_serializing_if = "Option::is_none")]
+    pub tonality: Option<String>,
+    #[serde(rename = "@Label", default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(rename = "@Remixer", default, skip_serializing_if = "Option::is_none")]
+    pub remixer: Option<String>,
+    /// `0xRRGGBB`
+    #[serde(rename = "@Colour", default, skip_serializing_if = "Option::is_none")]
+    pub colour: Option<String>,
+    #[serde(rename = "TEMPO", default)]
+    pub tempos: Vec<Tempo>,
+    #[serde(rename = "POSITION_MARK", default)]
+    pub position_marks: Vec<PositionMark>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tempo {
+    /// Position in seconds
+    #[serde(rename = "@Inizio")]
+    pub inizio: String,
+    #[serde(rename = "@Bpm")]
+    pub bpm: String,
+    #[serde(rename = "@Metro", default)]
+    pub metro: String,
+    /// Beat of the bar, from 1 to 4
+    #[serde(rename = "@Battito", default)]
+    pub battito: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PositionMark {
+    #[serde(rename = "@Name", default)]
+    pub name: String,
+    /// 0: cue, 1: fade-in, 2: fade-out, 3: load, 4: loop
+    #[serde(rename = "@Type")]
+    pub kind: String,
+    #[serde(rename = "@Start")]
+    pub start: String,
+    #[serde(rename = "@End", default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// Hot cue slot, -1 for memory cues
+    #[serde(rename = "@Num")]
+    pub num: String,
+    #[serde(rename = "@Red", default, skip_serializing_if = "Option::is_none")]
+    pub red: Option<String>,
+    #[serde(rename = "@Green", default, skip_serializing_if = "Option::is_none")]
+    pub green: Option<String>,
+    #[serde(rename = "@Blue", default, skip_serializing_if = "Option::is_none")]
+    pub blue: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Playlists {
+    #[serde(rename = "NODE", default)]
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Node {
+    /// `Node::FOLDER` or `Node::PLAYLIST`
+    #[serde(rename = "@Type")]
+    pub kind: String,
+    #[serde(rename = "@Name")]
+    pub name: String,
+    /// Number of child nodes of a folder
+    #[serde(rename = "@Count", default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    /// How the tracks of a playlist reference the collection, `0` by track id and `1` by location
+    #[serde(rename = "@KeyType", default, skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<String>,
+    #[serde(rename = "@Entries", default, skip_serializing_if = "Option::is_none")]
+    pub entries: Option<usize>,
+    #[serde(rename = "NODE", default)]
+    pub nodes: Vec<Node>,
+    #[serde(rename = "TRACK", default)]
+    pub tracks: Vec<PlaylistTrack>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaylistTrack {
+    #[serde(rename = "@Key")]
+    pub key: String,
+}
+
+impl Node {
+    pub const FOLDER: &str = "0";
+    pub const PLAYLIST: &str = "1";
+    pub const KEY_TYPE_TRACK_ID: &str = "0";
+    pub const KEY_TYPE_LOCATION: &str = "1";
+}
+
+impl DjPlaylists {
+    pub fn parse(xml: &str) -> Result<Self> {
+        Ok(quick_xml::de::from_str(xml)?)
+    }
+
+    pub fn to_xml(&self) -> Result<String> {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let mut serializer = quick_xml::se::Serializer::with_root(&mut xml, Some("DJ_PLAYLISTS"))?;
+        serializer.indent(' ', 2);
+        self.serialize(serializer)?;
+        xml.push('\n');
+        Ok(xml)
+    }
+}
+
+const LOCATION_PREFIX: &str = "file://localhost";
+
+/// Characters escaped in a location along with every non ascii character, `/` and `:` are kept as Rekordbox does
+const LOCATION_ESCAPED: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Convert a `file://localhost/...` location to a path, Windows locations keep their drive letter (i.e `C:/Music/a.mp3`)
+pub fn location_to_path(location: &str) -> Option<PathBuf> {
+    let encoded = location
+        .strip_prefix(LOCATION_PREFIX)
+        .or_else(|| location.strip_prefix("file://"))?;
+    let decoded = percent_decode_str(encoded).decode_utf8().ok()?;
+    let bytes = decoded.as_bytes();
+    let path = if bytes.len() > 2 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        &decoded[1..]
+    } else {
+        &decoded[..]
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Convert a path to a `file://localhost/...` location
+pub fn path_to_location(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let path = if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.to_string()
+    };
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        format!("/{}", path)
+    };
+    format!(
+        "{}{}",
+        LOCATION_PREFIX,
+        utf8_percent_encode(&path, LOCATION_ESCAPED)
+    )
+}
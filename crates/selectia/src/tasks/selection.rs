@@ -0,0 +1,115 @@
+use crate::prelude::*;
+use crate::views::entry_view::EntryViewFilter;
+
+/// Subset of the library handed to the exporters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ExportSelection {
+    /// Every entry along with the whole playlist tree
+    All,
+    /// A playlist, or every playlist under a folder
+    Playlist(i64),
+    /// Entries matching the filter, without any playlist
+    Filter(EntryViewFilter),
+}
+
+/// A selected playlist along with its children (folders) or its entries (playlists)
+#[derive(Debug, Clone)]
+pub struct PlaylistNode {
+    pub name: String,
+    pub is_folder: bool,
+    pub children: Vec<PlaylistNode>,
+    pub metadata_ids: Vec<i64>,
+}
+
+impl ExportSelection {
+    /// Metadata ids of the selected entries, playlists keep their order
+    pub async fn metadata_ids(&self, database: &Database) -> Result<Vec<i64>> {
+        let metadata_ids = match self {
+            ExportSelection::All => database
+                .get_entries(&EntryViewFilter::default())
+                .await?
+                .into_iter()
+                .map(|entry| entry.metadata_id)
+                .collect(),
+            ExportSelection::Filter(filter) => database
+                .get_entries(filter)
+                .await?
+                .into_iter()
+                .map(|entry| entry.metadata_id)
+                .collect(),
+            ExportSelection::Playlist(_) => {
+                let mut seen = HashSet::new();
+                let mut metadata_ids = vec![];
+                for playlist in self.playlists(database).await? {
+                    for metadata_id in database.get_playlist_entries(playlist.id).await? {
+                        if seen.insert(metadata_id) {
+                            metadata_ids.push(metadata_id);
+                        }
+                    }
+                }
+                metadata_ids
+            }
+        };
+        Ok(metadata_ids)
+    }
+
+    /// Selected playlists, parents first, the selected playlist being moved to the top level
+    pub async fn playlists(&self, database: &Database) -> Result<Vec<models::Playlist>> {
+        let playlists = database.get_playlists().await?;
+        let root_id = match self {
+            ExportSelection::All => return Ok(playlists),
+            ExportSelection::Filter(_) => return Ok(vec![]),
+            ExportSelection::Playlist(id) => *id,
+        };
+        let mut root = playlists
+            .iter()
+            .find(|playlist| playlist.id == root_id)
+            .cloned()
+            .ok_or_else(|| eyre!("Playlist {} does not exist", root_id))?;
+        root.parent_id = None;
+        let mut selected = vec![root];
+        let mut idx = 0;
+        while idx < selected.len() {
+            let parent_id = selected[idx].id;
+            selected.extend(
+                playlists
+                    .iter()
+                    .filter(|playlist| playlist.parent_id == Some(parent_id))
+                    .cloned(),
+            );
+            idx += 1;
+        }
+        Ok(selected)
+    }
+
+    /// Selected playlists as a tree
+    pub async fn playlist_tree(&self, database: &Database) -> Result<Vec<PlaylistNode>> {
+        let playlists = self.playlists(database).await?;
+        let mut entries = HashMap::new();
+        for playlist in playlists.iter().filter(|playlist| !playlist.is_folder) {
+            entries.insert(playlist.id, database.get_playlist_entries(playlist.id).await?);
+        }
+        Ok(playlists
+            .iter()
+            .filter(|playlist| playlist.parent_id.is_none())
+            .map(|playlist| playlist_node(playlist, &playlists, &mut entries))
+            .collect())
+    }
+}
+
+fn playlist_node(
+    playlist: &models::Playlist,
+    playlists: &[models::Playlist],
+    entries: &mut HashMap<i64, Vec<i64>>,
+) -> PlaylistNode {
+    PlaylistNode {
+        name: playlist.name.clone(),
+        is_folder: playlist.is_folder,
+        children: playlists
+            .iter()
+            .filter(|child| child.parent_id == Some(playlist.id))
+            .map(|child| playlist_node(child, playlists, entries))
+            .collect(),
+        metadata_ids: entries.remove(&playlist.id).unwrap_or_default(),
+    }
+}
@@ -0,0 +1,113 @@
+//! Serato crates are a list of fields, each field being a 4 bytes tag, a big endian u32 length and the data.
+//! Tags starting with `o` hold nested fields, strings are UTF-16BE.
+use crate::prelude::*;
+
+const VERSION: &str = "1.0/Serato ScratchLive Crate";
+/// Columns displayed by Serato when opening the crate
+const COLUMNS: [&str; 5] = ["song", "artist", "album", "bpm", "key"];
+/// Separator of the parent and child crate names in the file names
+pub const SUBCRATE_SEPARATOR: &str = "%%";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeratoCrate {
+    /// Paths relative to the root of their volume, `/` separated
+    pub tracks: Vec<String>,
+}
+
+impl SeratoCrate {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_field(&mut bytes, b"vrsn", &utf16(VERSION));
+
+        let mut sort = vec![];
+        write_field(&mut sort, b"tvcn", &utf16(COLUMNS[0]));
+        write_field(&mut sort, b"brev", &[0]);
+        write_field(&mut bytes, b"osrt", &sort);
+
+        for column in COLUMNS {
+            let mut field = vec![];
+            write_field(&mut field, b"tvcn", &utf16(column));
+            write_field(&mut field, b"tvcw", &utf16("0"));
+            write_field(&mut bytes, b"ovct", &field);
+        }
+
+        for track in self.tracks.iter() {
+            let mut field = vec![];
+            write_field(&mut field, b"ptrk", &utf16(track));
+            write_field(&mut bytes, b"otrk", &field);
+        }
+        bytes
+    }
+
+    /// Read the tracks of a crate, other fields are ignored
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut tracks = vec![];
+        for (tag, data) in read_fields(bytes)? {
+            if tag != *b"otrk" {
+                continue;
+            }
+            for (tag, data) in read_fields(data)? {
+                if tag == *b"ptrk" {
+                    tracks.push(from_utf16(data)?);
+                }
+            }
+        }
+        Ok(Self { tracks })
+    }
+}
+
+/// File name of the crate, nested crates being named after all their parents
+pub fn crate_file_name(path: &[String]) -> String {
+    let names = path
+        .iter()
+        .map(|name| name.replace(SUBCRATE_SEPARATOR, "%").replace(['/', '\\', ':'], "_"))
+        .collect::<Vec<_>>();
+    format!("{}.crate", names.join(SUBCRATE_SEPARATOR))
+}
+
+/// Serato stores paths relative to the root of the volume holding the `_Serato_` folder, without leading separator
+pub fn track_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let bytes = path.as_bytes();
+    let path = if bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        &path[2..]
+    } else {
+        &path[..]
+    };
+    path.trim_start_matches('/').to_string()
+}
+
+fn write_field(bytes: &mut Vec<u8>, tag: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(tag);
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(data);
+}
+
+fn read_fields(mut bytes: &[u8]) -> Result<Vec<([u8; 4], &[u8])>> {
+    let mut fields = vec![];
+    while !bytes.is_empty() {
+        if bytes.len() < 8 {
+            return Err(eyre!("Truncated crate field"));
+        }
+        let tag = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        let len = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+        let data = bytes
+            .get(8..8 + len)
+            .ok_or_else(|| eyre!("Truncated crate field"))?;
+        fields.push((tag, data));
+        bytes = &bytes[8 + len..];
+    }
+    Ok(fields)
+}
+
+fn utf16(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+fn from_utf16(bytes: &[u8]) -> Result<String> {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+        .collect::<Vec<_>>();
+    Ok(String::from_utf16(&units)?)
+}
@@ -0,0 +1,139 @@
+use super::crate_file::{crate_file_name, track_path, SeratoCrate};
+use super::id3::{replace_geob_frames, GeobFrame};
+use super::markers::*;
+use crate::prelude::*;
+use crate::tasks::selection::{ExportSelection, PlaylistNode};
+use models::CuePoint;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeratoExportReport {
+    /// File names of the written crates
+    pub crates: Vec<String>,
+    /// Files the markers were written to
+    pub files: Vec<SeratoFileReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeratoFileReport {
+    pub metadata_id: i64,
+    pub path: String,
+    pub error: Option<String>,
+}
+
+pub struct SeratoExport {
+    database: Database,
+    selection: ExportSelection,
+}
+
+impl SeratoExport {
+    pub fn new(database: Database, selection: ExportSelection) -> Self {
+        Self {
+            database,
+            selection,
+        }
+    }
+
+    /// A crate per selected playlist and folder (Serato has no folders, crates can be nested instead), by file name
+    pub async fn crates(&self) -> Result<Vec<(String, SeratoCrate)>> {
+        let mut crates = vec![];
+        let mut to_be_processed = self
+            .selection
+            .playlist_tree(&self.database)
+            .await?
+            .into_iter()
+            .rev()
+            .map(|playlist| (playlist, vec![]))
+            .collect::<Vec<(PlaylistNode, Vec<String>)>>();
+        while let Some((playlist, mut path)) = to_be_processed.pop() {
+            path.push(playlist.name.clone());
+            let mut tracks = vec![];
+            for metadata_id in playlist.metadata_ids.iter().copied() {
+                let file = self.database.get_file_from_metadata_id(metadata_id).await?;
+                tracks.push(track_path(Path::new(&file.path)));
+            }
+            crates.push((crate_file_name(&path), SeratoCrate { tracks }));
+            for child in playlist.children.into_iter().rev() {
+                to_be_processed.push((child, path.clone()));
+            }
+        }
+        Ok(crates)
+    }
+
+    /// `Serato Markers2` and `Serato BeatGrid` frames of the entry, the grid frame being omitted without grid markers
+    pub async fn frames(&self, metadata_id: i64) -> Result<Vec<GeobFrame>> {
+        let entry = self.database.get_entry_by_metadata_id(metadata_id).await?;
+        let cues = self
+            .database
+            .get_cues(metadata_id)
+            .await?
+            .into_iter()
+            .map(CuePoint::from)
+            .collect::<Vec<_>>();
+        let color = entry.tag_value(TagName::COLOR_ID);
+        let mut frames = vec![GeobFrame::new(
+            MARKERS2_DESCRIPTION,
+            markers2_frame_data(&markers2_payload(color.as_deref(), &cues)),
+        )];
+        let markers = self
+            .database
+            .get_beat_grid(metadata_id)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<_>>();
+        if let Some(data) = beatgrid_frame_data(&markers) {
+            frames.push(GeobFrame::new(BEATGRID_DESCRIPTION, data));
+        }
+        Ok(frames)
+    }
+
+    /// Write the crates into `<serato_dir>/Subcrates` and, when `write_markers` is set, the cues and grids into the MP3 files
+    pub async fn write(&self, serato_dir: &Path, write_markers: bool) -> Result<SeratoExportReport> {
+        let mut report = SeratoExportReport::default();
+        let subcrates = serato_dir.join("Subcrates");
+        fs::create_dir_all(&subcrates).await?;
+        for (file_name, serato_crate) in self.crates().await? {
+            fs::write(subcrates.join(&file_name), serato_crate.to_bytes()).await?;
+            report.crates.push(file_name);
+        }
+        if !write_markers {
+            return Ok(report);
+        }
+        for metadata_id in self.selection.metadata_ids(&self.database).await? {
+            let file = self.database.get_file_from_metadata_id(metadata_id).await?;
+            let error = match self.write_markers(metadata_id, Path::new(&file.path)).await {
+                Ok(()) => None,
+                Err(e) => {
+                    warn!(path = file.path, error = ?e, "Error writing Serato markers");
+                    Some(e.to_string())
+                }
+            };
+            report.files.push(SeratoFileReport {
+                metadata_id,
+                path: file.path,
+                error,
+            });
+        }
+        Ok(report)
+    }
+
+    async fn write_markers(&self, metadata_id: i64, path: &Path) -> Result<()> {
+        let is_mp3 = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("mp3"));
+        if !is_mp3 {
+            return Err(eyre!("Serato markers can only be written to MP3 files"));
+        }
+        let frames = self.frames(metadata_id).await?;
+        let content = replace_geob_frames(&fs::read(path).await?, &frames)?;
+        // Write next to the file then swap so that a failure never leaves a truncated file
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| eyre!("Invalid path {:?}", path))?
+            .to_string_lossy();
+        let temporary = path.with_file_name(format!(".{}.selectia", file_name));
+        fs::write(&temporary, content).await?;
+        fs::rename(&temporary, path).await?;
+        Ok(())
+    }
+}
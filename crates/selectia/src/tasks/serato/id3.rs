@@ -0,0 +1,230 @@
+//! Just enough of ID3v2.3/ID3v2.4 to replace `GEOB` frames while keeping the other frames untouched,
+//! and to read the attached pictures
+use crate::prelude::*;
+
+const HEADER_SIZE: usize = 10;
+const GEOB_MIME_TYPE: &str = "application/octet-stream";
+/// Version used when the file has no tag yet
+const DEFAULT_MAJOR_VERSION: u8 = 4;
+
+/// A general encapsulated object, Serato identifies its frames by their description
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeobFrame {
+    pub description: String,
+    pub data: Vec<u8>,
+}
+
+/// An attached picture (`APIC` frame)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttachedPicture {
+    pub mime_type: String,
+    /// 3 is the front cover
+    pub picture_type: u8,
+    pub data: Vec<u8>,
+}
+
+impl GeobFrame {
+    pub fn new(description: &str, data: Vec<u8>) -> Self {
+        Self {
+            description: description.to_string(),
+            data,
+        }
+    }
+
+    fn body(&self) -> Vec<u8> {
+        let mut body = vec![0x00]; // ISO-8859-1
+        body.extend_from_slice(GEOB_MIME_TYPE.as_bytes());
+        body.push(0x00);
+        body.push(0x00); // no file name
+        body.extend_from_slice(self.description.as_bytes());
+        body.push(0x00);
+        body.extend_from_slice(&self.data);
+        body
+    }
+}
+
+/// Size of the ID3v2 tag, header included, given at least the first 10 bytes of the file
+pub fn tag_len(header: &[u8]) -> Option<usize> {
+    if header.len() < HEADER_SIZE || !header.starts_with(b"ID3") {
+        return None;
+    }
+    let footer = if header[5] & 0x10 != 0 { HEADER_SIZE } else { 0 };
+    Some(HEADER_SIZE + read_syncsafe(&header[6..10]) as usize + footer)
+}
+
+/// Pictures of the ID3v2 tag at the start of `content`, which may hold only the tag
+pub fn attached_pictures(content: &[u8]) -> Result<Vec<AttachedPicture>> {
+    let Some((major_version, tag)) = read_tag(content)? else {
+        return Ok(vec![]);
+    };
+    Ok(frames(tag, major_version)?
+        .into_iter()
+        .filter(|frame| &frame[..4] == b"APIC")
+        .filter_map(|frame| attached_picture(&frame[HEADER_SIZE..]))
+        .collect())
+}
+
+/// Replace the `GEOB` frames having the description of one of `frames` in the ID3v2 tag at the start of `content`,
+/// the tag is created when missing. Returns the whole new file content
+pub fn replace_geob_frames(content: &[u8], frames: &[GeobFrame]) -> Result<Vec<u8>> {
+    let (major_version, kept_frames, audio) = match read_tag(content)? {
+        Some((major_version, tag)) => {
+            let kept_frames = kept_frames(tag, major_version, frames)?;
+            (major_version, kept_frames, &content[HEADER_SIZE + tag.len()..])
+        }
+        None => (DEFAULT_MAJOR_VERSION, vec![], content),
+    };
+
+    let mut tag = kept_frames;
+    for frame in frames {
+        let body = frame.body();
+        tag.extend_from_slice(b"GEOB");
+        match major_version {
+            3 => tag.extend((body.len() as u32).to_be_bytes()),
+            _ => tag.extend(write_syncsafe(body.len() as u32)?),
+        }
+        tag.extend([0x00, 0x00]);
+        tag.extend(body);
+    }
+
+    let mut output = Vec::with_capacity(HEADER_SIZE + tag.len() + audio.len());
+    output.extend_from_slice(b"ID3");
+    output.extend([major_version, 0x00, 0x00]);
+    output.extend(write_syncsafe(tag.len() as u32)?);
+    output.extend(tag);
+    output.extend_from_slice(audio);
+    Ok(output)
+}
+
+/// Major version and frames area of the ID3v2 tag at the start of `content`, if any
+fn read_tag(content: &[u8]) -> Result<Option<(u8, &[u8])>> {
+    if !content.starts_with(b"ID3") {
+        return Ok(None);
+    }
+    if content.len() < HEADER_SIZE {
+        return Err(eyre!("Truncated ID3 header"));
+    }
+    let major_version = content[3];
+    if major_version != 3 && major_version != 4 {
+        return Err(eyre!("Unsupported ID3v2.{} tag", major_version));
+    }
+    let flags = content[5];
+    if flags & 0xD0 != 0 {
+        return Err(eyre!("ID3 tags with unsynchronisation, extended header or footer are not supported"));
+    }
+    let tag_end = HEADER_SIZE + read_syncsafe(&content[6..10]) as usize;
+    let tag = content
+        .get(HEADER_SIZE..tag_end)
+        .ok_or_else(|| eyre!("Truncated ID3 tag"))?;
+    Ok(Some((major_version, tag)))
+}
+
+/// Raw bytes of each frame, header included, padding is dropped
+fn frames(mut tag: &[u8], major_version: u8) -> Result<Vec<&[u8]>> {
+    let mut frames = vec![];
+    while tag.len() >= HEADER_SIZE && tag[0] != 0 {
+        let size = match major_version {
+            3 => u32::from_be_bytes([tag[4], tag[5], tag[6], tag[7]]),
+            _ => read_syncsafe(&tag[4..8]),
+        } as usize;
+        let frame = tag
+            .get(..HEADER_SIZE + size)
+            .ok_or_else(|| eyre!("Truncated ID3 frame"))?;
+        frames.push(frame);
+        tag = &tag[HEADER_SIZE + size..];
+    }
+    Ok(frames)
+}
+
+/// Raw bytes of the frames to keep
+fn kept_frames(tag: &[u8], major_version: u8, replaced: &[GeobFrame]) -> Result<Vec<u8>> {
+    let mut kept = vec![];
+    for frame in frames(tag, major_version)? {
+        let is_replaced = &frame[..4] == b"GEOB"
+            && geob_description(&frame[HEADER_SIZE..]).is_some_and(|description| {
+                replaced.iter().any(|frame| frame.description == description)
+            });
+        if !is_replaced {
+            kept.extend_from_slice(frame);
+        }
+    }
+    Ok(kept)
+}
+
+fn attached_picture(body: &[u8]) -> Option<AttachedPicture> {
+    let (encoding, rest) = body.split_first()?;
+    let mime_end = rest.iter().position(|byte| *byte == 0)?;
+    let mime_type = String::from_utf8_lossy(&rest[..mime_end]).to_string();
+    let (picture_type, rest) = rest[mime_end + 1..].split_first()?;
+    let description_len = match encoding {
+        1 | 2 => {
+            rest.chunks_exact(2)
+                .position(|unit| unit == [0, 0])?
+                * 2
+                + 2
+        }
+        _ => rest.iter().position(|byte| *byte == 0)? + 1,
+    };
+    Some(AttachedPicture {
+        mime_type,
+        picture_type: *picture_type,
+        data: rest[description_len..].to_vec(),
+    })
+}
+
+fn geob_description(body: &[u8]) -> Option<String> {
+    let (encoding, rest) = body.split_first()?;
+    let mime_end = rest.iter().position(|byte| *byte == 0)?;
+    let rest = &rest[mime_end + 1..];
+    match encoding {
+        // UTF-16 strings end with two zero bytes
+        1 | 2 => {
+            let terminator = |bytes: &[u8]| {
+                bytes
+                    .chunks_exact(2)
+                    .position(|unit| unit == [0, 0])
+                    .map(|idx| idx * 2)
+            };
+            let file_name_end = terminator(rest)?;
+            let rest = &rest[file_name_end + 2..];
+            let description = &rest[..terminator(rest)?];
+            let (description, big_endian) = match description {
+                [0xFE, 0xFF, rest @ ..] => (rest, true),
+                [0xFF, 0xFE, rest @ ..] => (rest, false),
+                description => (description, *encoding == 2),
+            };
+            let units = description
+                .chunks_exact(2)
+                .map(|unit| match big_endian {
+                    true => u16::from_be_bytes([unit[0], unit[1]]),
+                    false => u16::from_le_bytes([unit[0], unit[1]]),
+                })
+                .collect::<Vec<_>>();
+            String::from_utf16(&units).ok()
+        }
+        _ => {
+            let file_name_end = rest.iter().position(|byte| *byte == 0)?;
+            let rest = &rest[file_name_end + 1..];
+            let description_end = rest.iter().position(|byte| *byte == 0)?;
+            Some(String::from_utf8_lossy(&rest[..description_end]).to_string())
+        }
+    }
+}
+
+fn read_syncsafe(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |value, byte| (value << 7) | (*byte & 0x7F) as u32)
+}
+
+fn write_syncsafe(value: u32) -> Result<[u8; 4]> {
+    if value >= 1 << 28 {
+        return Err(eyre!("ID3 tag too large"));
+    }
+    Ok([
+        (value >> 21) as u8 & 0x7F,
+        (value >> 14) as u8 & 0x7F,
+        (value >> 7) as u8 & 0x7F,
+        value as u8 & 0x7F,
+    ])
+}
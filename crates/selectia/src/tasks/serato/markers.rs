@@ -0,0 +1,110 @@
+//! Payloads of the `Serato Markers2` (base64 encoded entries) and `Serato BeatGrid` (binary) GEOB frames
+use base64ct::{Base64, Encoding};
+
+use crate::prelude::*;
+use models::{Cue, CuePoint, TempoMarker};
+
+pub const MARKERS2_DESCRIPTION: &str = "Serato Markers2";
+pub const BEATGRID_DESCRIPTION: &str = "Serato BeatGrid";
+
+const DEFAULT_TRACK_COLOR: [u8; 3] = [0xFF, 0xFF, 0xFF];
+const DEFAULT_CUE_COLOR: [u8; 3] = [0xCC, 0x00, 0x00];
+const DEFAULT_LOOP_COLOR: [u8; 3] = [0x27, 0xAA, 0xE1];
+/// Serato pads the `Serato Markers2` frame data with zeros up to this size
+const MARKERS2_MIN_SIZE: usize = 470;
+const BASE64_LINE_LENGTH: usize = 72;
+
+/// Decoded `Serato Markers2` entries: the track color then the hot cues and the saved loops, cues without slot are not supported by Serato
+pub fn markers2_payload(track_color: Option<&str>, cues: &[CuePoint]) -> Vec<u8> {
+    let mut payload = vec![0x01, 0x01];
+
+    let mut color = vec![0x00];
+    color.extend(track_color.and_then(rgb).unwrap_or(DEFAULT_TRACK_COLOR));
+    write_entry(&mut payload, "COLOR", &color);
+
+    for cue in cues.iter() {
+        let Some(index) = cue.hot_cue_index.and_then(|index| u8::try_from(index).ok()) else {
+            continue;
+        };
+        let name = cue.name.as_deref().unwrap_or_default();
+        let mut data = vec![0x00, index];
+        data.extend(seconds_to_ms(cue.start_time).to_be_bytes());
+        match (cue.kind.as_str(), cue.end_time) {
+            (Cue::LOOP_KIND, Some(end_time)) => {
+                data.extend(seconds_to_ms(end_time).to_be_bytes());
+                data.extend([0xFF, 0xFF, 0xFF, 0xFF, 0x00]);
+                data.extend(cue.color.as_deref().and_then(rgb).unwrap_or(DEFAULT_LOOP_COLOR));
+                data.extend([0x00, 0x00]); // unlocked
+                data.extend(name.as_bytes());
+                data.push(0x00);
+                write_entry(&mut payload, "LOOP", &data);
+            }
+            _ => {
+                data.push(0x00);
+                data.extend(cue.color.as_deref().and_then(rgb).unwrap_or(DEFAULT_CUE_COLOR));
+                data.extend([0x00, 0x00]);
+                data.extend(name.as_bytes());
+                data.push(0x00);
+                write_entry(&mut payload, "CUE", &data);
+            }
+        }
+    }
+
+    write_entry(&mut payload, "BPMLOCK", &[0x00]);
+    payload.push(0x00);
+    payload
+}
+
+/// Data of the `Serato Markers2` frame: a version header and the base64 encoded payload split in lines
+pub fn markers2_frame_data(payload: &[u8]) -> Vec<u8> {
+    // Serato does not pad the base64 data with `=`
+    let encoded = Base64::encode_string(payload).replace('=', "A");
+    let mut data = vec![0x01, 0x01];
+    for (idx, line) in encoded.as_bytes().chunks(BASE64_LINE_LENGTH).enumerate() {
+        if idx > 0 {
+            data.push(b'\n');
+        }
+        data.extend_from_slice(line);
+    }
+    if data.len() < MARKERS2_MIN_SIZE {
+        data.resize(MARKERS2_MIN_SIZE, 0x00);
+    }
+    data
+}
+
+/// Data of the `Serato BeatGrid` frame, every marker but the last one holds the number of beats until the next marker
+pub fn beatgrid_frame_data(markers: &[TempoMarker]) -> Option<Vec<u8>> {
+    let mut markers = markers.to_vec();
+    markers.sort_by(|a, b| a.position.total_cmp(&b.position));
+    let last = markers.last()?;
+
+    let mut data = vec![0x01, 0x00];
+    data.extend((markers.len() as u32).to_be_bytes());
+    for marker in markers.windows(2) {
+        let beats = ((marker[1].position - marker[0].position) * marker[0].bpm / 60.0).round();
+        data.extend((marker[0].position as f32).to_be_bytes());
+        data.extend((beats as u32).to_be_bytes());
+    }
+    data.extend((last.position as f32).to_be_bytes());
+    data.extend((last.bpm as f32).to_be_bytes());
+    data.push(0x00);
+    Some(data)
+}
+
+fn write_entry(payload: &mut Vec<u8>, name: &str, data: &[u8]) {
+    payload.extend_from_slice(name.as_bytes());
+    payload.push(0x00);
+    payload.extend((data.len() as u32).to_be_bytes());
+    payload.extend_from_slice(data);
+}
+
+fn seconds_to_ms(seconds: f64) -> u32 {
+    (seconds * 1000.0).round().max(0.0) as u32
+}
+
+/// `#rrggbb` to bytes
+fn rgb(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let color = u32::from_str_radix(hex, 16).ok()?;
+    Some([(color >> 16) as u8, (color >> 8) as u8, color as u8])
+}
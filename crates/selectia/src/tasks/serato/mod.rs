@@ -0,0 +1,7 @@
+//! Export to Serato: crates (`_Serato_/Subcrates/*.crate`) and the `Serato Markers2` / `Serato BeatGrid` ID3 frames
+pub mod crate_file;
+mod export;
+pub mod id3;
+pub mod markers;
+
+pub use export::*;
@@ -0,0 +1,95 @@
+//! Aggregates over the library to see where the collection is thin, computed by the database
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::prelude::*;
+
+/// Width of the tempo histogram ranges, in BPM
+pub const TEMPO_BIN_WIDTH: f64 = 5.0;
+/// Values listed per tag name, the most used first
+pub const TOP_TAG_VALUES: i64 = 50;
+/// Weeks covered by `added_per_week`, the current one included
+pub const RECENT_WEEKS: i64 = 12;
+
+const WEEK: i64 = 7 * 24 * 3600;
+/// 1970-01-05, weeks start on mondays
+const FIRST_MONDAY: i64 = 4 * 24 * 3600;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TempoBin {
+    /// Lowest BPM of the range, `TEMPO_BIN_WIDTH` wide
+    pub start: f64,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeekCount {
+    /// Unix timestamp of the monday starting the week (UTC)
+    pub week: i64,
+    pub count: i64,
+}
+
+/// Only the entries having a file are counted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryStatistics {
+    pub tracks: i64,
+    /// Seconds
+    pub total_duration: f64,
+    /// Tracks whose duration is not known, not part of `total_duration`
+    pub unknown_duration: i64,
+    /// Tracks with a beat grid
+    pub analysed: i64,
+    pub unanalysed: i64,
+    pub genres: Vec<ValueCount>,
+    pub artists: Vec<ValueCount>,
+    pub labels: Vec<ValueCount>,
+    pub keys: Vec<ValueCount>,
+    pub tempo_histogram: Vec<TempoBin>,
+    /// Weeks without new track are left out
+    pub added_per_week: Vec<WeekCount>,
+}
+
+impl LibraryStatistics {
+    pub async fn compute(database: &Database) -> Result<Self> {
+        let (tracks, total_duration, unknown_duration, analysed) = database.get_entry_totals().await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let current_week = now - (now - FIRST_MONDAY).rem_euclid(WEEK);
+        let since = current_week - (RECENT_WEEKS - 1) * WEEK;
+        Ok(Self {
+            tracks,
+            total_duration,
+            unknown_duration,
+            analysed,
+            unanalysed: tracks - analysed,
+            genres: value_counts(database, TagName::GENRE_ID).await?,
+            artists: value_counts(database, TagName::ARTIST_ID).await?,
+            labels: value_counts(database, TagName::LABEL_ID).await?,
+            keys: value_counts(database, TagName::KEY_ID).await?,
+            tempo_histogram: database
+                .get_tempo_histogram(TEMPO_BIN_WIDTH)
+                .await?
+                .into_iter()
+                .map(|(start, count)| TempoBin { start, count })
+                .collect(),
+            added_per_week: database
+                .get_created_per_week(since)
+                .await?
+                .into_iter()
+                .map(|(week, count)| WeekCount { week, count })
+                .collect(),
+        })
+    }
+}
+
+async fn value_counts(database: &Database, tag_name_id: i64) -> Result<Vec<ValueCount>> {
+    let counts = database.get_tag_value_counts(tag_name_id, TOP_TAG_VALUES).await?;
+    Ok(counts
+        .into_iter()
+        .map(|(value, count)| ValueCount { value, count })
+        .collect())
+}
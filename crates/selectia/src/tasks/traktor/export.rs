@@ -0,0 +1,225 @@
+use super::nml::{
+    musical_key_value, Album, Collection, CueV2, Entry, Grid, Head, Info, Location, MusicalKey,
+    Nml, Node, Playlist, PlaylistEntry, Playlists, PrimaryKey, Subnodes, Tempo, COLORS,
+};
+use crate::prelude::*;
+use crate::tasks::reconciliation::stars_to_rating;
+use crate::tasks::selection::{ExportSelection, PlaylistNode};
+use models::{Cue, CuePoint, TempoMarker};
+
+pub struct TraktorExport {
+    database: Database,
+    selection: ExportSelection,
+}
+
+impl TraktorExport {
+    pub fn new(database: Database, selection: ExportSelection) -> Self {
+        Self {
+            database,
+            selection,
+        }
+    }
+
+    pub async fn build(&self) -> Result<Nml> {
+        let mut entries = vec![];
+        let mut primary_keys = HashMap::new();
+        for metadata_id in self.selection.metadata_ids(&self.database).await? {
+            let entry = self.entry(metadata_id).await?;
+            primary_keys.insert(metadata_id, entry.location.primary_key());
+            entries.push(entry);
+        }
+
+        let roots = self
+            .selection
+            .playlist_tree(&self.database)
+            .await?
+            .iter()
+            .map(|playlist| playlist_node(playlist, &primary_keys))
+            .collect::<Vec<_>>();
+
+        Ok(Nml {
+            version: "19".to_string(),
+            head: Head {
+                company: "www.native-instruments.com".to_string(),
+                program: "Traktor".to_string(),
+            },
+            collection: Collection {
+                entries: entries.len(),
+                entries_list: entries,
+            },
+            playlists: Playlists {
+                nodes: vec![Node {
+                    kind: Node::FOLDER.to_string(),
+                    name: "$ROOT".to_string(),
+                    subnodes: Some(Subnodes {
+                        count: roots.len(),
+                        nodes: roots,
+                    }),
+                    playlist: None,
+                }],
+            },
+        })
+    }
+
+    pub async fn write(&self, path: &Path) -> Result<()> {
+        let nml = self.build().await?.to_xml()?;
+        fs::write(path, nml).await?;
+        Ok(())
+    }
+
+    async fn entry(&self, metadata_id: i64) -> Result<Entry> {
+        let file = self.database.get_file_from_metadata_id(metadata_id).await?;
+        let entry = self.database.get_entry_by_metadata_id(metadata_id).await?;
+        let tag = |tag_name_id: i64| entry.tag_value(tag_name_id);
+
+        // Grid markers are cues in Traktor
+        let mut cues = self
+            .database
+            .get_beat_grid(metadata_id)
+            .await?
+            .into_iter()
+            .map(|marker| grid_cue(marker.into()))
+            .collect::<Vec<_>>();
+        cues.extend(
+            self.database
+                .get_cues(metadata_id)
+                .await?
+                .into_iter()
+                .map(|cue| cue_v2(cue.into())),
+        );
+
+        let key = tag(TagName::KEY_ID);
+        Ok(Entry {
+            title: tag(TagName::TITLE_ID),
+            artist: tag(TagName::ARTIST_ID),
+            location: Location::from_path(Path::new(&file.path)),
+            album: tag(TagName::ALBUM_ID).map(|title| Album {
+                track: tag(TagName::TRACK_ID),
+                title: Some(title),
+            }),
+            info: Some(Info {
+                genre: tag(TagName::GENRE_ID),
+                label: tag(TagName::LABEL_ID),
+                comment: tag(TagName::COMMENT_ID),
+                remixer: tag(TagName::REMIX_ID),
+                key: key.clone(),
+                ranking: tag(TagName::RATING_ID).and_then(|stars| stars_to_rating(&stars)),
+                color: tag(TagName::COLOR_ID).and_then(|color| color_index(&color)),
+            }),
+            tempo: tag(TagName::TEMPO_ID)
+                .and_then(|bpm| bpm.parse::<f64>().ok())
+                .map(|bpm| Tempo {
+                    bpm: format!("{:.6}", bpm),
+                    bpm_quality: None,
+                }),
+            musical_key: key
+                .as_deref()
+                .and_then(musical_key_value)
+                .map(|value| MusicalKey {
+                    value: value.to_string(),
+                }),
+            cues,
+        })
+    }
+}
+
+fn playlist_node(playlist: &PlaylistNode, primary_keys: &HashMap<i64, String>) -> Node {
+    if playlist.is_folder {
+        let nodes = playlist
+            .children
+            .iter()
+            .map(|child| playlist_node(child, primary_keys))
+            .collect::<Vec<_>>();
+        return Node {
+            kind: Node::FOLDER.to_string(),
+            name: playlist.name.clone(),
+            subnodes: Some(Subnodes {
+                count: nodes.len(),
+                nodes,
+            }),
+            playlist: None,
+        };
+    }
+    let entries = playlist
+        .metadata_ids
+        .iter()
+        .filter_map(|metadata_id| primary_keys.get(metadata_id))
+        .map(|key| PlaylistEntry {
+            primary_key: PrimaryKey {
+                kind: "TRACK".to_string(),
+                key: key.clone(),
+            },
+        })
+        .collect::<Vec<_>>();
+    Node {
+        kind: Node::PLAYLIST.to_string(),
+        name: playlist.name.clone(),
+        subnodes: None,
+        playlist: Some(Playlist {
+            entries: entries.len(),
+            kind: "LIST".to_string(),
+            uuid: None,
+            entries_list: entries,
+        }),
+    }
+}
+
+fn cue_v2(cue: CuePoint) -> CueV2 {
+    let kind = match cue.kind.as_str() {
+        Cue::FADE_IN_KIND => CueV2::FADE_IN_TYPE,
+        Cue::FADE_OUT_KIND => CueV2::FADE_OUT_TYPE,
+        Cue::LOAD_KIND => CueV2::LOAD_TYPE,
+        Cue::LOOP_KIND => CueV2::LOOP_TYPE,
+        _ => CueV2::CUE_TYPE,
+    };
+    let len = cue
+        .end_time
+        .map(|end| (end - cue.start_time) * 1000.0)
+        .unwrap_or(0.0);
+    CueV2 {
+        name: cue.name.unwrap_or_else(|| "n.n.".to_string()),
+        displ_order: "0".to_string(),
+        kind: kind.to_string(),
+        start: format!("{:.6}", cue.start_time * 1000.0),
+        len: format!("{:.6}", len),
+        repeats: "-1".to_string(),
+        hotcue: cue.hot_cue_index.unwrap_or(-1).to_string(),
+        grid: None,
+    }
+}
+
+fn grid_cue(marker: TempoMarker) -> CueV2 {
+    CueV2 {
+        name: "AutoGrid".to_string(),
+        displ_order: "0".to_string(),
+        kind: CueV2::GRID_TYPE.to_string(),
+        start: format!("{:.6}", marker.position * 1000.0),
+        len: format!("{:.6}", 0.0),
+        repeats: "-1".to_string(),
+        hotcue: "-1".to_string(),
+        grid: Some(Grid {
+            bpm: format!("{:.6}", marker.bpm),
+        }),
+    }
+}
+
+/// Traktor only has a palette of 7 colors, the closest one is picked
+fn color_index(color: &str) -> Option<String> {
+    let rgb = |color: &str| {
+        let value = u32::from_str_radix(color.trim_start_matches('#'), 16).ok()?;
+        Some([(value >> 16) & 0xff, (value >> 8) & 0xff, value & 0xff].map(|c| c as i64))
+    };
+    let color = rgb(color)?;
+    let (index, _) = COLORS
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let candidate = rgb(candidate)?;
+            let distance = (0..3)
+                .map(|c| (candidate[c] - color[c]).pow(2))
+                .sum::<i64>();
+            Some((index, distance))
+        })
+        .min_by_key(|(_, distance)| *distance)?;
+    Some((index + 1).to_string())
+}
@@ -0,0 +1,208 @@
+use super::nml::{CueV2, Entry, Nml, Node, COLORS, MUSICAL_KEYS};
+use super::TRAKTOR_SOURCE;
+use crate::prelude::*;
+use crate::tasks::reconciliation::*;
+use models::{Cue, CuePoint, TempoMarker};
+
+pub struct TraktorImport {
+    database: Database,
+    file_loader: AddressableService<FileLoaderTask>,
+    collection: Nml,
+}
+
+impl TraktorImport {
+    pub fn new(
+        database: Database,
+        file_loader: AddressableService<FileLoaderTask>,
+        nml: &str,
+    ) -> Result<Self> {
+        Ok(Self {
+            database,
+            file_loader,
+            collection: Nml::parse(nml)?,
+        })
+    }
+
+    pub async fn from_file(
+        database: Database,
+        file_loader: AddressableService<FileLoaderTask>,
+        path: &Path,
+    ) -> Result<Self> {
+        let nml = fs::read_to_string(path).await?;
+        Self::new(database, file_loader, &nml)
+    }
+
+    /// Import the entries of the collection then the playlist tree
+    pub async fn run(&self) -> Result<ImportReport> {
+        let mut report = ImportReport::default();
+        let mut metadata_ids = HashMap::new();
+        for entry in self.collection.collection.entries_list.iter() {
+            let reconciliation = self.import_entry(entry).await;
+            if let Some(metadata_id) = reconciliation.metadata_id {
+                metadata_ids.insert(entry.location.primary_key(), metadata_id);
+            }
+            report.tracks.push(reconciliation);
+        }
+
+        // The tree has a single `$ROOT` folder which is not imported
+        let playlists = self
+            .collection
+            .playlists
+            .nodes
+            .iter()
+            .flat_map(|root| root.subnodes.iter().flat_map(|subnodes| subnodes.nodes.iter()))
+            .map(imported_playlist)
+            .collect::<Vec<_>>();
+        report.playlists =
+            import_playlists(&self.database, &playlists, &metadata_ids, TRAKTOR_SOURCE).await?;
+        Ok(report)
+    }
+
+    async fn import_entry(&self, entry: &Entry) -> TrackReconciliation {
+        let path = entry.location.to_path();
+        let mut reconciliation = TrackReconciliation::new(entry.location.primary_key(), &path);
+        if let Err(e) = self.reconcile_entry(entry, &path, &mut reconciliation).await {
+            warn!(path = ?path, error = ?e, "Error importing Traktor entry");
+            reconciliation.status = ReconciliationStatus::Failed;
+            reconciliation.error = Some(e.to_string());
+        }
+        reconciliation
+    }
+
+    async fn reconcile_entry(
+        &self,
+        entry: &Entry,
+        path: &Path,
+        reconciliation: &mut TrackReconciliation,
+    ) -> Result<()> {
+        let (status, metadata_id) = resolve_file(&self.database, &self.file_loader, path).await?;
+        reconciliation.status = status;
+        reconciliation.metadata_id = metadata_id;
+        let Some(metadata_id) = metadata_id else {
+            return Ok(());
+        };
+
+        merge_tags(&self.database, reconciliation, entry_tags(entry), TRAKTOR_SOURCE).await?;
+
+        let cues = entry
+            .cues
+            .iter()
+            .filter_map(cue_point)
+            .collect::<Vec<_>>();
+        self.database
+            .replace_cues(metadata_id, TRAKTOR_SOURCE, &cues)
+            .await?;
+        reconciliation.cues = cues.len();
+
+        let markers = entry
+            .cues
+            .iter()
+            .filter_map(tempo_marker)
+            .collect::<Vec<_>>();
+        self.database
+            .replace_beat_grid(metadata_id, TRAKTOR_SOURCE, &markers)
+            .await?;
+        reconciliation.beat_grid_markers = markers.len();
+        Ok(())
+    }
+}
+
+fn imported_playlist(node: &Node) -> ImportedPlaylist {
+    ImportedPlaylist {
+        name: node.name.clone(),
+        is_folder: node.kind == Node::FOLDER,
+        children: node
+            .subnodes
+            .iter()
+            .flat_map(|subnodes| subnodes.nodes.iter())
+            .map(imported_playlist)
+            .collect(),
+        keys: node
+            .playlist
+            .iter()
+            .flat_map(|playlist| playlist.entries_list.iter())
+            .map(|entry| entry.primary_key.key.clone())
+            .collect(),
+    }
+}
+
+fn entry_tags(entry: &Entry) -> Vec<(i64, String)> {
+    let mut tags = vec![];
+    let mut text = |tag_name_id: i64, value: Option<&String>| {
+        if let Some(value) = value {
+            tags.push((tag_name_id, value.clone()));
+        }
+    };
+    let info = entry.info.as_ref();
+    text(TagName::TITLE_ID, entry.title.as_ref());
+    text(TagName::ARTIST_ID, entry.artist.as_ref());
+    text(TagName::ALBUM_ID, entry.album.as_ref().and_then(|album| album.title.as_ref()));
+    text(TagName::GENRE_ID, info.and_then(|info| info.genre.as_ref()));
+    text(TagName::LABEL_ID, info.and_then(|info| info.label.as_ref()));
+    text(TagName::REMIX_ID, info.and_then(|info| info.remixer.as_ref()));
+    text(TagName::COMMENT_ID, info.and_then(|info| info.comment.as_ref()));
+
+    let key = info.and_then(|info| info.key.clone()).or_else(|| {
+        let value = entry.musical_key.as_ref()?.value.parse::<usize>().ok()?;
+        MUSICAL_KEYS.get(value).map(|key| key.to_string())
+    });
+    if let Some(key) = key {
+        tags.push((TagName::KEY_ID, key));
+    }
+    if let Some(bpm) = entry.tempo.as_ref().and_then(|tempo| parse_number(&tempo.bpm)) {
+        if bpm > 0.0 {
+            tags.push((TagName::TEMPO_ID, format_bpm(bpm)));
+        }
+    }
+    if let Some(stars) = info.and_then(|info| info.ranking.as_deref()).and_then(rating_to_stars) {
+        tags.push((TagName::RATING_ID, stars));
+    }
+    let color = info
+        .and_then(|info| info.color.as_ref())
+        .and_then(|color| color.parse::<usize>().ok())
+        .and_then(|color| COLORS.get(color.checked_sub(1)?));
+    if let Some(color) = color {
+        tags.push((TagName::COLOR_ID, color.to_string()));
+    }
+    tags
+}
+
+fn cue_point(cue: &CueV2) -> Option<CuePoint> {
+    let kind = match cue.kind.as_str() {
+        CueV2::CUE_TYPE => Cue::CUE_KIND,
+        CueV2::FADE_IN_TYPE => Cue::FADE_IN_KIND,
+        CueV2::FADE_OUT_TYPE => Cue::FADE_OUT_KIND,
+        CueV2::LOAD_TYPE => Cue::LOAD_KIND,
+        CueV2::LOOP_TYPE => Cue::LOOP_KIND,
+        CueV2::GRID_TYPE => return None,
+        kind => {
+            warn!(kind, "Unknown Traktor cue type");
+            return None;
+        }
+    };
+    let start_time = parse_number(&cue.start)? / 1000.0;
+    let end_time = match kind {
+        Cue::LOOP_KIND => Some(start_time + parse_number(&cue.len)? / 1000.0),
+        _ => None,
+    };
+    Some(CuePoint {
+        kind: kind.to_string(),
+        hot_cue_index: cue.hotcue.parse::<i64>().ok().filter(|hotcue| *hotcue >= 0),
+        start_time,
+        end_time,
+        name: Some(cue.name.clone()).filter(|name| !name.is_empty() && name != "n.n."),
+        color: None,
+    })
+}
+
+fn tempo_marker(cue: &CueV2) -> Option<TempoMarker> {
+    if cue.kind != CueV2::GRID_TYPE {
+        return None;
+    }
+    Some(TempoMarker {
+        position: parse_number(&cue.start)? / 1000.0,
+        bpm: parse_number(&cue.grid.as_ref()?.bpm)?,
+        meter: None,
+        beat: 1,
+    })
+}
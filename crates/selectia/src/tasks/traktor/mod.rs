@@ -0,0 +1,10 @@
+//! Interop with Traktor collections (`collection.nml`)
+mod export;
+mod import;
+pub mod nml;
+
+pub use export::*;
+pub use import::*;
+
+/// Value of the `source` columns for data coming from Traktor
+pub const TRAKTOR_SOURCE: &str = "traktor";
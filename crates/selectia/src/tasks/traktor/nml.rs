@@ -0,0 +1,297 @@
+//! Serde model of the Traktor `collection.nml` format
+//!
+//! Only the nodes mapped to Selectia data are modeled, the other ones are skipped when reading
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename = "NML")]
+pub struct Nml {
+    #[serde(rename = "@VERSION")]
+    pub version: String,
+    #[serde(rename = "HEAD")]
+    pub head: Head,
+    #[serde(rename = "COLLECTION")]
+    pub collection: Collection,
+    #[serde(rename = "PLAYLISTS", default)]
+    pub playlists: Playlists,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Head {
+    #[serde(rename = "@COMPANY", default)]
+    pub company: String,
+    #[serde(rename = "@PROGRAM", default)]
+    pub program: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Collection {
+    #[serde(rename = "@ENTRIES")]
+    pub entries: usize,
+    #[serde(rename = "ENTRY", default)]
+    pub entries_list: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(rename = "@TITLE", default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(rename = "@ARTIST", default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(rename = "LOCATION")]
+    pub location: Location,
+    #[serde(rename = "ALBUM", default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<Album>,
+    #[serde(rename = "INFO", default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<Info>,
+    #[serde(rename = "TEMPO", default, skip_serializing_if = "Option::is_none")]
+    pub tempo: Option<Tempo>,
+    #[serde(rename = "MUSICAL_KEY", default, skip_serializing_if = "Option::is_none")]
+    pub musical_key: Option<MusicalKey>,
+    #[serde(rename = "CUE_V2", default)]
+    pub cues: Vec<CueV2>,
+}
+
+/// `DIR` uses `/:` as separator (i.e `/:Users/:dj/:Music/:`), `VOLUME` is the drive letter on Windows
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(rename = "@DIR")]
+    pub dir: String,
+    #[serde(rename = "@FILE")]
+    pub file: String,
+    #[serde(rename = "@VOLUME", default)]
+    pub volume: String,
+    #[serde(rename = "@VOLUMEID", default, skip_serializing_if = "Option::is_none")]
+    pub volume_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Album {
+    #[serde(rename = "@TRACK", default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<String>,
+    #[serde(rename = "@TITLE", default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Info {
+    #[serde(rename = "@GENRE", default, skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    #[serde(rename = "@LABEL", default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(rename = "@COMMENT", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "@REMIXER", default, skip_serializing_if = "Option::is_none")]
+    pub remixer: Option<String>,
+    /// Key as displayed by Traktor (i.e `8A` or `Am` depending on the settings)
+    #[serde(rename = "@KEY", default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// 0 to 255 by steps of 51 (i.e 0 to 5 stars)
+    #[serde(rename = "@RANKING", default, skip_serializing_if = "Option::is_none")]
+    pub ranking: Option<String>,
+    /// Index in the Traktor palette, see `COLORS`
+    #[serde(rename = "@COLOR", default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tempo {
+    #[serde(rename = "@BPM")]
+    pub bpm: String,
+    #[serde(rename = "@BPM_QUALITY", default, skip_serializing_if = "Option::is_none")]
+    pub bpm_quality: Option<String>,
+}
+
+/// Index in `MUSICAL_KEYS`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MusicalKey {
+    #[serde(rename = "@VALUE")]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CueV2 {
+    #[serde(rename = "@NAME", default)]
+    pub name: String,
+    #[serde(rename = "@DISPL_ORDER", default)]
+    pub displ_order: String,
+    /// See the `CueV2::*_TYPE` constants
+    #[serde(rename = "@TYPE")]
+    pub kind: String,
+    /// Position in milliseconds
+    #[serde(rename = "@START")]
+    pub start: String,
+    /// Length of loops in milliseconds
+    #[serde(rename = "@LEN", default)]
+    pub len: String,
+    #[serde(rename = "@REPEATS", default)]
+    pub repeats: String,
+    /// Hot cue slot, -1 for memory cues
+    #[serde(rename = "@HOTCUE", default)]
+    pub hotcue: String,
+    /// Set on grid markers
+    #[serde(rename = "GRID", default, skip_serializing_if = "Option::is_none")]
+    pub grid: Option<Grid>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Grid {
+    #[serde(rename = "@BPM")]
+    pub bpm: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Playlists {
+    #[serde(rename = "NODE", default)]
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Node {
+    /// `Node::FOLDER` or `Node::PLAYLIST`
+    #[serde(rename = "@TYPE")]
+    pub kind: String,
+    #[serde(rename = "@NAME")]
+    pub name: String,
+    #[serde(rename = "SUBNODES", default, skip_serializing_if = "Option::is_none")]
+    pub subnodes: Option<Subnodes>,
+    #[serde(rename = "PLAYLIST", default, skip_serializing_if = "Option::is_none")]
+    pub playlist: Option<Playlist>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Subnodes {
+    #[serde(rename = "@COUNT")]
+    pub count: usize,
+    #[serde(rename = "NODE", default)]
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Playlist {
+    #[serde(rename = "@ENTRIES")]
+    pub entries: usize,
+    #[serde(rename = "@TYPE")]
+    pub kind: String,
+    #[serde(rename = "@UUID", default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    #[serde(rename = "ENTRY", default)]
+    pub entries_list: Vec<PlaylistEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    #[serde(rename = "PRIMARYKEY")]
+    pub primary_key: PrimaryKey,
+}
+
+/// `KEY` is the location of the track: volume, dir and file concatenated
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PrimaryKey {
+    #[serde(rename = "@TYPE")]
+    pub kind: String,
+    #[serde(rename = "@KEY")]
+    pub key: String,
+}
+
+impl Node {
+    pub const FOLDER: &str = "FOLDER";
+    pub const PLAYLIST: &str = "PLAYLIST";
+}
+
+impl CueV2 {
+    pub const CUE_TYPE: &str = "0";
+    pub const FADE_IN_TYPE: &str = "1";
+    pub const FADE_OUT_TYPE: &str = "2";
+    pub const LOAD_TYPE: &str = "3";
+    pub const GRID_TYPE: &str = "4";
+    pub const LOOP_TYPE: &str = "5";
+}
+
+/// Keys by `MUSICAL_KEY` value, majors then minors
+pub const MUSICAL_KEYS: [&str; 24] = [
+    "C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B", "Cm", "Dbm", "Dm", "Ebm",
+    "Em", "Fm", "F#m", "Gm", "Abm", "Am", "Bbm", "Bm",
+];
+
+/// Open key notation of `MUSICAL_KEYS` as displayed by Traktor
+pub const OPEN_KEYS: [&str; 24] = [
+    "1d", "8d", "3d", "10d", "5d", "12d", "7d", "2d", "9d", "4d", "11d", "6d", "10m", "5m", "12m",
+    "7m", "2m", "9m", "4m", "11m", "6m", "1m", "8m", "3m",
+];
+
+/// `MUSICAL_KEY` value of a key written as a name (`Am`), in open key (`1m`) or camelot (`8A`) notation
+pub fn musical_key_value(key: &str) -> Option<usize> {
+    let key = key.trim();
+    if let Some(value) = MUSICAL_KEYS.iter().position(|name| *name == key) {
+        return Some(value);
+    }
+    let key = key.to_lowercase();
+    let open_key = match key.strip_suffix('a').or_else(|| key.strip_suffix('b')) {
+        Some(number) => {
+            let number = number.parse::<usize>().ok().filter(|n| (1..=12).contains(n))?;
+            let mode = if key.ends_with('a') { 'm' } else { 'd' };
+            format!("{}{}", (number + 4) % 12 + 1, mode)
+        }
+        None => key,
+    };
+    OPEN_KEYS.iter().position(|name| *name == open_key)
+}
+
+/// Colors of the Traktor palette by `COLOR` value (red, orange, yellow, green, blue, violet, magenta)
+pub const COLORS: [&str; 7] = [
+    "#ff0000", "#ff8000", "#ffff00", "#00ff00", "#0000ff", "#8000ff", "#ff00ff",
+];
+
+impl Nml {
+    pub fn parse(xml: &str) -> Result<Self> {
+        Ok(quick_xml::de::from_str(xml)?)
+    }
+
+    pub fn to_xml(&self) -> Result<String> {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\" ?>\n");
+        let mut serializer = quick_xml::se::Serializer::with_root(&mut xml, Some("NML"))?;
+        serializer.indent(' ', 2);
+        self.serialize(serializer)?;
+        xml.push('\n');
+        Ok(xml)
+    }
+}
+
+impl Location {
+    pub fn from_path(path: &Path) -> Self {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let (volume, path) = match path.split_once(':') {
+            Some((drive, rest)) if drive.len() == 1 => (format!("{}:", drive), rest.to_string()),
+            _ => (String::new(), path),
+        };
+        let (dir, file) = path.rsplit_once('/').unwrap_or(("", &path));
+        let dir = dir
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .map(|component| format!("/:{}", component))
+            .collect::<String>();
+        Self {
+            dir: format!("{}/:", dir),
+            file: file.to_string(),
+            volume,
+            volume_id: None,
+        }
+    }
+
+    /// Volumes other than drive letters are ignored, paths are then taken from the root of the file system
+    pub fn to_path(&self) -> PathBuf {
+        let dir = self.dir.replace("/:", "/");
+        let volume = if self.volume.len() == 2 && self.volume.ends_with(':') {
+            self.volume.as_str()
+        } else {
+            ""
+        };
+        PathBuf::from(format!("{}{}{}", volume, dir, self.file))
+    }
+
+    /// Key used by playlists to reference the entry
+    pub fn primary_key(&self) -> String {
+        format!("{}{}{}", self.volume, self.dir, self.file)
+    }
+}
@@ -0,0 +1,150 @@
+//! Hash the files of the library again to find the ones whose content changed under the same path
+use eyre::bail;
+
+use crate::prelude::*;
+use crate::services::file_loader::{hash_file, HashStrategy};
+use crate::services::worker::tasks::TaskProgress;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifiedFileStatus {
+    Unchanged,
+    /// The file no longer resolves to its entry, it is ingested again on the next import
+    Changed,
+    Missing,
+    /// The file could not be read or decoded
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifiedFile {
+    pub file_id: i64,
+    pub metadata_id: i64,
+    pub path: PathBuf,
+    pub status: VerifiedFileStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub files: Vec<VerifiedFile>,
+}
+
+impl VerifyReport {
+    pub fn count(&self, status: VerifiedFileStatus) -> usize {
+        self.files.iter().filter(|file| file.status == status).count()
+    }
+}
+
+impl VerifiedFileStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerifiedFileStatus::Unchanged => "unchanged",
+            VerifiedFileStatus::Changed => "changed",
+            VerifiedFileStatus::Missing => "missing",
+            VerifiedFileStatus::Failed => "failed",
+        }
+    }
+}
+
+impl TryFrom<&str> for VerifiedFileStatus {
+    type Error = eyre::Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value {
+            "unchanged" => Ok(VerifiedFileStatus::Unchanged),
+            "changed" => Ok(VerifiedFileStatus::Changed),
+            "missing" => Ok(VerifiedFileStatus::Missing),
+            "failed" => Ok(VerifiedFileStatus::Failed),
+            _ => bail!("Invalid verified file status: {}", value),
+        }
+    }
+}
+
+pub struct LibraryVerification {
+    database: Database,
+}
+
+impl LibraryVerification {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Every file is hashed with the strategy of its pin or of its entry, the outcome replaces the previous one in the database
+    pub async fn run(&self, progress: Option<&TaskProgress>) -> Result<VerifyReport> {
+        let files = self.database.list_files().await?;
+        let mut report = VerifyReport::default();
+        for (idx, file) in files.iter().enumerate() {
+            if let Some(progress) = progress {
+                progress
+                    .report("verify", idx as f64 / files.len() as f64)
+                    .await?;
+            }
+            let (status, error) = match self.verify_file(file).await {
+                Ok(status) => (status, None),
+                Err(e) => {
+                    warn!(path = file.path, error = ?e, "Error verifying file");
+                    (VerifiedFileStatus::Failed, Some(e.to_string()))
+                }
+            };
+            report.files.push(VerifiedFile {
+                file_id: file.id,
+                metadata_id: file.metadata_id,
+                path: PathBuf::from(&file.path),
+                status,
+                error,
+            });
+        }
+        if let Some(progress) = progress {
+            progress.report("verify", 1.0).await?;
+        }
+
+        let verifications = report
+            .files
+            .iter()
+            .map(|file| models::FileVerification {
+                file_id: file.file_id,
+                status: file.status.as_str().to_string(),
+                error: file.error.clone(),
+            })
+            .collect::<Vec<_>>();
+        self.database.replace_file_verifications(&verifications).await?;
+        Ok(report)
+    }
+
+    async fn verify_file(&self, file: &models::File) -> Result<VerifiedFileStatus> {
+        let path = Path::new(&file.path);
+        if !fs::try_exists(path).await? {
+            return Ok(VerifiedFileStatus::Missing);
+        }
+        let known_hash = match self.database.get_file_pin_hash(path).await? {
+            Some(hash) => hash,
+            None => self.database.get_metadata(file.metadata_id).await?.hash,
+        };
+        let hash = hash_file(path, HashStrategy::of(&known_hash)).await?;
+        match self.database.find_metadata(path, &hash).await? {
+            Some(found) if found.id == file.metadata_id => Ok(VerifiedFileStatus::Unchanged),
+            _ => Ok(VerifiedFileStatus::Changed),
+        }
+    }
+}
+
+/// Outcome of the latest verification, the unchanged files left out
+pub async fn get_verification_report(database: &Database) -> Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    for verification in database.get_file_verifications().await? {
+        let status = VerifiedFileStatus::try_from(verification.status.as_str())?;
+        if status == VerifiedFileStatus::Unchanged {
+            continue;
+        }
+        let file = database.get_file(verification.file_id).await?;
+        report.files.push(VerifiedFile {
+            file_id: file.id,
+            metadata_id: file.metadata_id,
+            path: PathBuf::from(&file.path),
+            status,
+            error: verification.error,
+        });
+    }
+    Ok(report)
+}
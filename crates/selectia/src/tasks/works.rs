@@ -0,0 +1,158 @@
+//! Group the versions of a song (original, extended, radio edit, remixes ...) into works
+use std::collections::BTreeMap;
+
+use crate::prelude::*;
+
+/// Version of the titles without version suffix
+pub const ORIGINAL_VERSION: &str = "Original";
+/// Last word of the title suffixes naming a version, such as `(Extended Mix)` or `- Radio Edit`
+const VERSION_KEYWORDS: &[&str] = &[
+    "mix",
+    "edit",
+    "remix",
+    "dub",
+    "instrumental",
+    "vip",
+    "rework",
+    "bootleg",
+    "version",
+    "remaster",
+    "remastered",
+    "acapella",
+];
+/// Words introducing the featured artists, left out of the artist matching
+const FEATURING_KEYWORDS: &[&str] = &["feat.", "feat", "ft.", "ft", "featuring"];
+
+/// Entry with its title without version suffix and its version
+type VersionCandidate = (models::MetadataVersion, String, String);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkVersion {
+    pub metadata_id: i64,
+    pub version: String,
+}
+
+/// Entries sounding like versions of the same song
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkSuggestion {
+    /// Work some of the entries are already part of
+    pub work_id: Option<i64>,
+    pub artist: String,
+    /// Title without the version suffix
+    pub title: String,
+    pub versions: Vec<WorkVersion>,
+}
+
+/// Split the version suffix from the title, `("Song", Some("Extended Mix"))` for `Song (Extended Mix)`
+pub fn split_version(title: &str) -> (String, Option<String>) {
+    let title = title.trim();
+    let suffix = if let Some(stripped) = title.strip_suffix(')') {
+        stripped.rfind('(').map(|start| (start, &stripped[start + 1..]))
+    } else if let Some(stripped) = title.strip_suffix(']') {
+        stripped.rfind('[').map(|start| (start, &stripped[start + 1..]))
+    } else {
+        title.rfind(" - ").map(|start| (start, &title[start + 3..]))
+    };
+    match suffix {
+        Some((start, version)) if is_version(version) => (title[..start].trim().to_string(), Some(version.trim().to_string())),
+        _ => (title.to_string(), None),
+    }
+}
+
+fn is_version(version: &str) -> bool {
+    version
+        .split_whitespace()
+        .last()
+        .map(|word| VERSION_KEYWORDS.contains(&word.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Lowercase words of letters and digits
+pub fn normalise(value: &str) -> String {
+    value
+        .to_lowercase()
+        .replace('&', " and ")
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Main artist without the featured ones
+fn normalise_artist(artist: &str) -> String {
+    let words = artist
+        .split_whitespace()
+        .take_while(|word| !FEATURING_KEYWORDS.contains(&word.to_lowercase().trim_start_matches('(')))
+        .collect::<Vec<_>>();
+    normalise(&words.join(" "))
+}
+
+pub struct WorkGrouping {
+    database: Database,
+}
+
+impl WorkGrouping {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Entries sharing their artist and title once normalised, unless they already are grouped together
+    pub async fn suggest(&self) -> Result<Vec<WorkSuggestion>> {
+        let mut candidates: BTreeMap<(String, String), Vec<VersionCandidate>> = BTreeMap::new();
+        for entry in self.database.get_metadata_versions().await? {
+            let (Some(artist), Some(title)) = (entry.artist.clone(), entry.title.clone()) else {
+                continue;
+            };
+            let (base_title, version) = split_version(&title);
+            let key = (normalise_artist(&artist), normalise(&base_title));
+            if key.0.is_empty() || key.1.is_empty() {
+                continue;
+            }
+            let version = version.unwrap_or_else(|| ORIGINAL_VERSION.to_string());
+            candidates.entry(key).or_default().push((entry, base_title, version));
+        }
+
+        let suggestions = candidates
+            .into_values()
+            .filter(|entries| entries.len() > 1)
+            .filter(|entries| {
+                let work_id = entries[0].0.work_id;
+                work_id.is_none() || entries.iter().any(|(entry, _, _)| entry.work_id != work_id)
+            })
+            .map(|entries| WorkSuggestion {
+                work_id: entries.iter().find_map(|(entry, _, _)| entry.work_id),
+                artist: entries[0].0.artist.clone().unwrap_or_default(),
+                title: entries[0].1.clone(),
+                versions: entries
+                    .into_iter()
+                    .map(|(entry, _, version)| WorkVersion {
+                        metadata_id: entry.metadata_id,
+                        // A version label set by hand wins over the parsed one
+                        version: entry.version.unwrap_or(version),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(suggestions)
+    }
+
+    /// Create the work of the suggestion, or add the versions to the existing one, returns the work id
+    pub async fn group(&self, suggestion: &WorkSuggestion) -> Result<i64> {
+        if suggestion.versions.len() < 2 {
+            return Err(eyre!("A work needs at least two versions"));
+        }
+        let work_id = match suggestion.work_id {
+            Some(work_id) => work_id,
+            None => self.database.create_work(&suggestion.artist, &suggestion.title).await?.id,
+        };
+        let versions = suggestion
+            .versions
+            .iter()
+            .map(|version| (version.metadata_id, version.version.clone()))
+            .collect::<Vec<_>>();
+        self.database.set_work_versions(work_id, &versions).await?;
+        Ok(work_id)
+    }
+}
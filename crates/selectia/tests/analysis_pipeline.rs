@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use selectia::{
+    prelude::*,
+    tasks::analysis::{pending_analysis_stages, AnalysisPipelineSettings, AnalysisStage},
+    test_utils::TmpDatabase,
+};
+use selectia_audio_file::audio_file::AudioFilePayload;
+use tempdir::TempDir;
+
+/// An A minor chord with a click on each beat at 120 BPM
+fn track() -> AudioFilePayload<'static> {
+    const SAMPLE_RATE: f64 = 44100.0;
+    const DURATION: f64 = 20.0;
+    let mut samples = vec![];
+    for idx in 0..(DURATION * SAMPLE_RATE) as usize {
+        let time = idx as f64 / SAMPLE_RATE;
+        let chord = [220.0, 261.63, 329.63]
+            .iter()
+            .map(|frequency| (2.0 * std::f64::consts::PI * frequency * time).sin())
+            .sum::<f64>()
+            / 6.0;
+        let since_beat = time % 0.5;
+        let click = (2.0 * std::f64::consts::PI * 2000.0 * time).sin() * (-since_beat * 200.0).exp();
+        let value = (chord + 0.5 * click) as f32;
+        samples.push(value);
+        samples.push(value);
+    }
+    AudioFilePayload::from_interleaved_samples(SAMPLE_RATE, 2, samples).unwrap()
+}
+
+/// Ten seconds of silence
+fn silence() -> AudioFilePayload<'static> {
+    AudioFilePayload::from_interleaved_samples(44100.0, 2, vec![0.0; 44100 * 2 * 10]).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+pub async fn test_analysis_pipeline() {
+    let dir = TempDir::new("selectia-pipeline").unwrap();
+    let path = dir.path().join("track.wav");
+    track().wav_export(44100, &path).unwrap();
+
+    let theater = OwnedTheaterContext::new().await;
+    let database = TmpDatabase::new().await;
+    theater.register_singleton(database.clone()).await.unwrap();
+    let settings = AnalysisPipelineSettings::default();
+    theater.register_singleton(settings).await.unwrap();
+    StateMachine::spawn(&theater).await.unwrap();
+    let file_loader = FileLoader::spawn(&theater).await.unwrap();
+    Worker::spawn(&theater).await.unwrap();
+    let pipeline = Pipeline::spawn(&theater).await.unwrap();
+    theater.ready().await;
+
+    let (callback, resolve) = TaskCallback::new();
+    file_loader
+        .send(FileLoaderTask::LoadFile {
+            path: path.clone(),
+            callback: Some(callback),
+        })
+        .await
+        .unwrap();
+    let metadata_id = resolve.wait().await.unwrap();
+    let hash = database.get_metadata(metadata_id).await.unwrap().hash;
+
+    // Every stage but the stems runs once the file is ingested
+    let mut completed = vec![];
+    for _ in 0..600 {
+        completed = database.get_completed_analysis_stages(&hash).await.unwrap();
+        if completed.len() == 5 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    completed.sort();
+    assert_eq!(completed, vec!["grid", "key", "loudness", "metadata", "waveform"]);
+
+    let entry = database.get_entry_by_metadata_id(metadata_id).await.unwrap();
+    let tag = |tag_name_id: i64| {
+        entry
+            .tags
+            .0
+            .iter()
+            .find(|tag| tag.tag_name_id == tag_name_id)
+            .map(|tag| tag.tag_value.clone())
+    };
+    assert_eq!(tag(TagName::KEY_ID).as_deref(), Some("Am"));
+    assert!(tag(TagName::TEMPO_ID).is_some());
+    assert_eq!(database.get_beat_grid(metadata_id).await.unwrap().len(), 1);
+    let analysis = database.get_metadata_analysis(metadata_id).await.unwrap().unwrap();
+    assert_eq!(analysis.waveform.unwrap().len(), 1024);
+    assert!(analysis.loudness.unwrap() < 0.0);
+    assert!(analysis.peak.unwrap() <= 0.0);
+    assert!(analysis.grid_confidence.is_some());
+    assert!(analysis.key_confidence.unwrap() > 0.5);
+
+    // Nothing is scheduled again for the same content, only the stages enabled later are
+    let (callback, resolve) = TaskCallback::new();
+    pipeline
+        .send(PipelineTask::Analyse {
+            metadata_id,
+            callback: Some(callback),
+        })
+        .await
+        .unwrap();
+    assert!(resolve.wait().await.unwrap().is_empty());
+    let with_stems = AnalysisPipelineSettings {
+        stems: true,
+        ..settings
+    };
+    assert_eq!(
+        pending_analysis_stages(&database, metadata_id, &with_stems).await.unwrap(),
+        vec![AnalysisStage::Stems]
+    );
+    let disabled = AnalysisPipelineSettings {
+        enabled: false,
+        ..with_stems
+    };
+    assert!(pending_analysis_stages(&database, metadata_id, &disabled).await.unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+pub async fn test_analysis_stage_failure() {
+    let dir = TempDir::new("selectia-pipeline").unwrap();
+    let path = dir.path().join("silence.wav");
+    silence().wav_export(44100, &path).unwrap();
+
+    // Stems are enabled without a demuxer to extract them, their stage fails
+    let theater = OwnedTheaterContext::new().await;
+    let database = TmpDatabase::new().await;
+    theater.register_singleton(database.clone()).await.unwrap();
+    let settings = AnalysisPipelineSettings {
+        stems: true,
+        ..Default::default()
+    };
+    theater.register_singleton(settings).await.unwrap();
+    StateMachine::spawn(&theater).await.unwrap();
+    let file_loader = FileLoader::spawn(&theater).await.unwrap();
+    Worker::spawn(&theater).await.unwrap();
+    Pipeline::spawn(&theater).await.unwrap();
+    theater.ready().await;
+
+    let (callback, resolve) = TaskCallback::new();
+    file_loader
+        .send(FileLoaderTask::LoadFile {
+            path: path.clone(),
+            callback: Some(callback),
+        })
+        .await
+        .unwrap();
+    let metadata_id = resolve.wait().await.unwrap();
+    let hash = database.get_metadata(metadata_id).await.unwrap().hash;
+
+    // Nothing is detected in silence, the stages are done anyway and the failing one holds back none of them
+    let mut completed = vec![];
+    for _ in 0..600 {
+        completed = database.get_completed_analysis_stages(&hash).await.unwrap();
+        if completed.len() == 5 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    completed.sort();
+    assert_eq!(completed, vec!["grid", "key", "loudness", "metadata", "waveform"]);
+
+    let tasks = database.get_tasks().await.unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert!(tasks[0].payload.contains("stems"));
+    assert!(tasks[0].last_error.is_some());
+    assert!(database.get_task_dependencies(tasks[0].id).await.unwrap().is_empty());
+
+    let entry = database.get_entry_by_metadata_id(metadata_id).await.unwrap();
+    assert!(!entry.tags.0.iter().any(|tag| tag.tag_name_id == TagName::KEY_ID));
+    assert!(!entry.tags.0.iter().any(|tag| tag.tag_name_id == TagName::TEMPO_ID));
+    assert!(database.get_beat_grid(metadata_id).await.unwrap().is_empty());
+    let analysis = database.get_metadata_analysis(metadata_id).await.unwrap().unwrap();
+    assert!(analysis.waveform.unwrap().iter().all(|peak| *peak == 0));
+    assert!(analysis.loudness.is_none());
+    assert!(analysis.key_confidence.is_none());
+}
@@ -0,0 +1,157 @@
+//! Library fixture shared by the collection interop tests
+#![allow(dead_code)]
+use std::path::{Path, PathBuf};
+
+use selectia::{
+    database::{
+        models::{CuePoint, TempoMarker},
+        Database,
+    },
+    prelude::*,
+    test_utils::TmpDatabase,
+};
+use tempdir::TempDir;
+
+pub struct Library {
+    _dir: Option<TempDir>,
+    pub root: PathBuf,
+    _theater: OwnedTheaterContext,
+    pub database: TmpDatabase,
+    _covers_dir: TempDir,
+    pub covers: CoverCache,
+    pub file_loader: AddressableService<FileLoaderTask>,
+}
+
+impl Library {
+    /// A library over a temporary directory holding `files` (relative path, content)
+    pub async fn new(files: &[(&str, &[u8])]) -> Self {
+        let dir = TempDir::new("selectia-library").unwrap();
+        for (relative, content) in files {
+            let path = dir.path().join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let mut library = Self::open(dir.path().to_path_buf()).await;
+        library._dir = Some(dir);
+        library
+    }
+
+    /// A library with its own database over the files of an existing one
+    pub async fn open(root: PathBuf) -> Self {
+        let theater = OwnedTheaterContext::new().await;
+        let database = TmpDatabase::new().await;
+        theater.register_singleton(database.clone()).await.unwrap();
+        let covers_dir = TempDir::new("selectia-covers").unwrap();
+        let covers = CoverCache::new(covers_dir.path().to_path_buf());
+        theater.register_singleton(covers.clone()).await.unwrap();
+        let file_loader = FileLoader::spawn(&theater).await.unwrap();
+        theater.ready().await;
+        Self {
+            _dir: None,
+            root,
+            _theater: theater,
+            database,
+            _covers_dir: covers_dir,
+            covers,
+            file_loader,
+        }
+    }
+
+    pub fn path(&self, relative: &str) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    pub fn database(&self) -> Database {
+        (*self.database).clone()
+    }
+
+    /// The files are hashed with the sampled strategy until it is set, it can only be set once
+    pub async fn set_hash_strategy(&self, strategy: HashStrategy) {
+        self._theater.register_singleton(strategy).await.unwrap();
+    }
+
+    pub async fn load(&self, path: &Path) -> i64 {
+        let (callback, resolve) = TaskCallback::new();
+        self.file_loader
+            .send(FileLoaderTask::LoadFile {
+                path: path.to_path_buf(),
+                callback: Some(callback),
+            })
+            .await
+            .unwrap();
+        resolve.wait().await.unwrap()
+    }
+
+    /// Everything the interop formats carry for the file at `relative`
+    pub async fn snapshot(&self, relative: &str) -> (Vec<(i64, Vec<String>)>, Vec<CuePoint>, Vec<TempoMarker>) {
+        let file = self
+            .database
+            .get_file_by_path(&self.path(relative))
+            .await
+            .unwrap()
+            .unwrap();
+        let mut tags = vec![];
+        for tag_name_id in [
+            TagName::TITLE_ID,
+            TagName::ARTIST_ID,
+            TagName::ALBUM_ID,
+            TagName::GENRE_ID,
+            TagName::TEMPO_ID,
+            TagName::LABEL_ID,
+            TagName::REMIX_ID,
+            TagName::KEY_ID,
+            TagName::RATING_ID,
+            TagName::COMMENT_ID,
+            TagName::COLOR_ID,
+            TagName::PLAYLIST_ID,
+        ] {
+            tags.push((tag_name_id, self.tag_values(file.metadata_id, tag_name_id).await));
+        }
+        let cues = self.database.get_cues(file.metadata_id).await.unwrap();
+        let grid = self.database.get_beat_grid(file.metadata_id).await.unwrap();
+        (
+            tags,
+            cues.into_iter().map(CuePoint::from).collect(),
+            grid.into_iter().map(TempoMarker::from).collect(),
+        )
+    }
+
+    /// Playlist tree as (path, entries path)
+    pub async fn playlist_tree(&self) -> Vec<(Vec<String>, Vec<String>)> {
+        let playlists = self.database.get_playlists().await.unwrap();
+        let mut tree = vec![];
+        for playlist in playlists.iter() {
+            let mut path = vec![playlist.name.clone()];
+            let mut parent_id = playlist.parent_id;
+            while let Some(parent) = parent_id.and_then(|id| playlists.iter().find(|p| p.id == id)) {
+                path.insert(0, parent.name.clone());
+                parent_id = parent.parent_id;
+            }
+            let mut entries = vec![];
+            for metadata_id in self.database.get_playlist_entries(playlist.id).await.unwrap() {
+                let file = self.database.get_file_from_metadata_id(metadata_id).await.unwrap();
+                entries.push(file.path);
+            }
+            tree.push((path, entries));
+        }
+        tree.sort();
+        tree
+    }
+
+    pub async fn tag_values(&self, metadata_id: i64, tag_name_id: i64) -> Vec<String> {
+        let entry = self
+            .database
+            .get_entry_by_metadata_id(metadata_id)
+            .await
+            .unwrap();
+        let mut values = entry
+            .tags
+            .0
+            .into_iter()
+            .filter(|tag| tag.tag_name_id == tag_name_id)
+            .map(|tag| tag.tag_value)
+            .collect::<Vec<_>>();
+        values.sort();
+        values
+    }
+}
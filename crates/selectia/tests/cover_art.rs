@@ -0,0 +1,125 @@
+use std::io::Cursor;
+
+use common::Library;
+use image::{ImageFormat, RgbImage};
+use selectia::tasks::cover_art::THUMBNAIL_SIZES;
+use selectia_audio_file::flac::write_flac;
+
+mod common;
+
+fn picture(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+    let image = RgbImage::from_fn(width, height, |x, y| image::Rgb([x as u8, y as u8, 128]));
+    let mut output = Cursor::new(vec![]);
+    image.write_to(&mut output, format).unwrap();
+    output.into_inner()
+}
+
+/// An ID3v2.3 tag holding a back cover then a front cover, followed by fake audio
+fn mp3_with_pictures(back: &[u8], front: &[u8]) -> Vec<u8> {
+    let mut frames = vec![];
+    for (picture_type, data) in [(4u8, back), (3u8, front)] {
+        let mut body = vec![0x00];
+        body.extend_from_slice(b"image/png\0");
+        body.push(picture_type);
+        body.extend_from_slice(b"Cover\0");
+        body.extend_from_slice(data);
+        frames.extend_from_slice(b"APIC");
+        frames.extend((body.len() as u32).to_be_bytes());
+        frames.extend([0x00, 0x00]);
+        frames.extend(body);
+    }
+    let size = frames.len() as u32;
+    let mut content = b"ID3\x03\x00\x00".to_vec();
+    content.extend([
+        (size >> 21) as u8 & 0x7F,
+        (size >> 14) as u8 & 0x7F,
+        (size >> 7) as u8 & 0x7F,
+        size as u8 & 0x7F,
+    ]);
+    content.extend(frames);
+    content.extend_from_slice(b"not really audio");
+    content
+}
+
+/// A short FLAC file with a front cover `PICTURE` metadata block
+fn flac_with_picture(data: &[u8]) -> Vec<u8> {
+    let mut flac = vec![];
+    write_flac(&mut flac, 44100, 1, &vec![0i16; 4096]).unwrap();
+    // The picture block becomes the last one
+    flac[4] &= 0x7F;
+    let mut body = vec![];
+    body.extend(3u32.to_be_bytes());
+    body.extend((b"image/png".len() as u32).to_be_bytes());
+    body.extend_from_slice(b"image/png");
+    body.extend(0u32.to_be_bytes());
+    for value in [32u32, 32, 24, 0, data.len() as u32] {
+        body.extend(value.to_be_bytes());
+    }
+    body.extend_from_slice(data);
+    let mut block = vec![0x86];
+    block.extend(&(body.len() as u32).to_be_bytes()[1..]);
+    block.extend(body);
+    flac.splice(42..42, block);
+    flac
+}
+
+#[tokio::test]
+pub async fn test_cover_art_embedded_id3() {
+    let back = picture(16, 16, ImageFormat::Png);
+    let front = picture(300, 200, ImageFormat::Png);
+    let library = Library::new(&[("a.mp3", &mp3_with_pictures(&back, &front))]).await;
+    let metadata_id = library.load(&library.path("a.mp3")).await;
+
+    let cover = library.database.get_cover(metadata_id).await.unwrap().unwrap();
+    assert_eq!((cover.mime_type.as_str(), cover.width, cover.height), ("image/png", 300, 200));
+    let entry = library.database.get_entry_by_metadata_id(metadata_id).await.unwrap();
+    assert_eq!(entry.cover_hash.as_deref(), Some(cover.hash.as_str()));
+
+    let stored = std::fs::read(library.covers.picture_path(&cover)).unwrap();
+    assert_eq!(stored, front);
+    for size in THUMBNAIL_SIZES.iter().copied() {
+        let thumbnail = image::open(library.covers.thumbnail_path(&cover.hash, size)).unwrap();
+        assert_eq!(thumbnail.width(), size);
+        assert!(thumbnail.height() < size);
+    }
+}
+
+#[tokio::test]
+pub async fn test_cover_art_embedded_flac() {
+    let front = picture(32, 32, ImageFormat::Png);
+    let library = Library::new(&[("a.flac", &flac_with_picture(&front))]).await;
+    let metadata_id = library.load(&library.path("a.flac")).await;
+
+    let cover = library.database.get_cover(metadata_id).await.unwrap().unwrap();
+    assert_eq!(std::fs::read(library.covers.picture_path(&cover)).unwrap(), front);
+    // Pictures smaller than the thumbnails are not upscaled
+    let thumbnail = image::open(library.covers.thumbnail_path(&cover.hash, 256)).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (32, 32));
+}
+
+#[tokio::test]
+pub async fn test_cover_art_deduplicated() {
+    let folder = picture(64, 64, ImageFormat::Jpeg);
+    let front = picture(48, 48, ImageFormat::Png);
+    let library = Library::new(&[
+        ("album/a.mp3", b"first"),
+        ("album/b.mp3", b"second"),
+        ("album/Folder.jpg", &folder),
+        ("other/c.mp3", &mp3_with_pictures(&front, &folder)),
+        ("loose/d.mp3", b"third"),
+    ])
+    .await;
+    let mut covers = vec![];
+    for relative in ["album/a.mp3", "album/b.mp3", "other/c.mp3", "loose/d.mp3"] {
+        let metadata_id = library.load(&library.path(relative)).await;
+        let cover = library.database.get_cover(metadata_id).await.unwrap();
+        covers.push(cover.map(|cover| (cover.id, cover.mime_type)));
+    }
+
+    let shared = Some((covers[0].as_ref().unwrap().0, "image/jpeg".to_string()));
+    assert_eq!(covers, vec![shared.clone(), shared.clone(), shared, None]);
+    let cached = std::fs::read_dir(library.covers.thumbnail_path("", 64).parent().unwrap())
+        .unwrap()
+        .count();
+    assert_eq!(cached, 1);
+}
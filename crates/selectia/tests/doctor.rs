@@ -0,0 +1,87 @@
+use common::Library;
+use selectia::{
+    database::models::{FileVariationMetadata, ImportRules},
+    prelude::*,
+    services::worker::tasks::{FingerprintTask, TaskPayload},
+    tasks::doctor::{Doctor, DoctorIssueKind},
+};
+
+mod common;
+
+#[tokio::test]
+pub async fn test_doctor() {
+    let library = Library::new(&[("kept.mp3", b"kept"), ("gone.mp3", b"gone"), ("kept.stems/vocals.wav", b"vocals")]).await;
+    let database = library.database();
+    let kept = library.load(&library.path("kept.mp3")).await;
+    library.load(&library.path("gone.mp3")).await;
+    std::fs::remove_file(library.path("gone.mp3")).unwrap();
+
+    let file = database.get_file_from_metadata_id(kept).await.unwrap();
+    for stem in ["vocals", "drums"] {
+        let path = library.path(&format!("kept.stems/{}.wav", stem));
+        let metadata = FileVariationMetadata {
+            title: stem.to_string(),
+            stem: Some(stem.to_string()),
+        };
+        database.create_file_variation(file.id, path.to_str().unwrap(), metadata).await.unwrap();
+    }
+
+    let payload = serde_json::to_string(&TaskPayload::Fingerprint(FingerprintTask { metadata_id: kept })).unwrap();
+    let stuck = database.create_task(payload.clone()).await.unwrap();
+    database.dequeue_task().await.unwrap();
+    let running = database.create_task(payload).await.unwrap();
+    database.dequeue_task().await.unwrap();
+    database.create_task("{\"Removed\":{}}".to_string()).await.unwrap();
+
+    database.get_or_create_tag(TagName::GENRE_ID, "Unused".to_string()).await.unwrap();
+    database.get_or_create_metadata("no file").await.unwrap();
+
+    let doctor = Doctor::new(database.clone()).with_running_tasks(vec![running]);
+    let report = doctor.check().await.unwrap();
+    for (kind, count) in [
+        (DoctorIssueKind::MissingFile, 1),
+        (DoctorIssueKind::MissingFileVariation, 1),
+        (DoctorIssueKind::StuckTask, 1),
+        (DoctorIssueKind::InvalidTask, 1),
+        (DoctorIssueKind::BrokenReference, 0),
+        (DoctorIssueKind::UnusedTag, 1),
+        (DoctorIssueKind::EntryWithoutFile, 1),
+    ] {
+        assert_eq!(report.count(kind), count, "{:?}\n{}", kind, report);
+    }
+
+    let report = doctor.fix(&report).await.unwrap();
+    assert_eq!(report.fixed, 4);
+    let kinds = report.issues.iter().map(|issue| issue.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![DoctorIssueKind::MissingFile, DoctorIssueKind::EntryWithoutFile]);
+    assert_eq!(database.get_file_variations(file.id).await.unwrap().len(), 1);
+    assert_eq!(database.get_task(stuck).await.unwrap().status, "queued");
+    assert_eq!(database.get_task(running).await.unwrap().status, "processing");
+}
+
+#[tokio::test]
+pub async fn test_doctor_offline_root() {
+    let library = Library::new(&[("drive/track.mp3", b"track"), ("drive/track.stems/vocals.wav", b"vocals")]).await;
+    let database = library.database();
+    database
+        .upsert_library_root(&library.path("drive"), &ImportRules::default())
+        .await
+        .unwrap();
+    let metadata_id = library.load(&library.path("drive/track.mp3")).await;
+    let file = database.get_file_from_metadata_id(metadata_id).await.unwrap();
+    let path = library.path("drive/track.stems/vocals.wav");
+    let metadata = FileVariationMetadata {
+        title: "vocals".to_string(),
+        stem: Some("vocals".to_string()),
+    };
+    database.create_file_variation(file.id, path.to_str().unwrap(), metadata).await.unwrap();
+
+    // The drive is unplugged, its stems are not deleted
+    std::fs::rename(library.path("drive"), library.path("unplugged")).unwrap();
+    let doctor = Doctor::new(database.clone());
+    let report = doctor.check().await.unwrap();
+    assert_eq!(report.count(DoctorIssueKind::MissingFile), 1, "{}", report);
+    assert_eq!(report.count(DoctorIssueKind::MissingFileVariation), 0, "{}", report);
+    doctor.fix(&report).await.unwrap();
+    assert_eq!(database.get_file_variations(file.id).await.unwrap().len(), 1);
+}
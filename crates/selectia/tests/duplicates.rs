@@ -0,0 +1,81 @@
+use common::Library;
+use selectia::{
+    prelude::*,
+    tasks::duplicates::{fingerprint_metadata, DEFAULT_MIN_SIMILARITY},
+};
+use selectia_audio_file::audio_file::AudioFilePayload;
+
+mod common;
+
+/// A stereo melody of one chord per half second, the chords being picked by `seed`
+fn melody(seed: u64, sample_rate: f64, gain: f32, leading_silence: f64) -> AudioFilePayload<'static> {
+    const DURATION: f64 = 30.0;
+    let mut state = seed;
+    let chords = (0..(DURATION * 2.0) as usize)
+        .map(|_| {
+            (0..3)
+                .map(|_| {
+                    state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    let note = 48 + (state >> 33) % 36;
+                    440.0 * 2f64.powf((note as f64 - 69.0) / 12.0)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut samples = vec![];
+    let silence = (leading_silence * sample_rate) as usize;
+    for idx in 0..(DURATION * sample_rate) as usize + silence {
+        let value = match idx.checked_sub(silence) {
+            Some(idx) => {
+                let time = idx as f64 / sample_rate;
+                let chord = &chords[((time * 2.0) as usize).min(chords.len() - 1)];
+                let value = chord
+                    .iter()
+                    .map(|frequency| (2.0 * std::f64::consts::PI * frequency * time).sin())
+                    .sum::<f64>();
+                gain * value as f32 / 4.0
+            }
+            None => 0.0,
+        };
+        samples.push(value);
+        samples.push(value);
+    }
+    AudioFilePayload::from_interleaved_samples(sample_rate, 2, samples).unwrap()
+}
+
+#[tokio::test]
+pub async fn test_find_duplicates() {
+    let library = Library::new(&[]).await;
+    let original = library.path("original.wav");
+    melody(1, 44100.0, 1.0, 0.0).wav_export(44100, &original).unwrap();
+    let copy = library.path("copy.flac");
+    melody(1, 48000.0, 0.5, 0.4).flac_export(&copy).unwrap();
+    let other = library.path("other.wav");
+    melody(2, 44100.0, 1.0, 0.0).wav_export(44100, &other).unwrap();
+
+    let mut ids = vec![];
+    for path in [&original, &copy, &other] {
+        let metadata_id = library.load(path).await;
+        fingerprint_metadata(&library.database(), metadata_id).await.unwrap();
+        ids.push(metadata_id);
+    }
+    assert_eq!(library.database.get_unfingerprinted_metadata_ids().await.unwrap(), Vec::<i64>::new());
+
+    let groups = DuplicateFinder::new(library.database(), DEFAULT_MIN_SIMILARITY)
+        .find()
+        .await
+        .unwrap();
+    assert_eq!(groups.len(), 1);
+    let entries = &groups[0].entries;
+    assert_eq!(
+        entries.iter().map(|entry| entry.metadata_id).collect::<Vec<_>>(),
+        vec![ids[0], ids[1]]
+    );
+    assert_eq!(entries[0].similarity, 1.0);
+    assert!(entries[1].similarity > 0.9, "similarity {}", entries[1].similarity);
+
+    // Everything matches once the threshold is low enough
+    let groups = DuplicateFinder::new(library.database(), 0.0).find().await.unwrap();
+    assert_eq!(groups[0].entries.len(), 3);
+    assert!(groups[0].entries[2].similarity < DEFAULT_MIN_SIMILARITY);
+}
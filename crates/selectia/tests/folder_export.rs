@@ -0,0 +1,163 @@
+use common::Library;
+use selectia::{
+    prelude::*,
+    tasks::folder_export::{ExportedFileStatus, FolderExportOptions, TranscodeFormat},
+    tasks::selection::ExportSelection,
+};
+use selectia_audio_file::audio_file::{AudioFilePayload, EncodedAudioFile};
+
+mod common;
+
+async fn tagged(library: &Library, relative: &str, artist: Option<&str>, title: &str) -> i64 {
+    let metadata_id = library.load(&library.path(relative)).await;
+    if let Some(artist) = artist {
+        library
+            .database
+            .set_metadata_tag_by_tag_name_id(metadata_id, TagName::ARTIST_ID, artist.to_string())
+            .await
+            .unwrap();
+    }
+    library
+        .database
+        .set_metadata_tag_by_tag_name_id(metadata_id, TagName::TITLE_ID, title.to_string())
+        .await
+        .unwrap();
+    metadata_id
+}
+
+fn options(library: &Library, transcode: Option<TranscodeFormat>) -> FolderExportOptions {
+    FolderExportOptions {
+        target: library.path("usb"),
+        layout: "{artist}/{title}.{ext}".to_string(),
+        transcode,
+        playlist_name: "Gig".to_string(),
+    }
+}
+
+#[tokio::test]
+pub async fn test_folder_export_copy() {
+    let library = Library::new(&[
+        ("in/a.mp3", b"first"),
+        ("in/b.flac", b"second"),
+        ("in/c.mp3", b"third"),
+        ("in/d.mp3", b"fourth"),
+    ])
+    .await;
+    tagged(&library, "in/a.mp3", Some("AC/DC"), "Track?").await;
+    tagged(&library, "in/b.flac", None, "Second").await;
+    tagged(&library, "in/c.mp3", Some("AC/DC"), "Track?").await;
+    library.load(&library.path("in/d.mp3")).await;
+
+    let export = FolderExport::new(library.database(), ExportSelection::All, options(&library, None)).unwrap();
+    let report = export.run(None).await.unwrap();
+    let destinations = report
+        .files
+        .iter()
+        .map(|file| (file.destination.as_str(), file.status))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        destinations,
+        vec![
+            ("AC_DC/Track_.mp3", ExportedFileStatus::Copied),
+            ("Unknown artist/Second.flac", ExportedFileStatus::Copied),
+            ("AC_DC/Track_ (2).mp3", ExportedFileStatus::Copied),
+            ("Unknown artist/d.mp3", ExportedFileStatus::Copied),
+        ]
+    );
+    assert_eq!(std::fs::read(library.path("usb/AC_DC/Track_ (2).mp3")).unwrap(), b"third");
+    assert_eq!(
+        std::fs::read_to_string(library.path("usb/Gig.m3u8")).unwrap(),
+        "#EXTM3U\n#PLAYLIST:Gig\n#EXTINF:-1,AC/DC - Track?\nAC_DC/Track_.mp3\n#EXTINF:-1,Second\nUnknown artist/Second.flac\n#EXTINF:-1,AC/DC - Track?\nAC_DC/Track_ (2).mp3\n#EXTINF:-1,d\nUnknown artist/d.mp3\n"
+    );
+
+    // Exporting again only writes the changed files
+    std::fs::write(library.path("usb/Unknown artist/d.mp3"), b"altered").unwrap();
+    let report = export.run(None).await.unwrap();
+    assert_eq!(report.count(ExportedFileStatus::Skipped), 3);
+    assert_eq!(report.files[3].status, ExportedFileStatus::Copied);
+    assert_eq!(std::fs::read(library.path("usb/Unknown artist/d.mp3")).unwrap(), b"fourth");
+}
+
+#[tokio::test]
+pub async fn test_folder_export_transcode() {
+    let library = Library::new(&[]).await;
+    std::fs::create_dir_all(&library.root).unwrap();
+    let samples = (0..20_000)
+        .map(|idx| (idx as f32 * 0.01).sin() * 0.5 * (idx % 2) as f32)
+        .collect::<Vec<_>>();
+    AudioFilePayload::from_interleaved_samples(44100.0, 2, samples)
+        .unwrap()
+        .wav_export(44100, library.path("source.wav"))
+        .unwrap();
+    tagged(&library, "source.wav", Some("Artist"), "Sine").await;
+
+    let export = FolderExport::new(
+        library.database(),
+        ExportSelection::All,
+        options(&library, Some(TranscodeFormat::Flac)),
+    )
+    .unwrap();
+    let report = export.run(None).await.unwrap();
+    assert_eq!(report.files[0].status, ExportedFileStatus::Transcoded);
+    assert_eq!(report.files[0].destination, "Artist/Sine.flac");
+
+    let source = EncodedAudioFile::from_file(library.path("source.wav"))
+        .unwrap()
+        .read_into_payload()
+        .unwrap();
+    let flac = EncodedAudioFile::from_file(library.path("usb/Artist/Sine.flac")).unwrap();
+    assert_eq!(flac.duration(), Some(10_000.0 / 44100.0));
+    let transcoded = flac.read_into_payload().unwrap();
+    assert_eq!(transcoded.channels, 2);
+    assert_eq!(transcoded.sample_rate, 44100.0);
+    assert_eq!(transcoded.buffer.buffer, source.buffer.buffer);
+
+    // Transcoded files are recognized through the manifest
+    let report = export.run(None).await.unwrap();
+    assert_eq!(report.files[0].status, ExportedFileStatus::Skipped);
+}
+
+#[tokio::test]
+pub async fn test_folder_export_layout_validation() {
+    let library = Library::new(&[]).await;
+    for layout in [
+        "",
+        "../{title}.{ext}",
+        "{artist}/../../{title}.{ext}",
+        "/{title}.{ext}",
+        "{artist/{title}.{ext}",
+    ] {
+        let options = FolderExportOptions {
+            layout: layout.to_string(),
+            ..options(&library, None)
+        };
+        assert!(FolderExport::new(library.database(), ExportSelection::All, options).is_err());
+    }
+}
+
+#[tokio::test]
+pub async fn test_folder_export_entry_without_file() {
+    let library = Library::new(&[("in/a.mp3", b"first")]).await;
+    let (orphan, _) = library.database.get_or_create_metadata("orphan").await.unwrap();
+    let metadata_id = tagged(&library, "in/a.mp3", Some("Artist"), "Track").await;
+    let playlist = library.database.get_or_create_playlist(None, "Set", false).await.unwrap();
+    library
+        .database
+        .set_playlist_entries(playlist.id, &[orphan.id, metadata_id])
+        .await
+        .unwrap();
+
+    // The entry without a file is reported, the others are still exported
+    let export = FolderExport::new(
+        library.database(),
+        ExportSelection::Playlist(playlist.id),
+        options(&library, None),
+    )
+    .unwrap();
+    let report = export.run(None).await.unwrap();
+    assert_eq!(report.files.len(), 2);
+    assert_eq!(report.files[0].metadata_id, orphan.id);
+    assert_eq!(report.files[0].status, ExportedFileStatus::Failed);
+    assert_eq!(report.files[1].status, ExportedFileStatus::Copied);
+    assert_eq!(std::fs::read(library.path("usb/Artist/Track.mp3")).unwrap(), b"first");
+}
@@ -0,0 +1,109 @@
+use common::Library;
+use selectia::{
+    services::file_loader::{hash_file, HashStrategy},
+    tasks::verify_library::{get_verification_report, LibraryVerification, VerifiedFileStatus},
+};
+use selectia_audio_file::flac::write_flac;
+
+mod common;
+
+/// 1 MiB of content, `edit` changes one byte outside of the sampled blocks
+fn content(edit: bool) -> Vec<u8> {
+    let mut content = (0..1024 * 1024).map(|idx| (idx % 251) as u8).collect::<Vec<_>>();
+    if edit {
+        content[300 * 1024] ^= 0xFF;
+    }
+    content
+}
+
+fn flac(padding: bool) -> Vec<u8> {
+    let samples = (0..8192)
+        .map(|idx| ((idx as f32 * 0.05).sin() * 8000.0) as i16)
+        .collect::<Vec<_>>();
+    let mut flac = vec![];
+    write_flac(&mut flac, 44100, 1, &samples).unwrap();
+    if padding {
+        // A PADDING block after STREAMINFO, as tag editors leave behind
+        flac[4] &= 0x7F;
+        let mut block = vec![0x81, 0x00, 0x01, 0x00];
+        block.extend([0u8; 256]);
+        flac.splice(42..42, block);
+    }
+    flac
+}
+
+#[tokio::test]
+pub async fn test_full_hash_sees_edits_between_sampled_blocks() {
+    let library = Library::new(&[("a.bin", &content(false)), ("b.bin", &content(true))]).await;
+    let (a, b) = (library.path("a.bin"), library.path("b.bin"));
+
+    let sampled = hash_file(&a, HashStrategy::Sampled).await.unwrap();
+    assert_eq!(sampled, hash_file(&b, HashStrategy::Sampled).await.unwrap());
+    assert_eq!(HashStrategy::of(&sampled), HashStrategy::Sampled);
+
+    let full = hash_file(&a, HashStrategy::Full).await.unwrap();
+    assert_ne!(full, hash_file(&b, HashStrategy::Full).await.unwrap());
+    assert_eq!(HashStrategy::of(&full), HashStrategy::Full);
+}
+
+#[tokio::test]
+pub async fn test_decoded_pcm_hash_ignores_metadata_blocks() {
+    let library = Library::new(&[("a.flac", &flac(false)), ("b.flac", &flac(true))]).await;
+    let (a, b) = (library.path("a.flac"), library.path("b.flac"));
+
+    assert_ne!(
+        hash_file(&a, HashStrategy::Full).await.unwrap(),
+        hash_file(&b, HashStrategy::Full).await.unwrap()
+    );
+    let pcm = hash_file(&a, HashStrategy::DecodedPcm).await.unwrap();
+    assert_eq!(pcm, hash_file(&b, HashStrategy::DecodedPcm).await.unwrap());
+    assert_eq!(HashStrategy::of(&pcm), HashStrategy::DecodedPcm);
+}
+
+#[tokio::test]
+pub async fn test_strategy_change_keeps_entries() {
+    let library = Library::new(&[("a.bin", &content(false))]).await;
+    let sampled_id = library.load(&library.path("a.bin")).await;
+
+    library.set_hash_strategy(HashStrategy::Full).await;
+    assert_eq!(library.load(&library.path("a.bin")).await, sampled_id);
+
+    std::fs::write(library.path("a.bin"), content(true)).unwrap();
+    assert_ne!(library.load(&library.path("a.bin")).await, sampled_id);
+}
+
+#[tokio::test]
+pub async fn test_verify_library() {
+    let library = Library::new(&[
+        ("kept.bin", b"kept"),
+        ("changed.bin", b"before"),
+        ("missing.bin", b"missing"),
+    ])
+    .await;
+    for name in ["kept.bin", "changed.bin", "missing.bin"] {
+        library.load(&library.path(name)).await;
+    }
+    std::fs::write(library.path("changed.bin"), b"after").unwrap();
+    std::fs::remove_file(library.path("missing.bin")).unwrap();
+
+    let report = LibraryVerification::new(library.database()).run(None).await.unwrap();
+    assert_eq!(report.count(VerifiedFileStatus::Unchanged), 1);
+    assert_eq!(report.count(VerifiedFileStatus::Changed), 1);
+    assert_eq!(report.count(VerifiedFileStatus::Missing), 1);
+
+    let mut stored = get_verification_report(&library.database())
+        .await
+        .unwrap()
+        .files
+        .into_iter()
+        .map(|file| (file.path, file.status))
+        .collect::<Vec<_>>();
+    stored.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        stored,
+        vec![
+            (library.path("changed.bin"), VerifiedFileStatus::Changed),
+            (library.path("missing.bin"), VerifiedFileStatus::Missing),
+        ]
+    );
+}
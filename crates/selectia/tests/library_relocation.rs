@@ -0,0 +1,54 @@
+use common::Library;
+use selectia::{database::models::ImportRules, prelude::*, tasks::library_relocation::LibraryRelocation};
+
+mod common;
+
+#[tokio::test]
+pub async fn test_relocate_library_root() {
+    let library = Library::new(&[
+        ("drive/music/a.mp3", b"a"),
+        ("drive/music/sub/b.mp3", b"b"),
+        ("other/c.mp3", b"c"),
+    ])
+    .await;
+    let database = library.database();
+    let root = database
+        .upsert_library_root(&library.path("drive/music"), &ImportRules::default())
+        .await
+        .unwrap();
+    database.set_library_root_name(root.id, Some("Music")).await.unwrap();
+    let a = library.load(&library.path("drive/music/a.mp3")).await;
+    let b = library.load(&library.path("drive/music/sub/b.mp3")).await;
+    let c = library.load(&library.path("other/c.mp3")).await;
+
+    // The drive is mounted elsewhere, b is not there yet
+    let moved = library.path("mount/music");
+    std::fs::create_dir_all(moved.join("sub")).unwrap();
+    std::fs::copy(library.path("drive/music/a.mp3"), moved.join("a.mp3")).unwrap();
+    let relocation = LibraryRelocation::new(database.clone(), root.id, moved.clone());
+    let plan = relocation.plan().await.unwrap();
+    assert_eq!(plan.files, 2);
+    assert_eq!(plan.missing, vec![moved.join("sub/b.mp3")]);
+    assert!(relocation.relocate().await.is_err());
+    assert_eq!(database.get_file_from_metadata_id(a).await.unwrap().path, library.path("drive/music/a.mp3").to_string_lossy());
+
+    std::fs::copy(library.path("drive/music/sub/b.mp3"), moved.join("sub/b.mp3")).unwrap();
+    relocation.relocate().await.unwrap();
+    for (metadata_id, path) in [
+        (a, moved.join("a.mp3")),
+        (b, moved.join("sub/b.mp3")),
+        (c, library.path("other/c.mp3")),
+    ] {
+        let file = database.get_file_from_metadata_id(metadata_id).await.unwrap();
+        assert_eq!(file.path, path.to_string_lossy());
+        assert_eq!(
+            library.tag_values(metadata_id, TagName::DIRECTORY_ID).await,
+            vec![path.parent().unwrap().to_string_lossy().to_string()]
+        );
+    }
+    let root = database.get_library_root_by_name("Music").await.unwrap().unwrap();
+    assert_eq!(root.path, moved.to_string_lossy());
+
+    // Loading the moved files again keeps their entries
+    assert_eq!(library.load(&moved.join("sub/b.mp3")).await, b);
+}
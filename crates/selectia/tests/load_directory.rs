@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+use selectia::{database::models::ImportRules, prelude::*, test_utils::TmpDatabase};
+use tempdir::TempDir;
+
+fn write(root: &Path, relative: &str, size: usize) {
+    let path = root.join(relative);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, vec![0u8; size]).unwrap();
+}
+
+fn create_library() -> TempDir {
+    let dir = TempDir::new("selectia-load-directory").unwrap();
+    let root = dir.path();
+    write(root, "a.mp3", 4);
+    write(root, "b.FLAC", 4);
+    write(root, "notes.txt", 4);
+    write(root, "big.wav", 128);
+    write(root, ".hidden/c.mp3", 4);
+    write(root, "a.stems/drums.wav", 4);
+    write(root, "samples/kick.wav", 4);
+    write(root, "keep/d.opus", 4);
+    std::fs::write(root.join(".selectiaignore"), "samples/\n").unwrap();
+    std::os::unix::fs::symlink(root, root.join("keep/loop")).unwrap();
+    // Dangling symlink, skipped even when symlinks are followed
+    std::os::unix::fs::symlink(root.join("removed.mp3"), root.join("keep/removed.mp3")).unwrap();
+    dir
+}
+
+async fn collect(root: &Path, rules: ImportRules) -> Vec<PathBuf> {
+    let theater = OwnedTheaterContext::new().await;
+    let database = TmpDatabase::new().await;
+    theater.register_singleton(database.clone()).await.unwrap();
+    let file_loader = FileLoader::spawn(&theater).await.unwrap();
+
+    let mut files = LoadDirectory::new(file_loader, root.to_path_buf())
+        .unwrap()
+        .with_rules(rules)
+        .collect()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|path| path.strip_prefix(root).unwrap().to_path_buf())
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+#[tokio::test]
+pub async fn test_default_import_rules() {
+    let library = create_library();
+    let files = collect(library.path(), ImportRules::default()).await;
+    assert_eq!(
+        files,
+        vec![
+            PathBuf::from("a.mp3"),
+            PathBuf::from("b.FLAC"),
+            PathBuf::from("big.wav"),
+            PathBuf::from("keep/d.opus"),
+        ]
+    );
+}
+
+#[tokio::test]
+pub async fn test_custom_import_rules() {
+    let library = create_library();
+    let rules = ImportRules {
+        ignore: vec!["*.flac".to_string(), "*.stems/".to_string()],
+        follow_symlinks: true,
+        max_size: Some(64),
+        ..Default::default()
+    };
+    let files = collect(library.path(), rules).await;
+    assert_eq!(
+        files,
+        vec![PathBuf::from("a.mp3"), PathBuf::from("keep/d.opus")]
+    );
+}
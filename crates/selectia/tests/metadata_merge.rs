@@ -0,0 +1,93 @@
+use common::Library;
+use selectia::{
+    database::models::{CuePoint, TempoMarker},
+    prelude::*,
+    services::file_loader::{hash_file, HashStrategy},
+};
+
+mod common;
+
+fn hot_cue(hot_cue_index: i64, start_time: f64) -> CuePoint {
+    CuePoint {
+        kind: "cue".to_string(),
+        hot_cue_index: Some(hot_cue_index),
+        start_time,
+        end_time: None,
+        name: None,
+        color: None,
+    }
+}
+
+#[tokio::test]
+pub async fn test_merge_metadata() {
+    let library = Library::new(&[("a.mp3", b"mp3 encoding"), ("b.flac", b"flac encoding")]).await;
+    let into = library.load(&library.path("a.mp3")).await;
+    let from = library.load(&library.path("b.flac")).await;
+    let database = library.database();
+    database.set_metadata_tag_by_tag_name_id(into, TagName::TITLE_ID, "Song".to_string()).await.unwrap();
+    database.set_metadata_tag_by_tag_name_id(from, TagName::TITLE_ID, "Song (FLAC)".to_string()).await.unwrap();
+    database.replace_cues(into, "test", &[hot_cue(0, 1.0)]).await.unwrap();
+    database.replace_cues(from, "test", &[hot_cue(0, 2.0), hot_cue(1, 3.0)]).await.unwrap();
+    let grid = TempoMarker {
+        position: 0.1,
+        bpm: 128.0,
+        meter: None,
+        beat: 1,
+    };
+    database.replace_beat_grid(from, "test", &[grid]).await.unwrap();
+    let both = database.get_or_create_playlist(None, "Both", false).await.unwrap();
+    database.set_playlist_entries(both.id, &[into, from]).await.unwrap();
+    let single = database.get_or_create_playlist(None, "Single", false).await.unwrap();
+    database.set_playlist_entries(single.id, &[from]).await.unwrap();
+
+    database.merge_metadata(from, into).await.unwrap();
+
+    let (tags, cues, grid) = library.snapshot("b.flac").await;
+    assert_eq!(library.database.get_file_by_path(&library.path("b.flac")).await.unwrap().unwrap().metadata_id, into);
+    assert!(tags.contains(&(TagName::TITLE_ID, vec!["Song".to_string(), "Song (FLAC)".to_string()])));
+    let mut slots = cues.iter().map(|cue| (cue.start_time, cue.hot_cue_index)).collect::<Vec<_>>();
+    slots.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert_eq!(slots, vec![(1.0, Some(0)), (2.0, None), (3.0, Some(1))]);
+    assert_eq!(grid.len(), 1);
+    assert_eq!(database.get_playlist_entries(both.id).await.unwrap(), vec![into]);
+    assert_eq!(database.get_playlist_entries(single.id).await.unwrap(), vec![into]);
+    assert!(database.get_entry_by_metadata_id(from).await.is_err());
+
+    // The merged file keeps resolving to the merged entry
+    assert_eq!(library.load(&library.path("b.flac")).await, into);
+    assert!(database.merge_metadata(into, into).await.is_err());
+}
+
+#[tokio::test]
+pub async fn test_split_file() {
+    let library = Library::new(&[("x/a.mp3", b"same content"), ("y/a.mp3", b"same content")]).await;
+    let original = library.load(&library.path("x/a.mp3")).await;
+    assert_eq!(library.load(&library.path("y/a.mp3")).await, original);
+    let database = library.database();
+    database.set_metadata_tag_by_tag_name_id(original, TagName::TITLE_ID, "Title".to_string()).await.unwrap();
+    database.set_metadata_tag_by_tag_name_id(original, TagName::ARTIST_ID, "Artist".to_string()).await.unwrap();
+
+    let file = library.database.get_file_by_path(&library.path("y/a.mp3")).await.unwrap().unwrap();
+    let hash = hash_file(&library.path("y/a.mp3"), HashStrategy::Sampled).await.unwrap();
+    assert!(database.split_file(file.id, &hash, &[TagName::DIRECTORY_ID]).await.is_err());
+    let split = database.split_file(file.id, &hash, &[TagName::TITLE_ID]).await.unwrap();
+    assert_ne!(split, original);
+
+    let directory = |relative: &str| library.path(relative).to_string_lossy().to_string();
+    assert_eq!(library.tag_values(split, TagName::TITLE_ID).await, vec!["Title".to_string()]);
+    assert_eq!(library.tag_values(split, TagName::ARTIST_ID).await, Vec::<String>::new());
+    assert_eq!(library.tag_values(split, TagName::DIRECTORY_ID).await, vec![directory("y")]);
+    assert_eq!(library.tag_values(original, TagName::DIRECTORY_ID).await, vec![directory("x")]);
+    assert_eq!(library.tag_values(original, TagName::ARTIST_ID).await, vec!["Artist".to_string()]);
+
+    // Both files keep their own entry while their content is unchanged
+    assert_eq!(library.load(&library.path("y/a.mp3")).await, split);
+    assert_eq!(library.load(&library.path("x/a.mp3")).await, original);
+    let last = library.database.get_file_by_path(&library.path("x/a.mp3")).await.unwrap().unwrap();
+    assert!(database.split_file(last.id, &hash, &[]).await.is_err());
+
+    std::fs::write(library.path("y/a.mp3"), b"new content").unwrap();
+    let reloaded = library.load(&library.path("y/a.mp3")).await;
+    assert_ne!(reloaded, split);
+    assert_ne!(reloaded, original);
+}
@@ -0,0 +1,130 @@
+use std::path::{Path, PathBuf};
+
+use selectia::{
+    analyser::path_pattern_analyser::{ExtractedTag, PathPattern},
+    database::models::TaggingRule,
+    prelude::*,
+    test_utils::TmpDatabase,
+};
+use tempdir::TempDir;
+
+fn extract(template: &str, path: &str) -> Option<Vec<(String, String)>> {
+    PathPattern::parse(template)
+        .unwrap()
+        .extract(Path::new(path))
+        .map(|tags| {
+            tags.into_iter()
+                .map(|ExtractedTag { name, value }| (name, value))
+                .collect()
+        })
+}
+
+fn tags(pairs: &[(&str, &str)]) -> Option<Vec<(String, String)>> {
+    Some(
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+    )
+}
+
+#[test]
+pub fn test_path_pattern_extract() {
+    assert_eq!(
+        extract(
+            "{artist} - {title} ({remix})",
+            "/music/Daft Punk - One More Time (Extended Mix).mp3"
+        ),
+        tags(&[
+            ("artist", "Daft Punk"),
+            ("title", "One More Time"),
+            ("remix", "Extended Mix")
+        ])
+    );
+    assert_eq!(
+        extract(
+            "{label}/{artist}/{album}/{track} {title}",
+            "/music/Warp/Aphex Twin/Syro/03 produk 29.flac"
+        ),
+        tags(&[
+            ("label", "Warp"),
+            ("artist", "Aphex Twin"),
+            ("album", "Syro"),
+            ("track", "03"),
+            ("title", "produk 29")
+        ])
+    );
+    assert_eq!(
+        extract("[{bpm}] {_} - {title}", "/music/[124] Someone - Track.wav"),
+        tags(&[("tempo", "124"), ("title", "Track")])
+    );
+    assert_eq!(
+        extract("{artist} - {title} ({remix})", "/music/No Separator.mp3"),
+        None
+    );
+    assert_eq!(extract("{label}/{artist}/{title}", "/a.mp3"), None);
+}
+
+#[test]
+pub fn test_path_pattern_parse_errors() {
+    assert!(PathPattern::parse("{artist}{title}").is_err());
+    assert!(PathPattern::parse("{artist - {title}").is_err());
+    assert!(PathPattern::parse("artist} - {title}").is_err());
+    assert!(PathPattern::parse("{} - {title}").is_err());
+}
+
+#[tokio::test]
+pub async fn test_filename_tagging_apply() {
+    let theater = OwnedTheaterContext::new().await;
+    let database = TmpDatabase::new().await;
+    theater.register_singleton(database.clone()).await.unwrap();
+    let file_loader = FileLoader::spawn(&theater).await.unwrap();
+    theater.ready().await;
+
+    let rule = database
+        .create_tagging_rule("{artist} - {title}")
+        .await
+        .unwrap();
+    let (callback, reloaded) = TaskCallback::new();
+    file_loader
+        .send(FileLoaderTask::ReloadTaggingRules {
+            callback: Some(callback),
+        })
+        .await
+        .unwrap();
+    reloaded.wait().await.unwrap();
+
+    let dir = TempDir::new("selectia-path-pattern").unwrap();
+    let path = dir.path().join("Artist - Title.mp3");
+    std::fs::write(&path, b"not really audio").unwrap();
+
+    let (callback, resolve) = TaskCallback::new();
+    file_loader
+        .send(FileLoaderTask::LoadFile {
+            path: PathBuf::from(&path),
+            callback: Some(callback),
+        })
+        .await
+        .unwrap();
+    let metadata_id = resolve.wait().await.unwrap();
+
+    let entry = database.get_entry_by_metadata_id(metadata_id).await.unwrap();
+    let find = |tag_name_id: i64| {
+        entry
+            .tags
+            .0
+            .iter()
+            .find(|tag| tag.tag_name_id == tag_name_id)
+            .cloned()
+            .unwrap()
+    };
+    let artist = find(TagName::ARTIST_ID);
+    assert_eq!(artist.tag_value, "Artist");
+    assert_eq!(artist.tag_source, Some(TaggingRule::source(rule.id)));
+    assert_eq!(find(TagName::TITLE_ID).tag_value, "Title");
+
+    // Tags already set are kept, so applying again does not update the entry
+    let tagging = FilenameTagging::new(database.clone()).await.unwrap();
+    assert_eq!(tagging.apply(&[metadata_id]).await.unwrap(), Vec::<i64>::new());
+    assert_eq!(tagging.preview(&[metadata_id]).await.unwrap().len(), 1);
+}
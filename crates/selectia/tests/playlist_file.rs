@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use common::Library;
+use selectia::{
+    prelude::*,
+    tasks::playlist_file::format::{decode, resolve_location, PlaylistFile, PlaylistFileEntry},
+    tasks::reconciliation::ReconciliationStatus,
+    tasks::selection::ExportSelection,
+};
+
+mod common;
+
+fn entry(location: &str, title: Option<&str>, duration: Option<i64>) -> PlaylistFileEntry {
+    PlaylistFileEntry {
+        location: location.to_string(),
+        title: title.map(str::to_string),
+        duration,
+    }
+}
+
+#[test]
+pub fn test_playlist_file_parse() {
+    let plain = PlaylistFile::parse("# Promo pool\r\na.mp3\r\n\r\nsub/b.mp3\r\n").unwrap();
+    assert_eq!(
+        plain.entries,
+        vec![entry("a.mp3", None, None), entry("sub/b.mp3", None, None)]
+    );
+
+    let extended = PlaylistFile::parse(
+        "#EXTM3U\n#PLAYLIST:Radio Show\n#EXTINF:312,Artist - Title\na.mp3\n#EXTINF:-1 tvg-id=\"x\",Live\nhttp://stream.example.com/live\nb.mp3\n",
+    )
+    .unwrap();
+    assert_eq!(extended.name.as_deref(), Some("Radio Show"));
+    assert_eq!(
+        extended.entries,
+        vec![
+            entry("a.mp3", Some("Artist - Title"), Some(312)),
+            entry("http://stream.example.com/live", Some("Live"), None),
+            entry("b.mp3", None, None),
+        ]
+    );
+
+    let pls = PlaylistFile::parse(
+        "[playlist]\nFile2=b.mp3\nTitle2=Second\nFile1=a.mp3\nTitle1=First\nLength1=200.4\nLength2=-1\nNumberOfEntries=2\nVersion=2\n",
+    )
+    .unwrap();
+    assert_eq!(
+        pls.entries,
+        vec![entry("a.mp3", Some("First"), Some(200)), entry("b.mp3", Some("Second"), None)]
+    );
+}
+
+#[test]
+pub fn test_playlist_file_locations() {
+    let base_dir = Path::new("/music/lists");
+    assert_eq!(
+        resolve_location("../sets/./a.mp3", base_dir).unwrap(),
+        Path::new("/music/sets/a.mp3")
+    );
+    assert_eq!(resolve_location("/other/a.mp3", base_dir).unwrap(), Path::new("/other/a.mp3"));
+    assert_eq!(
+        resolve_location("file:///other/Caf%C3%A9.mp3", base_dir).unwrap(),
+        Path::new("/other/Café.mp3")
+    );
+    assert_eq!(resolve_location("https://example.com/a.mp3", base_dir), None);
+    assert_eq!(decode(b"Caf\xe9.mp3"), "Café.mp3");
+    assert_eq!(decode(b"\xEF\xBB\xBFCaf\xC3\xA9.mp3"), "Café.mp3");
+}
+
+#[tokio::test]
+pub async fn test_playlist_file_import() {
+    let library = Library::new(&[
+        ("Artist A - Matched.mp3", b"matched"),
+        ("sub dir/Café Track.flac", b"ingested"),
+    ])
+    .await;
+    let matched_id = library.load(&library.path("Artist A - Matched.mp3")).await;
+
+    // Latin-1 encoded, as written by older software
+    let content = format!(
+        "#EXTM3U\n#EXTINF:312,Artist B - Café Track\n../sub dir/Café Track.flac\n#EXTINF:-1,Lost\nmissing.mp3\nhttp://stream.example.com/live\n{}\n..\\sub dir\\Café Track.flac\n",
+        library.path("Artist A - Matched.mp3").display()
+    );
+    let path = library.path("lists/promo.m3u");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, content.chars().map(|c| c as u8).collect::<Vec<_>>()).unwrap();
+
+    let report = PlaylistFileImport::from_file(library.database(), library.file_loader.clone(), &path)
+        .await
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+    let statuses = report.tracks.iter().map(|track| track.status).collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        vec![
+            ReconciliationStatus::Ingested,
+            ReconciliationStatus::Missing,
+            ReconciliationStatus::Failed,
+            ReconciliationStatus::Matched,
+        ]
+    );
+    let ingested_id = report.tracks[0].metadata_id.unwrap();
+    assert_eq!(report.tracks[3].metadata_id, Some(matched_id));
+    assert_eq!(library.tag_values(ingested_id, TagName::ARTIST_ID).await, vec!["Artist B"]);
+    assert_eq!(library.tag_values(ingested_id, TagName::TITLE_ID).await, vec!["Café Track"]);
+    assert_eq!(library.tag_values(matched_id, TagName::PLAYLIST_ID).await, vec!["promo"]);
+
+    let playlist = &report.playlists[0];
+    assert_eq!(playlist.path, vec!["promo"]);
+    assert_eq!((playlist.entries, playlist.missing_entries), (3, 2));
+    assert_eq!(
+        library.database.get_playlist_entries(playlist.playlist_id).await.unwrap(),
+        vec![ingested_id, matched_id, ingested_id]
+    );
+}
+
+#[tokio::test]
+pub async fn test_playlist_file_export() {
+    let library = Library::new(&[
+        ("Artist A - Matched.mp3", b"matched"),
+        ("sub dir/Café Track.flac", b"ingested"),
+    ])
+    .await;
+    let path = library.path("promo.m3u8");
+    let content = "#EXTINF:312,Artist B - Café Track\nsub dir/Café Track.flac\nArtist A - Matched.mp3\n";
+    std::fs::write(&path, content).unwrap();
+    let report = PlaylistFileImport::from_file(library.database(), library.file_loader.clone(), &path)
+        .await
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+
+    let export = PlaylistFileExport::new(
+        library.database(),
+        ExportSelection::Playlist(report.playlists[0].playlist_id),
+    );
+    let playlist = export.build().await.unwrap();
+    let cafe = library.path("sub dir/Café Track.flac");
+    let matched = library.path("Artist A - Matched.mp3");
+    assert_eq!(
+        playlist.to_m3u8(),
+        format!(
+            "#EXTM3U\n#PLAYLIST:promo\n#EXTINF:-1,Artist B - Café Track\n{}\n#EXTINF:-1,Artist A - Matched\n{}\n",
+            cafe.display(),
+            matched.display()
+        )
+    );
+
+    let exported = library.path("exported.m3u8");
+    export.write(&exported).await.unwrap();
+    let parsed = PlaylistFile::parse(&std::fs::read_to_string(&exported).unwrap()).unwrap();
+    assert_eq!(parsed, playlist);
+}
+
+#[tokio::test]
+pub async fn test_playlist_file_export_entry_without_file() {
+    let library = Library::new(&[("Artist A - Matched.mp3", b"matched")]).await;
+    let matched = library.path("Artist A - Matched.mp3");
+    let matched_id = library.load(&matched).await;
+    let (orphan, _) = library.database.get_or_create_metadata("orphan").await.unwrap();
+    let playlist = library.database.get_or_create_playlist(None, "Set", false).await.unwrap();
+    library
+        .database
+        .set_playlist_entries(playlist.id, &[orphan.id, matched_id])
+        .await
+        .unwrap();
+
+    // The entry without a file is left out instead of failing the export
+    let export = PlaylistFileExport::new(library.database(), ExportSelection::Playlist(playlist.id));
+    let playlist = export.build().await.unwrap();
+    assert_eq!(playlist.entries.len(), 1);
+    assert_eq!(playlist.entries[0].location, matched.to_string_lossy());
+}
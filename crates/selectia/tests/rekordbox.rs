@@ -0,0 +1,241 @@
+use std::path::{Path, PathBuf};
+
+use common::Library;
+use selectia::{
+    database::models::CuePoint,
+    prelude::*,
+    tasks::reconciliation::{ImportReport, ReconciliationStatus, TagConflict},
+    tasks::rekordbox::xml::{location_to_path, path_to_location},
+    tasks::selection::ExportSelection,
+};
+
+mod common;
+
+const FIXTURE: &str = include_str!("fixtures/rekordbox.xml");
+
+async fn library() -> Library {
+    Library::new(&[
+        ("Artist A - Matched.mp3", b"matched"),
+        ("sub dir/Café Track.flac", b"ingested"),
+    ])
+    .await
+}
+
+async fn import(library: &Library) -> ImportReport {
+    let xml = FIXTURE.replace("{ROOT}", library.root.to_str().unwrap());
+    import_xml(library, &xml).await
+}
+
+async fn import_xml(library: &Library, xml: &str) -> ImportReport {
+    RekordboxImport::new(library.database(), library.file_loader.clone(), xml)
+        .unwrap()
+        .run()
+        .await
+        .unwrap()
+}
+
+async fn export(library: &Library, selection: ExportSelection) -> String {
+    RekordboxExport::new(library.database(), selection)
+        .build()
+        .await
+        .unwrap()
+        .to_xml()
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn test_rekordbox_import() {
+    let library = library().await;
+    let matched_id = library.load(&library.path("Artist A - Matched.mp3")).await;
+    library
+        .database
+        .set_metadata_tag_by_tag_name_id(matched_id, TagName::ARTIST_ID, "Someone Else".to_string())
+        .await
+        .unwrap();
+
+    let report = import(&library).await;
+    assert_eq!(report.count(ReconciliationStatus::Matched), 1);
+    assert_eq!(report.count(ReconciliationStatus::Ingested), 1);
+    assert_eq!(report.count(ReconciliationStatus::Missing), 1);
+    assert_eq!(report.count(ReconciliationStatus::Failed), 0);
+
+    let matched = &report.tracks[0];
+    assert_eq!(matched.metadata_id, Some(matched_id));
+    assert_eq!(
+        matched.conflicts,
+        vec![TagConflict {
+            name: "artist".to_string(),
+            existing: vec!["Someone Else".to_string()],
+            imported: "Artist A".to_string(),
+        }]
+    );
+    assert_eq!(library.tag_values(matched_id, TagName::TITLE_ID).await, vec!["Matched Track"]);
+    assert_eq!(library.tag_values(matched_id, TagName::ARTIST_ID).await, vec!["Someone Else"]);
+    assert_eq!(library.tag_values(matched_id, TagName::TEMPO_ID).await, vec!["124"]);
+    assert_eq!(library.tag_values(matched_id, TagName::KEY_ID).await, vec!["8A"]);
+    assert_eq!(library.tag_values(matched_id, TagName::RATING_ID).await, vec!["4"]);
+    assert_eq!(library.tag_values(matched_id, TagName::COMMENT_ID).await, vec!["Great opener"]);
+    assert_eq!(library.tag_values(matched_id, TagName::COLOR_ID).await, vec!["#ff007f"]);
+    assert_eq!(library.tag_values(matched_id, TagName::LABEL_ID).await, vec!["Some Label"]);
+    assert_eq!(
+        library.tag_values(matched_id, TagName::PLAYLIST_ID).await,
+        vec!["By Location", "Warmup"]
+    );
+
+    let cues = library
+        .database
+        .get_cues(matched_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(CuePoint::from)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        cues,
+        vec![
+            CuePoint {
+                kind: "cue".to_string(),
+                hot_cue_index: None,
+                start_time: 0.025,
+                end_time: None,
+                name: None,
+                color: None,
+            },
+            CuePoint {
+                kind: "cue".to_string(),
+                hot_cue_index: Some(0),
+                start_time: 64.541,
+                end_time: None,
+                name: Some("Drop".to_string()),
+                color: Some("#28e214".to_string()),
+            },
+            CuePoint {
+                kind: "loop".to_string(),
+                hot_cue_index: Some(1),
+                start_time: 96.025,
+                end_time: Some(103.767),
+                name: None,
+                color: Some("#ff8c00".to_string()),
+            },
+        ]
+    );
+    let grid = library.database.get_beat_grid(matched_id).await.unwrap();
+    assert_eq!(grid.len(), 2);
+    assert_eq!((grid[1].position, grid[1].bpm, grid[1].beat), (120.025, 126.5, 3));
+
+    let ingested = &report.tracks[1];
+    assert_eq!(ingested.path, library.path("sub dir/Café Track.flac").to_str().unwrap());
+    let ingested_id = ingested.metadata_id.unwrap();
+    assert_eq!(library.tag_values(ingested_id, TagName::TEMPO_ID).await, vec!["132"]);
+    assert_eq!(library.tag_values(ingested_id, TagName::RATING_ID).await, Vec::<String>::new());
+    assert_eq!(library.tag_values(ingested_id, TagName::COLOR_ID).await, Vec::<String>::new());
+
+    let playlists = library.database.get_playlists().await.unwrap();
+    let sets = playlists.iter().find(|p| p.name == "Sets").unwrap();
+    let warmup = playlists.iter().find(|p| p.name == "Warmup").unwrap();
+    assert!(sets.is_folder && sets.parent_id.is_none());
+    assert_eq!(warmup.parent_id, Some(sets.id));
+    assert_eq!(
+        library.database.get_playlist_entries(warmup.id).await.unwrap(),
+        vec![ingested_id, matched_id]
+    );
+    let warmup_report = report.playlists.iter().find(|p| p.playlist_id == warmup.id).unwrap();
+    assert_eq!(warmup_report.path, vec!["Sets", "Warmup"]);
+    assert_eq!((warmup_report.entries, warmup_report.missing_entries), (2, 1));
+
+    // Importing again only matches the known files and does not duplicate anything
+    let report = import(&library).await;
+    assert_eq!(report.count(ReconciliationStatus::Matched), 2);
+    assert!(report.tracks[1].updated.is_empty());
+    assert_eq!(library.database.get_cues(matched_id).await.unwrap().len(), 3);
+    assert_eq!(library.database.get_playlists().await.unwrap().len(), 3);
+    assert_eq!(
+        library.database.get_playlist_entries(warmup.id).await.unwrap(),
+        vec![ingested_id, matched_id]
+    );
+}
+
+#[test]
+pub fn test_rekordbox_location_encoding() {
+    let path = Path::new("/music/Café #1 & 100% [live].mp3");
+    let location = path_to_location(path);
+    assert_eq!(
+        location,
+        "file://localhost/music/Caf%C3%A9%20%231%20%26%20100%25%20%5Blive%5D.mp3"
+    );
+    assert_eq!(location_to_path(&location).unwrap(), path);
+    assert_eq!(
+        location_to_path("file://localhost/C:/My%20Music/a.mp3").unwrap(),
+        PathBuf::from("C:/My Music/a.mp3")
+    );
+}
+
+#[tokio::test]
+pub async fn test_rekordbox_export_round_trip() {
+    let library = library().await;
+    import(&library).await;
+    let xml = export(&library, ExportSelection::All).await;
+
+    let copy = Library::open(library.root.clone()).await;
+    let report = import_xml(&copy, &xml).await;
+    assert_eq!(report.count(ReconciliationStatus::Ingested), 2);
+    assert_eq!(report.count(ReconciliationStatus::Failed), 0);
+    for relative in ["Artist A - Matched.mp3", "sub dir/Café Track.flac"] {
+        assert_eq!(library.snapshot(relative).await, copy.snapshot(relative).await);
+    }
+    assert_eq!(library.playlist_tree().await, copy.playlist_tree().await);
+}
+
+#[tokio::test]
+pub async fn test_rekordbox_export_playlist() {
+    let library = library().await;
+    import(&library).await;
+    let playlists = library.database.get_playlists().await.unwrap();
+    let sets = playlists.iter().find(|p| p.name == "Sets").unwrap();
+
+    let xml = export(&library, ExportSelection::Playlist(sets.id)).await;
+    let copy = Library::open(library.root.clone()).await;
+    let report = import_xml(&copy, &xml).await;
+    assert_eq!(report.tracks.len(), 2);
+    assert_eq!(
+        copy.playlist_tree().await,
+        vec![
+            (vec!["Sets".to_string()], vec![]),
+            (
+                vec!["Sets".to_string(), "Warmup".to_string()],
+                vec![
+                    library.path("sub dir/Café Track.flac").to_string_lossy().to_string(),
+                    library.path("Artist A - Matched.mp3").to_string_lossy().to_string(),
+                ]
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+pub async fn test_rekordbox_export_entry_without_file() {
+    let library = library().await;
+    let matched_id = library.load(&library.path("Artist A - Matched.mp3")).await;
+    library.database.set_metadata_duration(matched_id, 312.4).await.unwrap();
+    let (orphan, _) = library.database.get_or_create_metadata("orphan").await.unwrap();
+    let playlist = library.database.get_or_create_playlist(None, "Set", false).await.unwrap();
+    library
+        .database
+        .set_playlist_entries(playlist.id, &[orphan.id, matched_id])
+        .await
+        .unwrap();
+
+    // The entry without a file is left out instead of failing the export
+    let xml = export(&library, ExportSelection::Playlist(playlist.id)).await;
+    assert!(xml.contains(r#"TotalTime="312""#), "{}", xml);
+    let copy = Library::open(library.root.clone()).await;
+    let report = import_xml(&copy, &xml).await;
+    assert_eq!(report.tracks.len(), 1);
+    assert_eq!(
+        copy.playlist_tree().await,
+        vec![(
+            vec!["Set".to_string()],
+            vec![library.path("Artist A - Matched.mp3").to_string_lossy().to_string()]
+        )]
+    );
+}
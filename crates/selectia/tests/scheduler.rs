@@ -0,0 +1,107 @@
+use chrono::{Local, TimeZone};
+use common::Library;
+use selectia::services::worker::{
+    scheduler::{claim_due_schedules, create_schedule, CronExpression, ScheduleSpec},
+    tasks::{TaskPayload, VerifyLibraryTask},
+};
+use selectia::tasks::backup::{backup_database, list_backups};
+use tempdir::TempDir;
+
+mod common;
+
+fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+    Local
+        .with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .unwrap()
+        .timestamp()
+}
+
+#[test]
+pub fn test_cron_next_run() {
+    let next = |expression: &str, after: i64| {
+        expression
+            .parse::<CronExpression>()
+            .unwrap()
+            .next_run_after(after)
+            .unwrap()
+    };
+    // Wednesday
+    let now = local(2024, 11, 13, 10, 17);
+    assert_eq!(next("* * * * *", now), local(2024, 11, 13, 10, 18));
+    assert_eq!(next("0 3 * * *", now), local(2024, 11, 14, 3, 0));
+    assert_eq!(next("*/15 * * * *", now), local(2024, 11, 13, 10, 30));
+    assert_eq!(next("0 9-17/4 * * *", now), local(2024, 11, 13, 13, 0));
+    // Sunday as 0 and 7
+    assert_eq!(next("30 3 * * 0", now), local(2024, 11, 17, 3, 30));
+    assert_eq!(next("30 3 * * 7", now), local(2024, 11, 17, 3, 30));
+    assert_eq!(next("0 0 1 1 *", now), local(2025, 1, 1, 0, 0));
+    // Either the day of month or the day of week
+    assert_eq!(next("0 0 20 * 5", now), local(2024, 11, 15, 0, 0));
+    assert_eq!(next("0 0 29 2 *", now), local(2028, 2, 29, 0, 0));
+
+    for invalid in ["* * * *", "60 * * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+        assert!(invalid.parse::<CronExpression>().is_err(), "{}", invalid);
+    }
+    assert!("0 0 31 2 *".parse::<CronExpression>().unwrap().next_run_after(now).is_err());
+    assert_eq!(ScheduleSpec::Interval(60).next_run_after(now).unwrap(), now + 60);
+}
+
+#[tokio::test]
+pub async fn test_due_schedules() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let payload = TaskPayload::VerifyLibrary(VerifyLibraryTask {});
+    let spec = ScheduleSpec::Interval(3600);
+
+    let id = create_schedule(&database, "Verify", &payload, &spec)
+        .await
+        .unwrap()
+        .unwrap();
+    // Installing the same schedule again keeps the existing one
+    assert!(create_schedule(&database, "Verify", &payload, &spec)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(claim_due_schedules(&database).await.unwrap().is_empty());
+
+    // A schedule missed several times runs once, then waits for its next run from now
+    let now = chrono::Utc::now().timestamp();
+    database.set_schedule_enabled(id, true, now - 3 * 3600).await.unwrap();
+    let payloads = claim_due_schedules(&database).await.unwrap();
+    assert_eq!(payloads.len(), 1);
+    assert_eq!(payloads[0].name(), "VerifyLibrary");
+    let schedule = database.get_schedule(id).await.unwrap();
+    assert!(schedule.last_run_at.unwrap() >= now);
+    assert!(schedule.next_run_at >= now + 3600);
+    assert!(claim_due_schedules(&database).await.unwrap().is_empty());
+    assert_eq!(database.get_next_schedule_run_at().await.unwrap(), Some(schedule.next_run_at));
+
+    // The run can only be claimed once
+    assert!(!database
+        .claim_schedule_run(id, now - 3 * 3600, now + 3600)
+        .await
+        .unwrap());
+
+    // Disabled schedules are not run
+    database.set_schedule_enabled(id, false, now - 60).await.unwrap();
+    assert!(claim_due_schedules(&database).await.unwrap().is_empty());
+    assert!(database.get_next_schedule_run_at().await.unwrap().is_none());
+    assert_eq!(database.get_schedules().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+pub async fn test_database_backup() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let dir = TempDir::new("selectia-backup").unwrap();
+    let directory = dir.path().join("backups");
+
+    let mut paths = vec![];
+    for _ in 0..4 {
+        paths.push(backup_database(&database, &directory, 2).await.unwrap());
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+    // Only the most recent ones are kept
+    assert_eq!(list_backups(&directory).await.unwrap(), paths[2..].to_vec());
+    assert!(std::fs::metadata(&paths[3]).unwrap().len() > 0);
+}
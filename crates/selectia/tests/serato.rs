@@ -0,0 +1,136 @@
+use std::path::Path;
+
+use common::Library;
+use selectia::{
+    prelude::*,
+    tasks::selection::ExportSelection,
+    tasks::serato::crate_file::{crate_file_name, track_path, SeratoCrate},
+    tasks::serato::id3::{replace_geob_frames, GeobFrame},
+    tasks::serato::markers::*,
+};
+
+mod common;
+
+/// Bytes of an annotated hex dump, `#` starting a comment
+fn fixture(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/serato").join(name);
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| line.split('#').next().unwrap())
+        .flat_map(|line| line.split_whitespace())
+        .map(|byte| u8::from_str_radix(byte, 16).unwrap())
+        .collect()
+}
+
+async fn library() -> Library {
+    let library = Library::new(&[
+        ("Artist A - Matched.mp3", b"matched"),
+        ("sub dir/Café Track.flac", b"ingested"),
+    ])
+    .await;
+    let xml = include_str!("fixtures/rekordbox.xml").replace("{ROOT}", library.root.to_str().unwrap());
+    RekordboxImport::new(library.database(), library.file_loader.clone(), &xml)
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+    library
+}
+
+#[test]
+pub fn test_serato_crate_bytes() {
+    let serato_crate = SeratoCrate {
+        tracks: vec![
+            "Music/sub dir/Café Track.flac".to_string(),
+            "Music/Artist A - Matched.mp3".to_string(),
+        ],
+    };
+    assert_eq!(serato_crate.to_bytes(), fixture("warmup.crate.hex"));
+    assert_eq!(SeratoCrate::parse(&fixture("warmup.crate.hex")).unwrap(), serato_crate);
+
+    assert_eq!(track_path(Path::new("/Users/dj/Music/a.mp3")), "Users/dj/Music/a.mp3");
+    assert_eq!(track_path(Path::new("C:\\Music\\a.mp3")), "Music/a.mp3");
+    assert_eq!(
+        crate_file_name(&["Sets".to_string(), "Warm/up".to_string()]),
+        "Sets%%Warm_up.crate"
+    );
+}
+
+#[test]
+pub fn test_serato_markers2_encoding() {
+    let data = markers2_frame_data(&markers2_payload(None, &[]));
+    assert!(data.starts_with(b"\x01\x01AQFDT0xPUgAAAAAEAP///0JQTUxPQ0sAAAAAAQAA"));
+    assert_eq!(data.len(), 470);
+    assert!(data[2..].iter().all(|byte| byte.is_ascii_alphanumeric() || b"+/\n\0".contains(byte)));
+
+    // Long payloads are split in lines of 72 characters
+    let data = markers2_frame_data(&[0xAB; 200]);
+    let text = std::str::from_utf8(&data[2..]).unwrap().trim_end_matches('\0');
+    let lines = text.split('\n').map(str::len).collect::<Vec<_>>();
+    assert_eq!(lines, vec![72, 72, 72, 52]);
+    assert!(!text.contains('='));
+}
+
+#[test]
+pub fn test_serato_id3_frames() {
+    let frames = [
+        GeobFrame::new(MARKERS2_DESCRIPTION, b"new".to_vec()),
+        GeobFrame::new(BEATGRID_DESCRIPTION, vec![0x01, 0x00]),
+    ];
+    assert_eq!(
+        replace_geob_frames(&fixture("id3_v23.hex"), &frames).unwrap(),
+        fixture("id3_v23_replaced.hex")
+    );
+    // Writing again replaces the frames instead of appending new ones
+    assert_eq!(
+        replace_geob_frames(&fixture("id3_v23_replaced.hex"), &frames).unwrap(),
+        fixture("id3_v23_replaced.hex")
+    );
+    assert_eq!(
+        replace_geob_frames(b"AUDIO", &frames[1..]).unwrap(),
+        fixture("id3_created.hex")
+    );
+    assert!(replace_geob_frames(b"ID3\x02\x00\x00\x00\x00\x00\x00AUDIO", &frames).is_err());
+}
+
+#[tokio::test]
+pub async fn test_serato_export() {
+    let library = library().await;
+    let matched = library.path("Artist A - Matched.mp3");
+    let cafe = library.path("sub dir/Café Track.flac");
+    let export = SeratoExport::new(library.database(), ExportSelection::All);
+
+    let mut crates = export.crates().await.unwrap();
+    crates.sort_by(|a, b| a.0.cmp(&b.0));
+    let names = crates.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["By Location.crate", "Sets%%Warmup.crate", "Sets.crate"]);
+    assert_eq!(crates[1].1.tracks, vec![track_path(&cafe), track_path(&matched)]);
+    assert!(crates[2].1.tracks.is_empty());
+
+    let matched_id = library.database.get_file_by_path(&matched).await.unwrap().unwrap().metadata_id;
+    let frames = export.frames(matched_id).await.unwrap();
+    assert_eq!(
+        frames,
+        vec![
+            GeobFrame::new(MARKERS2_DESCRIPTION, markers2_frame_data(&fixture("markers2.hex"))),
+            GeobFrame::new(BEATGRID_DESCRIPTION, fixture("beatgrid.hex")),
+        ]
+    );
+
+    let serato_dir = library.path("_Serato_");
+    let report = export.write(&serato_dir, true).await.unwrap();
+    assert_eq!(report.crates.len(), 3);
+    let written = std::fs::read(serato_dir.join("Subcrates/Sets%%Warmup.crate")).unwrap();
+    assert_eq!(SeratoCrate::parse(&written).unwrap(), crates[1].1);
+
+    let errors = report
+        .files
+        .iter()
+        .map(|file| (Path::new(&file.path).to_path_buf(), file.error.is_some()))
+        .collect::<Vec<_>>();
+    assert_eq!(errors, vec![(matched.clone(), false), (cafe.clone(), true)]);
+    let tagged = std::fs::read(&matched).unwrap();
+    assert_eq!(tagged, replace_geob_frames(b"matched", &frames).unwrap());
+    assert_eq!(std::fs::read(&cafe).unwrap(), b"ingested");
+}
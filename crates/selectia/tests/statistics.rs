@@ -0,0 +1,64 @@
+use common::Library;
+use selectia::{
+    database::models::TempoMarker,
+    prelude::*,
+    tasks::statistics::{LibraryStatistics, TempoBin, ValueCount},
+};
+use selectia_audio_file::flac::write_flac;
+
+mod common;
+
+fn flac(seconds: usize) -> Vec<u8> {
+    let mut flac = vec![];
+    write_flac(&mut flac, 8000, 1, &vec![0i16; seconds * 8000]).unwrap();
+    flac
+}
+
+#[tokio::test]
+pub async fn test_library_statistics() {
+    let library = Library::new(&[
+        ("a.flac", &flac(2)),
+        ("b.flac", &flac(3)),
+        ("c.mp3", b"unknown duration"),
+    ])
+    .await;
+    let database = library.database();
+    let a = library.load(&library.path("a.flac")).await;
+    let b = library.load(&library.path("b.flac")).await;
+    let c = library.load(&library.path("c.mp3")).await;
+    for (metadata_id, genre, tempo) in [(a, "House", Some("124.00")), (b, "House", None), (c, "Techno", Some("131.50"))] {
+        database.set_metadata_tag_by_tag_name_id(metadata_id, TagName::GENRE_ID, genre.to_string()).await.unwrap();
+        if let Some(tempo) = tempo {
+            database.set_metadata_tag_by_tag_name_id(metadata_id, TagName::TEMPO_ID, tempo.to_string()).await.unwrap();
+        }
+    }
+    let grid = TempoMarker {
+        position: 0.0,
+        bpm: 122.0,
+        meter: None,
+        beat: 1,
+    };
+    database.replace_beat_grid(b, "test", &[grid]).await.unwrap();
+    // Entries without file are not counted
+    let (orphan, _) = database.get_or_create_metadata("no file").await.unwrap();
+    database.set_metadata_tag_by_tag_name_id(orphan.id, TagName::GENRE_ID, "Techno".to_string()).await.unwrap();
+
+    let statistics = LibraryStatistics::compute(&database).await.unwrap();
+    assert_eq!(statistics.tracks, 3);
+    assert!((statistics.total_duration - 5.0).abs() < 0.01, "{}", statistics.total_duration);
+    assert_eq!(statistics.unknown_duration, 1);
+    assert_eq!((statistics.analysed, statistics.unanalysed), (1, 2));
+    assert_eq!(
+        statistics.genres,
+        vec![
+            ValueCount { value: "House".to_string(), count: 2 },
+            ValueCount { value: "Techno".to_string(), count: 1 },
+        ]
+    );
+    assert_eq!(
+        statistics.tempo_histogram,
+        vec![TempoBin { start: 120.0, count: 2 }, TempoBin { start: 130.0, count: 1 }]
+    );
+    assert_eq!(statistics.added_per_week.len(), 1);
+    assert_eq!(statistics.added_per_week[0].count, 3);
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use selectia::{
+    database::models::{TaskHistoryQuery, TaskRun},
+    prelude::*,
+    services::worker::tasks::{TaskPayload, VerifyLibraryTask},
+    test_utils::TmpDatabase,
+};
+
+fn run(task_id: i64, status: &str, duration_ms: i64) -> TaskRun {
+    TaskRun {
+        task_id,
+        name: "VerifyLibrary".to_string(),
+        status: status.to_string(),
+        worker_slot: 0,
+        started_at: chrono::Utc::now().timestamp(),
+        duration_ms,
+        error: (status == "failed").then(|| "Task failed: disk unplugged".to_string()),
+        summary: None,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+pub async fn test_worker_archives_runs() {
+    let theater = OwnedTheaterContext::new().await;
+    let database = TmpDatabase::new().await;
+    theater.register_singleton(database.clone()).await.unwrap();
+    let worker = Worker::spawn(&theater).await.unwrap();
+    theater.ready().await;
+
+    let (callback, resolve) = TaskCallback::new();
+    worker
+        .send(WorkerTask::ScheduleWith {
+            payload: TaskPayload::VerifyLibrary(VerifyLibraryTask {}),
+            options: Default::default(),
+            callback: Some(callback),
+        })
+        .await
+        .unwrap();
+    let task_id = resolve.wait().await.unwrap();
+
+    let mut runs = vec![];
+    for _ in 0..100 {
+        runs = database.get_task_history(&Default::default()).await.unwrap();
+        if !runs.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    // The task is deleted once done, its run is kept with the summary of the verification
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run.task_id, task_id);
+    assert_eq!(run.name, "VerifyLibrary");
+    assert_eq!(run.status, "done");
+    assert_eq!(run.attempt, 1);
+    assert_eq!(run.worker_slot, 0);
+    assert!(run.finished_at >= run.started_at);
+    assert!(run.error.is_none());
+    let summary: serde_json::Value = serde_json::from_str(run.summary.as_deref().unwrap()).unwrap();
+    assert_eq!(summary["missing"], 0);
+    assert!(database.get_task(task_id).await.is_err());
+}
+
+#[tokio::test]
+pub async fn test_task_history_query() {
+    let database = TmpDatabase::new().await;
+    let payload = serde_json::to_string(&TaskPayload::VerifyLibrary(VerifyLibraryTask {})).unwrap();
+    let task_id = database.create_task(payload).await.unwrap();
+
+    // Each attempt is archived with its error
+    database.archive_task_run(&run(task_id, "failed", 100)).await.unwrap();
+    database.fail_task(task_id, "Task failed: disk unplugged", 0, 0).await.unwrap();
+    database.archive_task_run(&run(task_id, "done", 300)).await.unwrap();
+    database.archive_task_run(&run(task_id, "done", 500)).await.unwrap();
+
+    let runs = database.get_task_history(&Default::default()).await.unwrap();
+    assert_eq!(runs.len(), 3);
+    // Most recent first
+    assert_eq!(runs[0].duration_ms, 500);
+    assert_eq!(runs[2].attempt, 1);
+    assert_eq!(runs[1].attempt, 2);
+
+    let failed = database
+        .get_task_history(&TaskHistoryQuery {
+            status: Some("failed".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].error.as_deref(), Some("Task failed: disk unplugged"));
+    let page = database
+        .get_task_history(&TaskHistoryQuery {
+            limit: Some(1),
+            offset: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(page[0].id, runs[1].id);
+    assert!(database
+        .get_task_history(&TaskHistoryQuery {
+            name: Some("StemExtraction".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .is_empty());
+    let future = chrono::Utc::now().timestamp() + 60;
+    assert!(database
+        .get_task_history(&TaskHistoryQuery {
+            since: Some(future),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Durations only account for the successful runs
+    let stats = database.get_task_history_stats(None).await.unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].name, "VerifyLibrary");
+    assert_eq!(stats[0].runs, 3);
+    assert_eq!(stats[0].failed, 1);
+    assert_eq!(stats[0].cancelled, 0);
+    assert_eq!(stats[0].average_duration_ms, Some(400.0));
+    assert_eq!(stats[0].max_duration_ms, Some(500));
+
+    // Only the most recent runs are kept
+    assert_eq!(database.prune_task_history(24 * 60 * 60, 2).await.unwrap(), 1);
+    let kept = database.get_task_history(&Default::default()).await.unwrap();
+    assert_eq!(kept.iter().map(|run| run.id).collect::<Vec<_>>(), vec![runs[0].id, runs[1].id]);
+    assert_eq!(database.prune_task_history(24 * 60 * 60, 2).await.unwrap(), 0);
+}
@@ -0,0 +1,148 @@
+use std::path::{Path, PathBuf};
+
+use common::Library;
+use selectia::{
+    database::models::CuePoint,
+    prelude::*,
+    tasks::reconciliation::{ImportReport, ReconciliationStatus},
+    tasks::selection::ExportSelection,
+    tasks::traktor::nml::{musical_key_value, Location},
+};
+
+mod common;
+
+const FIXTURE: &str = include_str!("fixtures/collection.nml");
+
+async fn library() -> Library {
+    Library::new(&[
+        ("Artist A - Matched.mp3", b"matched"),
+        ("sub dir/Café Track.flac", b"ingested"),
+        ("moved.mp3", b"moved content"),
+        ("old/moved.mp3", b"moved content"),
+    ])
+    .await
+}
+
+async fn import(library: &Library) -> ImportReport {
+    // `DIR` of the files at the root of the library
+    let dir = Location::from_path(&library.path("file")).dir;
+    import_nml(library, &FIXTURE.replace("{DIR}", &dir)).await
+}
+
+async fn import_nml(library: &Library, nml: &str) -> ImportReport {
+    TraktorImport::new(library.database(), library.file_loader.clone(), nml)
+        .unwrap()
+        .run()
+        .await
+        .unwrap()
+}
+
+#[test]
+pub fn test_traktor_location() {
+    let location = Location::from_path(Path::new("/Users/dj/Music/a b.mp3"));
+    assert_eq!(location.dir, "/:Users/:dj/:Music/:");
+    assert_eq!(location.file, "a b.mp3");
+    assert_eq!(location.primary_key(), "/:Users/:dj/:Music/:a b.mp3");
+    assert_eq!(location.to_path(), PathBuf::from("/Users/dj/Music/a b.mp3"));
+
+    let location = Location::from_path(Path::new("C:/Music/a.mp3"));
+    assert_eq!((location.volume.as_str(), location.dir.as_str()), ("C:", "/:Music/:"));
+    assert_eq!(location.to_path(), PathBuf::from("C:/Music/a.mp3"));
+}
+
+#[test]
+pub fn test_traktor_musical_key() {
+    assert_eq!(musical_key_value("Am"), Some(21));
+    assert_eq!(musical_key_value("1m"), Some(21));
+    assert_eq!(musical_key_value("8A"), Some(21));
+    assert_eq!(musical_key_value("1d"), Some(0));
+    assert_eq!(musical_key_value("8B"), Some(0));
+    assert_eq!(musical_key_value("12A"), Some(13));
+    assert_eq!(musical_key_value("H"), None);
+}
+
+#[tokio::test]
+pub async fn test_traktor_import() {
+    let library = library().await;
+    let matched_id = library.load(&library.path("Artist A - Matched.mp3")).await;
+    let moved_id = library.load(&library.path("old/moved.mp3")).await;
+
+    let report = import(&library).await;
+    let statuses = report.tracks.iter().map(|track| track.status).collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        vec![
+            ReconciliationStatus::Matched,
+            ReconciliationStatus::Ingested,
+            ReconciliationStatus::MatchedByHash,
+            ReconciliationStatus::Missing,
+        ]
+    );
+    assert_eq!(report.tracks[0].metadata_id, Some(matched_id));
+    assert_eq!(report.tracks[2].metadata_id, Some(moved_id));
+
+    assert_eq!(library.tag_values(matched_id, TagName::TITLE_ID).await, vec!["Matched Track"]);
+    assert_eq!(library.tag_values(matched_id, TagName::ALBUM_ID).await, vec!["First Album"]);
+    assert_eq!(library.tag_values(matched_id, TagName::KEY_ID).await, vec!["1m"]);
+    assert_eq!(library.tag_values(matched_id, TagName::TEMPO_ID).await, vec!["124"]);
+    assert_eq!(library.tag_values(matched_id, TagName::RATING_ID).await, vec!["5"]);
+    assert_eq!(library.tag_values(matched_id, TagName::COLOR_ID).await, vec!["#ff8000"]);
+
+    let cues = library
+        .database
+        .get_cues(matched_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(CuePoint::from)
+        .collect::<Vec<_>>();
+    assert_eq!(cues.len(), 3);
+    assert_eq!(cues[0].hot_cue_index, None);
+    assert_eq!(cues[0].name, None);
+    assert_eq!((cues[1].start_time, cues[1].name.as_deref()), (64.541, Some("Drop")));
+    assert_eq!(cues[2].kind, "loop");
+    assert!((cues[2].end_time.unwrap() - 103.767).abs() < 1e-9);
+    let grid = library.database.get_beat_grid(matched_id).await.unwrap();
+    assert_eq!(grid.len(), 1);
+    assert_eq!((grid[0].position, grid[0].bpm), (0.025, 124.0));
+
+    let ingested_id = report.tracks[1].metadata_id.unwrap();
+    assert_eq!(library.tag_values(ingested_id, TagName::KEY_ID).await, vec!["Fm"]);
+    assert_eq!(library.tag_values(ingested_id, TagName::RATING_ID).await, Vec::<String>::new());
+
+    let tree = library.playlist_tree().await;
+    let warmup = tree
+        .iter()
+        .find(|(path, _)| path == &vec!["Sets".to_string(), "Warmup".to_string()])
+        .unwrap();
+    assert_eq!(
+        warmup.1,
+        vec![
+            library.path("sub dir/Café Track.flac").to_string_lossy().to_string(),
+            library.path("Artist A - Matched.mp3").to_string_lossy().to_string(),
+        ]
+    );
+    let warmup_report = report.playlists.iter().find(|p| p.path.len() == 2).unwrap();
+    assert_eq!((warmup_report.entries, warmup_report.missing_entries), (2, 1));
+}
+
+#[tokio::test]
+pub async fn test_traktor_export_round_trip() {
+    let library = library().await;
+    import(&library).await;
+    let nml = TraktorExport::new(library.database(), ExportSelection::All)
+        .build()
+        .await
+        .unwrap()
+        .to_xml()
+        .unwrap();
+
+    let copy = Library::open(library.root.clone()).await;
+    let report = import_nml(&copy, &nml).await;
+    assert_eq!(report.count(ReconciliationStatus::Failed), 0);
+    assert_eq!(report.count(ReconciliationStatus::Missing), 0);
+    for relative in ["Artist A - Matched.mp3", "sub dir/Café Track.flac", "moved.mp3"] {
+        assert_eq!(library.snapshot(relative).await, copy.snapshot(relative).await);
+    }
+    assert_eq!(library.playlist_tree().await, copy.playlist_tree().await);
+}
@@ -0,0 +1,289 @@
+// The running tasks are blocked on named pipes
+#![cfg(unix)]
+
+use std::{fs::OpenOptions, io::Write, path::PathBuf, process::Command, time::Duration};
+
+use selectia::{
+    prelude::*,
+    services::worker::{
+        tasks::{FingerprintTask, ResourceLimits, TaskPayload, TaskProgress, TaskStatus},
+        WorkerConcurrency, WorkerDatabaseExt, WorkerEvent,
+    },
+    test_utils::TmpDatabase,
+};
+use selectia_audio_file::audio_file::AudioFilePayload;
+use tempdir::TempDir;
+use tokio::sync::mpsc::{channel, Receiver};
+
+struct TestWorker {
+    dir: TempDir,
+    _theater: OwnedTheaterContext,
+    database: TmpDatabase,
+    file_loader: AddressableService<FileLoaderTask>,
+    worker: AddressableService<WorkerTask>,
+    events: Receiver<WorkerEvent>,
+}
+
+impl TestWorker {
+    async fn new(concurrency: WorkerConcurrency) -> Self {
+        let dir = TempDir::new("selectia-worker").unwrap();
+        let theater = OwnedTheaterContext::new().await;
+        let database = TmpDatabase::new().await;
+        theater.register_singleton(database.clone()).await.unwrap();
+        theater.register_singleton(concurrency).await.unwrap();
+        let file_loader = FileLoader::spawn(&theater).await.unwrap();
+        let worker = Worker::spawn(&theater).await.unwrap();
+        theater.ready().await;
+        let (sender, events) = channel(1024);
+        theater
+            .get_singleton_dispatcher::<Worker, WorkerEvent>()
+            .await
+            .unwrap()
+            .register(sender)
+            .await;
+        Self {
+            dir,
+            _theater: theater,
+            database,
+            file_loader,
+            worker,
+            events,
+        }
+    }
+
+    /// Library file whose reads block until it is released, `seconds` keeps the files distinct
+    async fn blocking_file(&self, seconds: usize) -> BlockingFile {
+        let path = self.dir.path().join(format!("{}.wav", seconds));
+        AudioFilePayload::from_interleaved_samples(44100.0, 2, vec![0.0; 44100 * 2 * seconds])
+            .unwrap()
+            .wav_export(44100, &path)
+            .unwrap();
+        let (callback, resolve) = TaskCallback::new();
+        self.file_loader
+            .send(FileLoaderTask::LoadFile {
+                path: path.clone(),
+                callback: Some(callback),
+            })
+            .await
+            .unwrap();
+        let metadata_id = resolve.wait().await.unwrap();
+        let content = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(Command::new("mkfifo").arg(&path).status().unwrap().success());
+        BlockingFile {
+            path,
+            content,
+            metadata_id,
+        }
+    }
+
+    async fn fingerprint(&self, file: &BlockingFile) -> i64 {
+        let (callback, resolve) = TaskCallback::new();
+        self.worker
+            .send(WorkerTask::ScheduleWith {
+                payload: TaskPayload::Fingerprint(FingerprintTask {
+                    metadata_id: file.metadata_id,
+                }),
+                options: Default::default(),
+                callback: Some(callback),
+            })
+            .await
+            .unwrap();
+        resolve.wait().await.unwrap()
+    }
+
+    /// Ids of the tasks being processed, sorted
+    async fn running_tasks(&self) -> Vec<i64> {
+        let (callback, resolve) = TaskCallback::new();
+        self.worker.send(WorkerTask::RunningTasks { callback }).await.unwrap();
+        let mut ids = resolve.wait().await.unwrap();
+        ids.sort();
+        ids
+    }
+
+    /// Next update of the task `id`, the other events are skipped
+    async fn next_update(&mut self, id: i64) -> TaskStatus {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(30), self.events.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if let WorkerEvent::QueueTaskUpdated { id: updated, status, .. } = event {
+                if updated == id {
+                    return status;
+                }
+            }
+        }
+    }
+
+    /// Next progress event as (id, step, progress, eta)
+    async fn next_progress(&mut self) -> (i64, String, f64, Option<f64>) {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(30), self.events.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if let WorkerEvent::QueueTaskProgress { id, step, progress, eta } = event {
+                return (id, step, progress, eta);
+            }
+        }
+    }
+}
+
+/// Audio file replaced by a named pipe
+struct BlockingFile {
+    path: PathBuf,
+    content: Vec<u8>,
+    metadata_id: i64,
+}
+
+impl BlockingFile {
+    /// Unblock the reader
+    fn release(&self) {
+        // Unlike a write only open, opening the pipe for reading and writing does not wait for a reader
+        let mut pipe = OpenOptions::new().read(true).write(true).open(&self.path).unwrap();
+        let content = self.content.clone();
+        // The reader may stop before the end of the content once it decoded enough
+        std::thread::spawn(move || pipe.write_all(&content));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+pub async fn test_cancel_running_task() {
+    let mut worker = TestWorker::new(WorkerConcurrency::default()).await;
+    let file = worker.blocking_file(1).await;
+    let id = worker.fingerprint(&file).await;
+    assert!(matches!(worker.next_update(id).await, TaskStatus::Processing));
+
+    // The task is stopped while it waits for its file
+    worker.worker.send(WorkerTask::Cancel { id }).await.unwrap();
+    assert!(matches!(worker.next_update(id).await, TaskStatus::Cancelled));
+    file.release();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+pub async fn test_task_progress() {
+    let mut worker = TestWorker::new(WorkerConcurrency::default()).await;
+    let progress = TaskProgress::new(42, worker.worker.clone());
+
+    // Nothing is estimated before the step progressed
+    progress.report("Decoding", 0.0).await.unwrap();
+    assert_eq!(worker.next_progress().await, (42, "Decoding".to_string(), 0.0, None));
+
+    // Half of the step took the elapsed time, the other half should take as long
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    progress.report("Decoding", 0.5).await.unwrap();
+    let (_, _, value, eta) = worker.next_progress().await;
+    assert_eq!(value, 0.5);
+    let eta = eta.unwrap();
+    assert!((0.3..5.0).contains(&eta), "{}", eta);
+
+    // Out of range values are clamped
+    progress.report("Decoding", 1.5).await.unwrap();
+    assert_eq!(worker.next_progress().await, (42, "Decoding".to_string(), 1.0, Some(0.0)));
+    progress.report("Decoding", -0.5).await.unwrap();
+    assert_eq!(worker.next_progress().await, (42, "Decoding".to_string(), 0.0, None));
+
+    // A new step starts its own estimation
+    progress.report("Separating", 0.5).await.unwrap();
+    let (_, step, _, step_eta) = worker.next_progress().await;
+    assert_eq!(step, "Separating");
+    assert!(step_eta.unwrap() < 0.3);
+
+    // Values sent to a forwarding channel or from a blocking thread are reported as well
+    let sender = progress.forward("Forwarded");
+    sender.send(0.25).unwrap();
+    let (id, step, value, _) = worker.next_progress().await;
+    assert_eq!((id, step.as_str(), value), (42, "Forwarded", 0.25));
+    let blocking = progress.clone();
+    tokio::task::spawn_blocking(move || blocking.blocking_report("Forwarded", 0.75).unwrap())
+        .await
+        .unwrap();
+    let (_, step, value, _) = worker.next_progress().await;
+    assert_eq!((step.as_str(), value), ("Forwarded", 0.75));
+}
+
+/// Pool of `pool_size` slots running up to `analysis` analysis tasks
+fn concurrency(pool_size: usize, analysis: usize) -> WorkerConcurrency {
+    WorkerConcurrency {
+        pool_size,
+        limits: ResourceLimits {
+            analysis,
+            ..Default::default()
+        },
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+pub async fn test_worker_concurrency() {
+    let mut worker = TestWorker::new(concurrency(3, 1)).await;
+    let mut files = vec![];
+    let mut ids = vec![];
+    for seconds in 1..=4 {
+        let file = worker.blocking_file(seconds).await;
+        ids.push(worker.fingerprint(&file).await);
+        files.push(file);
+    }
+
+    // The class limit applies even though the pool has empty slots
+    assert!(matches!(worker.next_update(ids[0]).await, TaskStatus::Processing));
+    assert_eq!(worker.running_tasks().await, ids[..1]);
+
+    // Growing the class limit fills the pool, the pool size still applies
+    worker.worker.send(WorkerTask::SetConcurrency(concurrency(3, 4))).await.unwrap();
+    assert!(matches!(worker.next_update(ids[1]).await, TaskStatus::Processing));
+    assert!(matches!(worker.next_update(ids[2]).await, TaskStatus::Processing));
+    assert_eq!(worker.running_tasks().await, ids[..3]);
+
+    // Shrinking lets the running tasks finish, no task starts until they are below the new limits
+    worker.worker.send(WorkerTask::SetConcurrency(concurrency(2, 1))).await.unwrap();
+    assert_eq!(worker.running_tasks().await, ids[..3]);
+    for (idx, file) in files[..3].iter().enumerate() {
+        file.release();
+        assert!(matches!(worker.next_update(ids[idx]).await, TaskStatus::Done));
+        match idx {
+            2 => assert_eq!(worker.running_tasks().await, ids[3..]),
+            _ => assert_eq!(worker.running_tasks().await, ids[idx + 1..3]),
+        }
+    }
+    files[3].release();
+    assert!(matches!(worker.next_update(ids[3]).await, TaskStatus::Processing));
+    assert!(matches!(worker.next_update(ids[3]).await, TaskStatus::Done));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+pub async fn test_retry_failed_task() {
+    // Nothing is dequeued by the worker
+    let mut worker = TestWorker::new(concurrency(0, 0)).await;
+    let database = worker.database.clone();
+    let id = database
+        .enqueue_task(TaskPayload::Fingerprint(FingerprintTask { metadata_id: 1 }))
+        .await
+        .unwrap();
+    let retry = |id| {
+        let worker = worker.worker.clone();
+        async move {
+            let (callback, resolve) = TaskCallback::new();
+            worker
+                .send(WorkerTask::Retry {
+                    id,
+                    callback: Some(callback),
+                })
+                .await
+                .unwrap();
+            resolve.wait().await.unwrap()
+        }
+    };
+
+    // A task that did not fail is not retried
+    assert!(retry(id).await.is_empty());
+
+    let mut task = database.get_task(id).await.unwrap();
+    while task.status == "queued" {
+        database.dequeue_task().await.unwrap().unwrap();
+        task = database.fail_task(id, "failed", 0, 0).await.unwrap();
+    }
+    assert_eq!(retry(id).await, vec![id]);
+    assert!(matches!(worker.next_update(id).await, TaskStatus::Queued));
+    assert_eq!(database.get_task(id).await.unwrap().status, "queued");
+}
@@ -0,0 +1,292 @@
+use common::Library;
+use selectia::database::Database;
+use selectia::services::worker::{
+    tasks::{
+        AnalysisTask, FingerprintTask, ResourceClass, StemExtractionTask, TaskOptions, TaskPayload, TaskPriority,
+        TaskStatus, VerifyLibraryTask,
+    },
+    WorkerDatabaseExt,
+};
+use selectia::tasks::analysis::AnalysisStage;
+
+mod common;
+
+#[tokio::test]
+pub async fn test_task_retry() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let payload = serde_json::to_string(&TaskPayload::VerifyLibrary(VerifyLibraryTask {})).unwrap();
+    let delayed = database.create_task(payload.clone()).await.unwrap();
+
+    // A failure puts the task back in the queue, it isn't dequeued before its backoff
+    assert_eq!(database.dequeue_task().await.unwrap().unwrap().id, delayed);
+    let task = database.fail_task(delayed, "first", 30, 3600).await.unwrap();
+    assert_eq!(task.status, "queued");
+    assert_eq!(task.attempts, 1);
+    assert_eq!(task.last_error.as_deref(), Some("first"));
+    assert!(database.dequeue_task().await.unwrap().is_none());
+    assert_eq!(database.get_next_task_run_at().await.unwrap(), task.next_run_at);
+
+    // Without backoff the task runs again until it runs out of attempts
+    let id = database.create_task(payload).await.unwrap();
+    let mut task = database.get_task(id).await.unwrap();
+    while task.status == "queued" {
+        assert_eq!(database.dequeue_task().await.unwrap().unwrap().id, id);
+        task = database.fail_task(id, "again", 0, 0).await.unwrap();
+    }
+    assert!(matches!(TaskStatus::try_from(task.status.as_str()), Ok(TaskStatus::Failed)));
+    assert_eq!(task.attempts, task.max_attempts);
+    assert!(database.dequeue_task().await.unwrap().is_none());
+    assert_eq!(database.get_failed_tasks().await.unwrap().len(), 1);
+
+    assert_eq!(database.retry_failed_task(id).await.unwrap(), vec![id]);
+    assert!(database.retry_failed_task(id).await.unwrap().is_empty());
+    let task = database.dequeue_task().await.unwrap().unwrap();
+    assert_eq!(task.attempts, 0);
+    assert!(database.get_failed_tasks().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+pub async fn test_cancel_pending_task() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let payload = serde_json::to_string(&TaskPayload::VerifyLibrary(VerifyLibraryTask {})).unwrap();
+    let queued = database.create_task(payload.clone()).await.unwrap();
+    let processing = database.create_task(payload).await.unwrap();
+    assert_eq!(database.dequeue_task().await.unwrap().unwrap().id, queued);
+    assert_eq!(database.dequeue_task().await.unwrap().unwrap().id, processing);
+    database.requeue_task(queued).await.unwrap();
+
+    // Processing tasks are stopped by the worker, not removed from the queue
+    assert_eq!(database.delete_pending_task(queued).await.unwrap(), vec![queued]);
+    assert!(database.delete_pending_task(queued).await.unwrap().is_empty());
+    assert!(database.delete_pending_task(processing).await.unwrap().is_empty());
+    let tasks = database.get_tasks().await.unwrap();
+    assert_eq!(tasks.iter().map(|t| t.id).collect::<Vec<_>>(), vec![processing]);
+}
+
+#[tokio::test]
+pub async fn test_task_priority_and_dependencies() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let mut metadata_id = 0;
+    let mut enqueue = |priority, depends_on: Vec<i64>| {
+        let database = database.clone();
+        metadata_id += 1;
+        let task = TaskPayload::Fingerprint(FingerprintTask { metadata_id });
+        async move {
+            let options = TaskOptions {
+                priority,
+                depends_on,
+                ..Default::default()
+            };
+            database.enqueue_task_with(task, &options).await.unwrap()
+        }
+    };
+
+    let done = enqueue(TaskPriority::Normal, vec![]).await;
+    database.delete_task(done).await.unwrap();
+    let bulk = enqueue(TaskPriority::Background, vec![]).await;
+    let normal = enqueue(TaskPriority::Normal, vec![]).await;
+    let prerequisite = enqueue(TaskPriority::Normal, vec![]).await;
+    // Dependencies already done are ignored
+    let dependent = enqueue(TaskPriority::User, vec![prerequisite, done]).await;
+    let user = enqueue(TaskPriority::User, vec![]).await;
+    assert_eq!(database.get_task_dependencies(dependent).await.unwrap(), vec![prerequisite]);
+
+    let mut order = vec![];
+    while let Some(task) = database.dequeue_task().await.unwrap() {
+        order.push(task.id);
+        if task.id == prerequisite {
+            database.delete_task(prerequisite).await.unwrap();
+        }
+    }
+    assert_eq!(order, vec![user, normal, prerequisite, dependent, bulk]);
+}
+
+/// Chain of fingerprint tasks, each one waiting for the previous one
+async fn enqueue_chain(database: &Database, length: i64) -> Vec<i64> {
+    let mut ids: Vec<i64> = vec![];
+    for metadata_id in 1..=length {
+        let options = TaskOptions {
+            depends_on: ids.last().copied().into_iter().collect(),
+            ..Default::default()
+        };
+        let task = TaskPayload::Fingerprint(FingerprintTask { metadata_id });
+        ids.push(database.enqueue_task_with(task, &options).await.unwrap());
+    }
+    ids
+}
+
+#[tokio::test]
+pub async fn test_failed_task_dependents() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let chain = enqueue_chain(&database, 3).await;
+    let independent = database.enqueue_task(TaskPayload::VerifyLibrary(VerifyLibraryTask {})).await.unwrap();
+
+    // A retried failure keeps the dependents waiting
+    assert_eq!(database.dequeue_task().await.unwrap().unwrap().id, chain[0]);
+    database.fail_task(chain[0], "first", 0, 0).await.unwrap();
+    assert_eq!(database.get_task(chain[1]).await.unwrap().status, "queued");
+
+    // Once it ran out of attempts the tasks waiting for it fail too and leave their dedupe key
+    let mut task = database.get_task(chain[0]).await.unwrap();
+    while task.status == "queued" {
+        assert_eq!(database.dequeue_task().await.unwrap().unwrap().id, chain[0]);
+        task = database.fail_task(chain[0], "again", 0, 0).await.unwrap();
+    }
+    assert_eq!(task.status, "failed");
+    assert_eq!(database.get_task_dependents(chain[0]).await.unwrap(), chain[1..].to_vec());
+    for id in chain[1..].iter() {
+        let dependent = database.get_task(*id).await.unwrap();
+        assert_eq!(dependent.status, "failed");
+        assert_eq!(dependent.last_error, Some(format!("Dependency {} failed", chain[0])));
+    }
+    let fingerprint = TaskPayload::Fingerprint(FingerprintTask { metadata_id: 2 });
+    assert!(!chain.contains(&database.enqueue_task(fingerprint.clone()).await.unwrap()));
+    assert_eq!(database.dequeue_task().await.unwrap().unwrap().id, independent);
+    let requeued = database.dequeue_task().await.unwrap().unwrap().id;
+    assert!(database.dequeue_task().await.unwrap().is_none());
+
+    // Retrying any task of the chain puts the whole chain back in the queue
+    assert_eq!(database.retry_failed_task(chain[2]).await.unwrap(), chain);
+    database.delete_task(requeued).await.unwrap();
+    let mut order = vec![];
+    while let Some(task) = database.dequeue_task().await.unwrap() {
+        order.push(task.id);
+        database.delete_task(task.id).await.unwrap();
+    }
+    assert_eq!(order, chain);
+}
+
+#[tokio::test]
+pub async fn test_cancelled_task_dependents() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+
+    // The tasks waiting for a cancelled pending task never run
+    let chain = enqueue_chain(&database, 3).await;
+    assert_eq!(database.delete_pending_task(chain[1]).await.unwrap(), chain[1..].to_vec());
+    assert_eq!(database.get_tasks().await.unwrap().len(), 1);
+    assert!(database.get_task_dependents(chain[0]).await.unwrap().is_empty());
+
+    // Same for a task stopped while processing
+    let options = TaskOptions {
+        depends_on: vec![chain[0]],
+        ..Default::default()
+    };
+    let task = TaskPayload::Fingerprint(FingerprintTask { metadata_id: 4 });
+    let dependent = database.enqueue_task_with(task, &options).await.unwrap();
+    assert_eq!(database.dequeue_task().await.unwrap().unwrap().id, chain[0]);
+    assert_eq!(
+        database.delete_task_with_dependents(chain[0]).await.unwrap(),
+        vec![chain[0], dependent]
+    );
+    assert!(database.dequeue_task().await.unwrap().is_none());
+}
+
+#[tokio::test]
+pub async fn test_task_resource_classes() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let io = database.enqueue_task(TaskPayload::VerifyLibrary(VerifyLibraryTask {})).await.unwrap();
+    let analysis = database
+        .enqueue_task(TaskPayload::Fingerprint(FingerprintTask { metadata_id: 1 }))
+        .await
+        .unwrap();
+    assert_eq!(database.get_task(io).await.unwrap().resource_class, ResourceClass::Io.as_str());
+
+    // Tasks of a class at its limit wait while the other classes keep running
+    let full = [ResourceClass::Io.as_str()];
+    assert_eq!(database.dequeue_task_excluding(&full).await.unwrap().unwrap().id, analysis);
+    assert!(database.dequeue_task_excluding(&full).await.unwrap().is_none());
+    assert_eq!(database.dequeue_task_excluding(&[]).await.unwrap().unwrap().id, io);
+}
+
+#[tokio::test]
+pub async fn test_task_dedupe() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let fingerprint = |metadata_id| TaskPayload::Fingerprint(FingerprintTask { metadata_id });
+
+    let first = database.enqueue_task(fingerprint(1)).await.unwrap();
+    assert_eq!(database.enqueue_task(fingerprint(1)).await.unwrap(), first);
+    let other = database.enqueue_task(fingerprint(2)).await.unwrap();
+    assert_ne!(other, first);
+
+    // Superseding a queued task deletes it, its dependents wait for the new one
+    let dependent = database
+        .enqueue_task_with(fingerprint(3), &TaskOptions { depends_on: vec![first], ..Default::default() })
+        .await
+        .unwrap();
+    let supersede = TaskOptions {
+        supersede: true,
+        ..Default::default()
+    };
+    let second = database.enqueue_task_with(fingerprint(1), &supersede).await.unwrap();
+    assert_ne!(second, first);
+    assert!(database.get_task(first).await.is_err());
+    assert_eq!(database.get_task_dependencies(dependent).await.unwrap(), vec![second]);
+
+    // A processing task is kept, the worker stops it
+    while database.dequeue_task().await.unwrap().is_some() {}
+    assert_eq!(database.enqueue_task(fingerprint(1)).await.unwrap(), second);
+    let third = database.enqueue_task_with(fingerprint(1), &supersede).await.unwrap();
+    assert_eq!(database.get_task(second).await.unwrap().status, "processing");
+    assert_eq!(database.enqueue_task(fingerprint(1)).await.unwrap(), third);
+
+    // Tasks done or failed are not reused
+    database.delete_task(third).await.unwrap();
+    database.delete_task(second).await.unwrap();
+    assert_ne!(database.enqueue_task(fingerprint(1)).await.unwrap(), third);
+}
+
+#[tokio::test]
+pub async fn test_task_dedupe_options() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+    let fingerprint = |metadata_id| TaskPayload::Fingerprint(FingerprintTask { metadata_id });
+    let id = database
+        .enqueue_task_with(fingerprint(1), &TaskOptions::priority(TaskPriority::Background))
+        .await
+        .unwrap();
+    let dependency = database.enqueue_task(fingerprint(2)).await.unwrap();
+
+    // The queued task takes the priority and the dependencies of the new request
+    let options = TaskOptions {
+        priority: TaskPriority::User,
+        depends_on: vec![dependency],
+        ..Default::default()
+    };
+    assert_eq!(database.enqueue_task_with(fingerprint(1), &options).await.unwrap(), id);
+    assert_eq!(database.get_task(id).await.unwrap().priority, TaskPriority::User as i64);
+    assert_eq!(database.get_task_dependencies(id).await.unwrap(), vec![dependency]);
+
+    // A lower priority request does not lower it
+    let background = TaskOptions::priority(TaskPriority::Background);
+    assert_eq!(database.enqueue_task_with(fingerprint(1), &background).await.unwrap(), id);
+    assert_eq!(database.get_task(id).await.unwrap().priority, TaskPriority::User as i64);
+}
+
+#[tokio::test]
+pub async fn test_stem_extraction_dedupe() {
+    let library = Library::new(&[]).await;
+    let database = library.database();
+
+    // The stems stage of the pipeline and a stem extraction requested by the user are the same task
+    let extraction = database
+        .enqueue_task(TaskPayload::StemExtraction(StemExtractionTask { metadata_id: 1 }))
+        .await
+        .unwrap();
+    let stage = TaskPayload::Analysis(AnalysisTask {
+        metadata_id: 1,
+        stage: AnalysisStage::Stems,
+    });
+    assert_eq!(database.enqueue_task(stage).await.unwrap(), extraction);
+    let other = TaskPayload::Analysis(AnalysisTask {
+        metadata_id: 1,
+        stage: AnalysisStage::Key,
+    });
+    assert_ne!(database.enqueue_task(other).await.unwrap(), extraction);
+}
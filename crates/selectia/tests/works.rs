@@ -0,0 +1,75 @@
+use common::Library;
+use selectia::{
+    prelude::*,
+    tasks::works::{split_version, WorkGrouping, WorkVersion},
+};
+
+mod common;
+
+#[test]
+pub fn test_split_version() {
+    for (title, expected) in [
+        ("Song (Extended Mix)", ("Song", Some("Extended Mix"))),
+        ("Song [Radio Edit]", ("Song", Some("Radio Edit"))),
+        ("Song - Someone Remix", ("Song", Some("Someone Remix"))),
+        ("Song (feat. Someone)", ("Song (feat. Someone)", None)),
+        ("Song - Part 2", ("Song - Part 2", None)),
+        ("Song", ("Song", None)),
+    ] {
+        let (title, version) = split_version(title);
+        assert_eq!((title.as_str(), version.as_deref()), expected);
+    }
+}
+
+#[tokio::test]
+pub async fn test_work_grouping() {
+    let library = Library::new(&[
+        ("original.mp3", b"original"),
+        ("extended.mp3", b"extended"),
+        ("remix.mp3", b"remix"),
+        ("other.mp3", b"other"),
+    ])
+    .await;
+    let database = library.database();
+    let mut ids = vec![];
+    for (name, artist, title) in [
+        ("original.mp3", "Artist", "Song"),
+        ("extended.mp3", "ARTIST feat. Singer", "Song (Extended Mix)"),
+        ("remix.mp3", "Artist", "Song - Someone Remix"),
+        ("other.mp3", "Artist", "Other Song"),
+    ] {
+        let id = library.load(&library.path(name)).await;
+        database.set_metadata_tag_by_tag_name_id(id, TagName::ARTIST_ID, artist.to_string()).await.unwrap();
+        database.set_metadata_tag_by_tag_name_id(id, TagName::TITLE_ID, title.to_string()).await.unwrap();
+        ids.push(id);
+    }
+
+    let grouping = WorkGrouping::new(database.clone());
+    let suggestions = grouping.suggest().await.unwrap();
+    assert_eq!(suggestions.len(), 1);
+    let suggestion = &suggestions[0];
+    assert_eq!((suggestion.artist.as_str(), suggestion.title.as_str()), ("Artist", "Song"));
+    assert_eq!(
+        suggestion.versions,
+        vec![
+            WorkVersion { metadata_id: ids[0], version: "Original".to_string() },
+            WorkVersion { metadata_id: ids[1], version: "Extended Mix".to_string() },
+            WorkVersion { metadata_id: ids[2], version: "Someone Remix".to_string() },
+        ]
+    );
+
+    let work_id = grouping.group(suggestion).await.unwrap();
+    assert!(grouping.suggest().await.unwrap().is_empty());
+    let entry = database.get_entry_by_metadata_id(ids[1]).await.unwrap();
+    assert_eq!(entry.work_id, Some(work_id));
+    assert_eq!(entry.version.as_deref(), Some("Extended Mix"));
+    let mut siblings = entry.versions.0.iter().map(|version| version.metadata_id).collect::<Vec<_>>();
+    siblings.sort();
+    assert_eq!(siblings, vec![ids[0], ids[2]]);
+    assert!(database.get_entry_by_metadata_id(ids[3]).await.unwrap().versions.0.is_empty());
+
+    // The work is dissolved once a single version is left
+    database.delete_metadata_work(ids[0]).await.unwrap();
+    database.delete_metadata_work(ids[2]).await.unwrap();
+    assert_eq!(database.get_entry_by_metadata_id(ids[1]).await.unwrap().work_id, None);
+}
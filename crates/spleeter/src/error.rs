@@ -10,6 +10,8 @@ pub enum SpleeterError {
     MalformatedModelsIndex,
     #[error("Cannot process audio: {0}")]
     ProcessAudioError(&'static str),
+    #[error("Separation cancelled")]
+    Cancelled,
 }
 
 pub type SpleeterResult<T> = Result<T, SpleeterError>;
@@ -7,7 +7,7 @@
 //! If you get tensorflow >2 installed on the host machine you should be good to go but may have some troble using the crate with `tauri`
 
 use crate::prelude::*;
-use std::{borrow::Cow, path::PathBuf};
+use std::{borrow::Cow, ops::ControlFlow, path::PathBuf};
 use tensorflow::{Graph, SavedModelBundle, SessionOptions, SessionRunArgs, Tensor};
 
 mod error;
@@ -69,8 +69,18 @@ pub fn get_models_from_index(index_path: impl AsRef<Path>) -> SpleeterResult<Vec
         .collect())
 }
 
-#[instrument]
 pub fn split_pcm_audio(audio: &AudioData, model: &SpleeterModel) -> SpleeterResult<Vec<Stem<'static>>> {
+    split_pcm_audio_with_progress(audio, model, |_, _| ControlFlow::Continue(()))
+}
+
+/// `on_segment` is called with the number of processed segments and the segment count after each segment,
+/// the separation stops with `SpleeterError::Cancelled` when it breaks
+#[instrument(skip(on_segment))]
+pub fn split_pcm_audio_with_progress(
+    audio: &AudioData,
+    model: &SpleeterModel,
+    mut on_segment: impl FnMut(usize, usize) -> ControlFlow<()>,
+) -> SpleeterResult<Vec<Stem<'static>>> {
     let tensorflow_version = tensorflow::version().unwrap();
     info!(?tensorflow_version);
 
@@ -160,6 +170,9 @@ pub fn split_pcm_audio(audio: &AudioData, model: &SpleeterModel) -> SpleeterResu
             transformed_samples[i].extend_from_slice(&data.as_ref()[begin..begin + len]);
         }
         info!("{}/{} done...", i + 1, segment_count);
+        if on_segment(i + 1, segment_count).is_break() {
+            return Err(SpleeterError::Cancelled);
+        }
     }
     Ok(transformed_samples.into_iter().enumerate().map(|(i, s)| {
         let stem = Stem {
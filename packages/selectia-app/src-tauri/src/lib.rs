@@ -19,6 +19,38 @@ pub async fn run() {
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             import_folder,
+            get_import_rules,
+            set_import_rules,
+            get_library_roots,
+            rename_library_root,
+            preview_library_root_relocation,
+            relocate_library_root,
+            get_tagging_rules,
+            create_tagging_rule,
+            set_tagging_rule_enabled,
+            delete_tagging_rule,
+            preview_filename_tags,
+            apply_filename_tags,
+            import_rekordbox_xml,
+            export_rekordbox_xml,
+            import_traktor_nml,
+            export_traktor_nml,
+            import_playlist_file,
+            export_m3u8,
+            export_to_folder,
+            export_serato,
+            get_cover_path,
+            fingerprint_library,
+            find_duplicates,
+            verify_library,
+            get_verification_report,
+            run_doctor,
+            get_library_statistics,
+            merge_metadata,
+            split_file,
+            suggest_works,
+            group_versions,
+            ungroup_version,
             get_tag_names,
             get_tags_by_name,
             get_interactive_list_context_entries,
@@ -28,6 +60,21 @@ pub async fn run() {
             interactive_list_create_context,
             get_worker_queue_tasks,
             get_worker_queue_task,
+            get_failed_worker_tasks,
+            retry_worker_tasks,
+            cancel_worker_task,
+            get_worker_concurrency,
+            set_worker_concurrency,
+            get_worker_schedules,
+            set_worker_schedule_enabled,
+            get_task_history,
+            get_task_history_stats,
+            get_task_history_retention,
+            set_task_history_retention,
+            get_analysis_pipeline_settings,
+            set_analysis_pipeline_settings,
+            analyse_entries,
+            get_entry_analysis,
             create_audio_deck,
             get_audio_decks,
             load_audio_track_from_metadata,
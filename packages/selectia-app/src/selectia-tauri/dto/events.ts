@@ -17,10 +17,20 @@ export type EntryChangedEvent = { entry: EntryView, };
 
 export type EntryListChangedEvent = Record<string, never>;
 
-export type Events = { "type": "AudioDeckFileMetadataUpdated" } & AudioDeckFileMetadataUpdatedEvent | { "type": "AudioDeckFilePayloadUpdated" } & AudioDeckFilePayloadUpdatedEvent | { "type": "AudioDeckFileStatusUpdated" } & AudioDeckFileStatusUpdatedEvent | { "type": "AudioDeckCreated" } & AudioDeckCreatedEvent | { "type": "WorkerQueueTaskCreated" } & WorkerQueueTaskCreatedEvent | { "type": "WorkerQueueTaskUpdated" } & WorkerQueueTaskUpdatedEvent | { "type": "TagListChanged" } & TagListChangedEvent | { "type": "EntryChanged" } & EntryChangedEvent | { "type": "EntryListChanged" } & EntryListChangedEvent;
+export type Events = { "type": "AudioDeckFileMetadataUpdated" } & AudioDeckFileMetadataUpdatedEvent | { "type": "AudioDeckFilePayloadUpdated" } & AudioDeckFilePayloadUpdatedEvent | { "type": "AudioDeckFileStatusUpdated" } & AudioDeckFileStatusUpdatedEvent | { "type": "AudioDeckCreated" } & AudioDeckCreatedEvent | { "type": "WorkerQueueTaskCreated" } & WorkerQueueTaskCreatedEvent | { "type": "WorkerQueueTaskUpdated" } & WorkerQueueTaskUpdatedEvent | { "type": "WorkerQueueTaskProgress" } & WorkerQueueTaskProgressEvent | { "type": "TagListChanged" } & TagListChangedEvent | { "type": "EntryChanged" } & EntryChangedEvent | { "type": "EntryListChanged" } & EntryListChangedEvent;
 
 export type TagListChangedEvent = Record<string, never>;
 
 export type WorkerQueueTaskCreatedEvent = { task: WorkerQueueTask, };
 
+export type WorkerQueueTaskProgressEvent = { id: bigint, step: string, 
+/**
+ * From 0 to 1
+ */
+progress: number, 
+/**
+ * Estimated remaining time of the step in seconds
+ */
+eta: number | null, };
+
 export type WorkerQueueTaskUpdatedEvent = { id: bigint, task: WorkerQueueTask | null, };
@@ -1,10 +1,23 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
+/**
+ * Stages of the analysis run on the newly ingested tracks
+ */
+export type AnalysisPipelineSettings = { enabled: boolean, 
+/**
+ * Tags embedded in the file
+ */
+metadata: boolean, waveform: boolean, 
+/**
+ * Tempo and beat grid
+ */
+grid: boolean, key: boolean, loudness: boolean, stems: boolean, };
+
 export type AppError = { message: string, id: number, };
 
 export type ContextId = bigint;
 
-export type DeckFileMetadataSnapshot = { title: string, };
+export type DeckFileMetadataSnapshot = { title: string, cover_hash: string | null, };
 
 export type DeckFilePayloadSnapshot = { duration: number, sample_rate: number, channels_count: number, samples_count: number, preview: DeckFilePreview | null, };
 
@@ -16,15 +29,78 @@ export type DeckFileView = { metadata: DeckFileMetadataSnapshot | null, payload:
 
 export type DeckView = { file: DeckFileView | null, id: number, };
 
-export type EntryView = { metadata_id: bigint, metadata_hash: string, tags: Array<MetadataTagView>, };
+export type DoctorIssue = { kind: DoctorIssueKind, fixable: boolean, table: string, row_id: bigint, detail: string, };
+
+export type DoctorIssueKind = "MissingFile" | "MissingFileVariation" | "StuckTask" | "InvalidTask" | "BrokenReference" | "UnusedTag" | "EntryWithoutFile";
+
+export type DoctorReport = { issues: Array<DoctorIssue>, fixed: number, };
+
+export type DuplicateEntry = { metadata_id: bigint, similarity: number, };
+
+export type DuplicateGroup = { entries: Array<DuplicateEntry>, };
+
+/**
+ * Results of the analysis pipeline that are not tags nor beat grid
+ */
+export type EntryAnalysis = { metadata_id: bigint, 
+/**
+ * Peaks from 0 to 255, empty until analysed
+ */
+waveform: Array<number>, 
+/**
+ * Integrated loudness in dB relative to full scale
+ */
+loudness: number | null, 
+/**
+ * Highest sample in dB relative to full scale
+ */
+peak: number | null, grid_confidence: number | null, key_confidence: number | null, };
+
+export type EntryView = { metadata_id: bigint, metadata_hash: string, cover_hash: string | null, tags: Array<MetadataTagView>, work_id: bigint | null, version: string | null, versions: Array<VersionView>, };
+
+export type ExtractedTag = { name: string, value: string, };
+
+export type FailedTask = { id: bigint, 
+/**
+ * Kind of task, `Unknown` if the payload can't be read anymore
+ */
+name: string, attempts: bigint, max_attempts: bigint, last_error: string | null, };
 
 export type FileVariation = { id: bigint, path: string, title: string, stem: string | null, };
 
+export type FilenameTagPreview = { metadata_id: bigint, path: string, rule_id: bigint, tags: Array<ExtractedTag>, };
+
 export type FilterSelection = { directories: Array<string>, tags: { [key in number]?: Array<TagSelection> }, };
 
-export type MetadataTagView = { tag_id: bigint, metadata_tag_id: bigint, tag_name_id: bigint, tag_value: string, metadata_id: bigint, };
+export type FolderExportOptions = { target: string, layout: string, transcode: TranscodeFormat | null, playlist_name: string, };
+
+export type ImportReport = { tracks: Array<TrackReconciliation>, playlists: Array<PlaylistReconciliation>, };
 
-export type Models = { "DeckFileMetadataSnapshot": DeckFileMetadataSnapshot } | { "DeckFilePayloadSnapshot": DeckFilePayloadSnapshot } | { "DeckFileStatus": DeckFileStatus } | { "AppError": AppError } | { "ContextId": ContextId } | { "WorkerQueueTask": WorkerQueueTask } | { "TaskStatus": TaskStatus } | { "DeckView": DeckView } | { "DeckFileView": DeckFileView } | { "TagSelection": TagSelection } | { "FilterSelection": FilterSelection } | { "EntryView": EntryView } | { "MetadataTagView": MetadataTagView } | { "TagName": TagName } | { "TagView": TagView } | { "FileVariation": FileVariation };
+export type ImportRules = { extensions: Array<string>, ignore: Array<string>, skip_hidden: boolean, follow_symlinks: boolean, min_size: bigint | null, max_size: bigint | null, min_duration: number | null, max_duration: number | null, };
+
+export type LibraryRoot = { id: bigint, name: string, path: string, };
+
+export type LibraryStatistics = { tracks: bigint, total_duration: number, unknown_duration: bigint, analysed: bigint, unanalysed: bigint, genres: Array<ValueCount>, artists: Array<ValueCount>, labels: Array<ValueCount>, keys: Array<ValueCount>, tempo_histogram: Array<TempoBin>, added_per_week: Array<WeekCount>, };
+
+export type MetadataTagView = { tag_id: bigint, metadata_tag_id: bigint, tag_name_id: bigint, tag_value: string, metadata_id: bigint, 
+/**
+ * Origin of the tag when set automatically (e.g `filename_rule:1`)
+ */
+tag_source: string | null, };
+
+export type Models = { "DeckFileMetadataSnapshot": DeckFileMetadataSnapshot } | { "DeckFilePayloadSnapshot": DeckFilePayloadSnapshot } | { "DeckFileStatus": DeckFileStatus } | { "AppError": AppError } | { "ContextId": ContextId } | { "WorkerQueueTask": WorkerQueueTask } | { "TaskStatus": TaskStatus } | { "DeckView": DeckView } | { "DeckFileView": DeckFileView } | { "TagSelection": TagSelection } | { "FilterSelection": FilterSelection } | { "EntryView": EntryView } | { "MetadataTagView": MetadataTagView } | { "TagName": TagName } | { "TagView": TagView } | { "FileVariation": FileVariation } | { "ImportRules": ImportRules } | { "TaggingRule": TaggingRule } | { "ExtractedTag": ExtractedTag } | { "FilenameTagPreview": FilenameTagPreview } | { "ImportReport": ImportReport } | { "ReconciliationStatus": ReconciliationStatus } | { "TrackReconciliation": TrackReconciliation } | { "TagConflict": TagConflict } | { "PlaylistReconciliation": PlaylistReconciliation } | { "TranscodeFormat": TranscodeFormat } | { "FolderExportOptions": FolderExportOptions } | { "SeratoExportReport": SeratoExportReport } | { "SeratoFileReport": SeratoFileReport } | { "DuplicateGroup": DuplicateGroup } | { "DuplicateEntry": DuplicateEntry } | { "VerifiedFileStatus": VerifiedFileStatus } | { "VerifiedFile": VerifiedFile } | { "LibraryRoot": LibraryRoot } | { "RelocationPlan": RelocationPlan } | { "DoctorIssueKind": DoctorIssueKind } | { "DoctorIssue": DoctorIssue } | { "DoctorReport": DoctorReport } | { "ValueCount": ValueCount } | { "TempoBin": TempoBin } | { "WeekCount": WeekCount } | { "LibraryStatistics": LibraryStatistics } | { "VersionView": VersionView } | { "WorkVersion": WorkVersion } | { "WorkSuggestion": WorkSuggestion } | { "FailedTask": FailedTask } | { "WorkerConcurrency": WorkerConcurrency } | { "AnalysisPipelineSettings": AnalysisPipelineSettings } | { "EntryAnalysis": EntryAnalysis } | { "WorkerSchedule": WorkerSchedule } | { "TaskHistoryRun": TaskHistoryRun } | { "TaskHistoryQuery": TaskHistoryQuery } | { "TaskHistoryStats": TaskHistoryStats } | { "TaskHistoryRetention": TaskHistoryRetention };
+
+export type PlaylistReconciliation = { path: Array<string>, playlist_id: bigint, entries: number, missing_entries: number, };
+
+export type ReconciliationStatus = "Matched" | "MatchedByHash" | "Ingested" | "Missing" | "Failed";
+
+export type RelocationPlan = { root_id: bigint, from: string, to: string, files: number, file_variations: number, missing: Array<string>, };
+
+export type SeratoExportReport = { crates: Array<string>, files: Array<SeratoFileReport>, };
+
+export type SeratoFileReport = { metadata_id: bigint, path: string, error: string | null, };
+
+export type TagConflict = { name: string, existing: Array<string>, imported: string, };
 
 export type TagName = { id: bigint, name: string, use_for_filtering: boolean, };
 
@@ -32,6 +108,102 @@ export type TagSelection = { id: bigint, value: string, selected: boolean, };
 
 export type TagView = { id: bigint, value: string, name_id: bigint, };
 
-export type TaskStatus = "Queued" | "Processing" | "Done";
+export type TaggingRule = { id: bigint, template: string, enabled: boolean, position: bigint, };
+
+/**
+ * Filters of the task history, `limit` defaults to 100
+ */
+export type TaskHistoryQuery = { name: string | null, status: TaskStatus | null, 
+/**
+ * Unix timestamp, runs finished before are left out
+ */
+since: bigint | null, limit: bigint | null, offset: bigint | null, };
+
+/**
+ * Runs kept in the task history, the oldest are deleted once either limit is reached
+ */
+export type TaskHistoryRetention = { max_age_days: bigint, max_runs: bigint, };
+
+/**
+ * Run of a task that left the worker pool
+ */
+export type TaskHistoryRun = { id: bigint, task_id: bigint, name: string, 
+/**
+ * Done, failed or cancelled, a failed run may have been retried
+ */
+status: TaskStatus, resource_class: string, 
+/**
+ * 1 for the first run of the task
+ */
+attempt: bigint, worker_slot: bigint, 
+/**
+ * Unix timestamps
+ */
+started_at: bigint, finished_at: bigint, duration_ms: bigint, 
+/**
+ * Error chain of a failed run
+ */
+error: string | null, 
+/**
+ * JSON outcome of the task, i.e the counts of a library verification
+ */
+summary: string | null, };
+
+/**
+ * Runs of a kind of task, durations are of the successful runs
+ */
+export type TaskHistoryStats = { name: string, runs: bigint, failed: bigint, cancelled: bigint, average_duration_ms: number | null, max_duration_ms: bigint | null, };
+
+export type TaskStatus = "Queued" | "Processing" | "Done" | "Failed" | "Cancelled";
+
+export type TempoBin = { start: number, count: bigint, };
+
+export type TrackReconciliation = { source_id: string, path: string, metadata_id: bigint | null, status: ReconciliationStatus, updated: Array<string>, conflicts: Array<TagConflict>, cues: number, beat_grid_markers: number, error: string | null, };
+
+export type TranscodeFormat = "Wav" | "Flac";
+
+export type ValueCount = { value: string, count: bigint, };
+
+export type VerifiedFile = { file_id: bigint, metadata_id: bigint, path: string, status: VerifiedFileStatus, error: string | null, };
+
+export type VerifiedFileStatus = "Unchanged" | "Changed" | "Missing" | "Failed";
+
+export type VersionView = { metadata_id: bigint, version: string, };
+
+export type WeekCount = { week: bigint, count: bigint, };
+
+export type WorkSuggestion = { work_id: bigint | null, artist: string, title: string, versions: Array<WorkVersion>, };
+
+export type WorkVersion = { metadata_id: bigint, version: string, };
+
+/**
+ * Size of the worker pool and tasks of each resource class allowed to run at the same time within it
+ */
+export type WorkerConcurrency = { pool_size: number, 
+/**
+ * Stem separation
+ */
+separation: number, 
+/**
+ * Exports and library verification
+ */
+io: number, 
+/**
+ * Light analysis like fingerprinting
+ */
+analysis: number, };
 
 export type WorkerQueueTask = { id: bigint, status: TaskStatus, };
+
+/**
+ * Task enqueued periodically by the worker, either on a cron expression or every `interval_seconds`
+ */
+export type WorkerSchedule = { id: bigint, name: string, 
+/**
+ * Kind of task, `Unknown` if the payload can't be read anymore
+ */
+task: string, cron: string | null, interval_seconds: bigint | null, enabled: boolean, 
+/**
+ * Unix timestamps
+ */
+last_run_at: bigint | null, next_run_at: bigint, };